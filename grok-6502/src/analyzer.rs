@@ -0,0 +1,728 @@
+//! Static control-flow analysis of 6502 binaries.
+//!
+//! Starting from a set of entry points (reset/IRQ vectors, slot ROM entry points, etc),
+//! code is traced recursively by following every branch, jump and subroutine call
+//! the opcode table tells us about. Anything never reached is assumed to be data.
+//!
+//! This is purely static, so anything computed at runtime (self-modifying code,
+//! pointers built in zero page, etc) won't be discovered. Known jump tables can be
+//! described manually to help the analysis along.
+
+use crate::opcodes::{OPCODES, OPCODES_65C02, Opcode};
+use crate::{AddrMode, Instruction, Variant};
+use std::collections::BTreeMap;
+use std::fmt;
+
+// Number of data bytes printed per line in a listing
+const BYTES_PER_LINE: usize = 8;
+
+// Split lo/hi tables further apart than this are unlikely to belong together
+const MAX_SPLIT_TABLE_LEN: u16 = 64;
+
+// Opcodes the analyzer needs to recognize, since many of them are dispatched as implied
+// (handling their own cycles), which hides their operands and control flow from the opcode table
+mod op {
+    pub const BRK: u8 = 0x00;
+    pub const JSR: u8 = 0x20;
+    pub const RTI: u8 = 0x40;
+    pub const PHA: u8 = 0x48;
+    pub const JMP_ABS: u8 = 0x4C;
+    pub const RTS: u8 = 0x60;
+    pub const JMP_IND: u8 = 0x6C;
+    pub const LDA_ABS_Y: u8 = 0xB9;
+    pub const LDA_ABS_X: u8 = 0xBD;
+
+    // NMOS only
+    pub const JAMS: [u8; 12] = [
+        0x02, 0x12, 0x22, 0x32, 0x42, 0x52, 0x62, 0x72, 0x92, 0xB2, 0xD2, 0xF2,
+    ];
+
+    // 65C02 only
+    pub const NOP_ABS: u8 = 0x5C;
+    pub const JMP_IND_X: u8 = 0x7C;
+    pub const BRA: u8 = 0x80;
+}
+
+// How an instruction passes control on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    Next,
+    Branch,
+    Jump,
+    Call,
+    IndirectJump,
+    Return,
+    Stop,
+}
+
+/// What kind of location a label marks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LabelKind {
+    /// An entry point given to the analyzer.
+    Entry,
+    /// The target of a `JSR`.
+    Subroutine,
+    /// The target of a branch or jump.
+    Branch,
+    /// The start of a jump table.
+    JumpTable,
+}
+
+/// A named location discovered during analysis.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    /// The name used for this location in the listing.
+    pub name: String,
+    /// Why this location was labelled.
+    pub kind: LabelKind,
+}
+
+/// How a jump table stores its targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Consecutive little-endian words, as used with `JMP ($nnnn)`.
+    Words,
+    /// Consecutive little-endian words holding `target - 1`, as used with the `RTS` trick.
+    RtsWords,
+    /// Separate low and high byte tables holding `target - 1`, as used with the `RTS` trick.
+    ///
+    /// The value is the address of the high byte table.
+    RtsSplit(u16),
+}
+
+/// A table of code pointers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JumpTable {
+    /// Address of the table (the low byte table for [`TableFormat::RtsSplit`]).
+    pub addr: u16,
+    /// Number of entries in the table.
+    pub len: u16,
+    /// How the entries are stored.
+    pub format: TableFormat,
+}
+
+impl JumpTable {
+    fn entries(&self, analysis: &Analysis) -> Vec<u16> {
+        (0..self.len)
+            .filter_map(|i| match self.format {
+                TableFormat::Words => analysis.word(self.addr.wrapping_add(i * 2)),
+                TableFormat::RtsWords => analysis
+                    .word(self.addr.wrapping_add(i * 2))
+                    .map(|w| w.wrapping_add(1)),
+                TableFormat::RtsSplit(hi) => {
+                    let lo = analysis.byte(self.addr.wrapping_add(i))?;
+                    let hi = analysis.byte(hi.wrapping_add(i))?;
+                    Some(u16::from_le_bytes([lo, hi]).wrapping_add(1))
+                }
+            })
+            .collect()
+    }
+}
+
+/// A single decoded instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Instr {
+    /// Address of the opcode.
+    pub addr: u16,
+    /// The opcode byte.
+    pub opcode: u8,
+    /// Operand bytes (only the first `len - 1` are meaningful).
+    pub operand: [u8; 2],
+    /// Total length of the instruction in bytes.
+    pub len: u8,
+    /// The CPU the instruction was decoded for.
+    pub variant: Variant,
+}
+
+impl Instr {
+    fn info(&self) -> &'static Opcode {
+        &opcodes(self.variant)[self.opcode as usize]
+    }
+
+    fn flow(&self) -> Flow {
+        match (self.variant, self.opcode) {
+            (_, op::JSR) => Flow::Call,
+            (_, op::RTS | op::RTI) => Flow::Return,
+            (_, op::BRK) => Flow::Stop,
+            (_, op::JMP_ABS) | (Variant::Cmos, op::BRA) => Flow::Jump,
+            (_, op::JMP_IND) | (Variant::Cmos, op::JMP_IND_X) => Flow::IndirectJump,
+            (Variant::Nmos, opcode) if op::JAMS.contains(&opcode) => Flow::Stop,
+            _ if matches!(self.info().instr, Instruction::Branch(_)) => Flow::Branch,
+            _ => Flow::Next,
+        }
+    }
+
+    /// Returns the instruction mnemonic.
+    pub fn mnemonic(&self) -> &'static str {
+        self.info().name
+    }
+
+    /// Returns the address of the next instruction in memory.
+    pub fn next(&self) -> u16 {
+        self.addr.wrapping_add(self.len as u16)
+    }
+
+    fn operand_word(&self) -> u16 {
+        u16::from_le_bytes(self.operand)
+    }
+
+    /// Returns the address this instruction transfers control to, if it is known statically.
+    pub fn target(&self) -> Option<u16> {
+        match self.flow() {
+            Flow::Branch | Flow::Jump if matches!(self.info().mode, AddrMode::Rel0) => {
+                Some((self.next() as i32 + (self.operand[0] as i8) as i32) as u16)
+            }
+            Flow::Jump | Flow::Call => Some(self.operand_word()),
+            _ => None,
+        }
+    }
+
+    /// Returns the memory address referenced by the operand (ignoring any indexing).
+    ///
+    /// Returns `None` for implied, accumulator and immediate instructions.
+    pub fn operand_addr(&self) -> Option<u16> {
+        match self.info().mode {
+            AddrMode::Acm0 | AddrMode::Imm0 => None,
+            AddrMode::Imp0 if self.len == 3 => Some(self.operand_word()),
+            AddrMode::Imp0 | AddrMode::Imp1 => None,
+            AddrMode::Rel0 => self.target(),
+            AddrMode::Abs0 | AddrMode::AbsX | AddrMode::AbsY | AddrMode::Ind0 => {
+                Some(self.operand_word())
+            }
+            AddrMode::IndX
+            | AddrMode::IndY
+            | AddrMode::Zpg0
+            | AddrMode::ZpgI
+            | AddrMode::ZpgX
+            | AddrMode::ZpgY => Some(self.operand[0] as u16),
+        }
+    }
+
+    // Whether execution can continue to the next instruction in memory
+    fn falls_through(&self) -> bool {
+        matches!(self.flow(), Flow::Next | Flow::Branch | Flow::Call)
+    }
+
+    fn format(&self, analysis: &Analysis) -> String {
+        let info = self.info();
+        let name = |addr: u16| analysis.label_or_addr(addr);
+        let operand = match (self.variant, self.opcode) {
+            (Variant::Cmos, op::JMP_IND) => format!("({})", name(self.operand_word())),
+            (Variant::Cmos, op::JMP_IND_X) => format!("({},X)", name(self.operand_word())),
+            _ => match info.mode {
+                AddrMode::Acm0 => "A".into(),
+                AddrMode::Imp0 if self.len == 3 => name(self.operand_word()),
+                AddrMode::Imp0 | AddrMode::Imp1 => String::new(),
+                AddrMode::Imm0 => format!("#${:02X}", self.operand[0]),
+                AddrMode::Abs0 => name(self.operand_word()),
+                AddrMode::AbsX => format!("{},X", name(self.operand_word())),
+                AddrMode::AbsY => format!("{},Y", name(self.operand_word())),
+                AddrMode::Ind0 => format!("({})", name(self.operand_word())),
+                AddrMode::IndX => format!("(${:02X},X)", self.operand[0]),
+                AddrMode::IndY => format!("(${:02X}),Y", self.operand[0]),
+                AddrMode::Rel0 => name(self.target().unwrap()),
+                AddrMode::Zpg0 => format!("${:02X}", self.operand[0]),
+                AddrMode::ZpgI => format!("(${:02X})", self.operand[0]),
+                AddrMode::ZpgX => format!("${:02X},X", self.operand[0]),
+                AddrMode::ZpgY => format!("${:02X},Y", self.operand[0]),
+            },
+        };
+
+        if operand.is_empty() {
+            info.name.into()
+        } else {
+            format!("{} {}", info.name, operand)
+        }
+    }
+}
+
+fn opcodes(variant: Variant) -> &'static [Opcode; 0x100] {
+    match variant {
+        Variant::Nmos => &OPCODES,
+        Variant::Cmos => &OPCODES_65C02,
+    }
+}
+
+// Operand length (in bytes) of each addressing mode
+fn operand_len(variant: Variant, opcode: u8) -> u8 {
+    match (variant, opcode) {
+        // These are technically absolute but are dispatched as implied
+        (_, op::JSR) | (Variant::Cmos, op::JMP_IND | op::JMP_IND_X | op::NOP_ABS) => return 2,
+        _ => (),
+    }
+
+    match opcodes(variant)[opcode as usize].mode {
+        AddrMode::Acm0 | AddrMode::Imp0 | AddrMode::Imp1 => 0,
+        AddrMode::Imm0
+        | AddrMode::IndX
+        | AddrMode::IndY
+        | AddrMode::Rel0
+        | AddrMode::Zpg0
+        | AddrMode::ZpgI
+        | AddrMode::ZpgX
+        | AddrMode::ZpgY => 1,
+        AddrMode::Abs0 | AddrMode::AbsX | AddrMode::AbsY | AddrMode::Ind0 => 2,
+    }
+}
+
+/// Decodes the instruction at `addr` for the given CPU variant using the given memory reader.
+///
+/// Returns `None` if any byte of the instruction could not be read.
+pub fn decode(addr: u16, variant: Variant, read: impl Fn(u16) -> Option<u8>) -> Option<Instr> {
+    let opcode = read(addr)?;
+    let len = operand_len(variant, opcode);
+    let mut operand = [0; 2];
+    for (i, byte) in operand.iter_mut().enumerate().take(len as usize) {
+        *byte = read(addr.wrapping_add(i as u16 + 1))?;
+    }
+
+    Some(Instr {
+        addr,
+        opcode,
+        operand,
+        len: len + 1,
+        variant,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mark {
+    Unknown,
+    Opcode,
+    Operand,
+    Pointer,
+}
+
+/// Recursive-descent code tracer for a binary image loaded at a fixed address.
+///
+/// # Example
+/// ```
+/// use grok_6502::analyzer::Analyzer;
+///
+/// // LDX #$00 / INX / BNE *-1 / RTS
+/// let rom = [0xA2, 0x00, 0xE8, 0xD0, 0xFD, 0x60];
+/// let analysis = Analyzer::new(&rom, 0xC600).entry(0xC600, "BOOT").analyze();
+/// assert_eq!(analysis.instructions().count(), 4);
+/// ```
+pub struct Analyzer<'a> {
+    image: &'a [u8],
+    base: u16,
+    variant: Variant,
+    entries: Vec<(u16, String)>,
+    tables: Vec<JumpTable>,
+}
+
+impl<'a> Analyzer<'a> {
+    /// Create a new analyzer for the given image, which is loaded starting at address `base`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the image runs past the end of the 64K address space (`$FFFF`).
+    pub fn new(image: &'a [u8], base: u16) -> Self {
+        assert!(
+            image.len() <= 0x10000 - base as usize,
+            "Image must fit in the 64K address space"
+        );
+
+        Self {
+            image,
+            base,
+            variant: Variant::Nmos,
+            entries: Vec::new(),
+            tables: Vec::new(),
+        }
+    }
+
+    /// Decode for the given CPU variant instead of the NMOS 6502.
+    pub fn variant(mut self, variant: Variant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Add an entry point at the given address.
+    pub fn entry(mut self, addr: u16, name: &str) -> Self {
+        self.entries.push((addr, name.into()));
+        self
+    }
+
+    /// Add an entry point read from a little-endian vector stored in the image,
+    /// such as the reset vector at $FFFC.
+    ///
+    /// Vectors outside the image are ignored.
+    pub fn vector(self, addr: u16, name: &str) -> Self {
+        let read = |a: u16| self.read(a);
+        match read(addr).zip(read(addr.wrapping_add(1))) {
+            Some((lo, hi)) => self.entry(u16::from_le_bytes([lo, hi]), name),
+            None => self,
+        }
+    }
+
+    /// Add the standard 6502 NMI, reset and IRQ/BRK vectors as entry points.
+    pub fn vectors(self) -> Self {
+        self.vector(0xFFFA, "NMI")
+            .vector(0xFFFC, "RESET")
+            .vector(0xFFFE, "IRQ")
+    }
+
+    /// Describe a jump table the analyzer cannot find on its own.
+    pub fn jump_table(mut self, table: JumpTable) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    fn read(&self, addr: u16) -> Option<u8> {
+        let offset = addr.wrapping_sub(self.base) as usize;
+        self.image.get(offset).copied()
+    }
+
+    /// Trace all code reachable from the entry points.
+    pub fn analyze(&self) -> Analysis {
+        let mut analysis = Analysis {
+            image: self.image.to_vec(),
+            base: self.base,
+            variant: self.variant,
+            marks: vec![Mark::Unknown; self.image.len()],
+            labels: BTreeMap::new(),
+            tables: Vec::new(),
+        };
+
+        let mut pending: Vec<u16> = Vec::new();
+        for (addr, name) in &self.entries {
+            analysis.add_label(*addr, LabelKind::Entry, Some(name));
+            pending.push(*addr);
+        }
+        for table in &self.tables {
+            analysis.add_table(*table, &mut pending);
+        }
+
+        while let Some(addr) = pending.pop() {
+            self.trace(addr, &mut analysis, &mut pending);
+        }
+
+        analysis
+    }
+
+    // Linearly decode from `addr` until control flow stops, queueing any other targets found
+    fn trace(&self, mut addr: u16, analysis: &mut Analysis, pending: &mut Vec<u16>) {
+        // Recently decoded instructions, used to recognize jump table idioms
+        let mut history: Vec<Instr> = Vec::new();
+
+        loop {
+            match analysis.mark(addr) {
+                // Outside the image or already traced
+                None | Some(Mark::Opcode) => return,
+                // Jumping into the middle of something we already decoded is either
+                // an overlapping instruction trick or (more likely) a bad guess, so stop
+                Some(Mark::Operand) | Some(Mark::Pointer) => return,
+                Some(Mark::Unknown) => (),
+            }
+
+            let Some(instr) = decode(addr, self.variant, |a| self.read(a)) else {
+                return;
+            };
+            let fits = (1..instr.len as u16)
+                .all(|i| analysis.mark(addr.wrapping_add(i)) == Some(Mark::Unknown));
+            if !fits {
+                return;
+            }
+
+            analysis.set_mark(addr, Mark::Opcode);
+            for i in 1..instr.len as u16 {
+                analysis.set_mark(addr.wrapping_add(i), Mark::Operand);
+            }
+
+            let flow = instr.flow();
+            if let Some(target) = instr.target() {
+                let kind = if flow == Flow::Call {
+                    LabelKind::Subroutine
+                } else {
+                    LabelKind::Branch
+                };
+                analysis.add_label(target, kind, None);
+                pending.push(target);
+            }
+
+            // An indirect jump through a pointer inside the image is the start of a jump table.
+            // We can only be sure about the first entry though.
+            if flow == Flow::IndirectJump {
+                let table = JumpTable {
+                    addr: instr.operand_word(),
+                    len: 1,
+                    format: TableFormat::Words,
+                };
+                if analysis.word(table.addr).is_some() {
+                    analysis.add_table(table, pending);
+                }
+            }
+
+            history.push(instr);
+            if instr.opcode == op::RTS
+                && let Some(table) = Self::find_rts_table(&history)
+            {
+                analysis.add_table(table, pending);
+            }
+
+            if !instr.falls_through() {
+                return;
+            }
+            addr = instr.next();
+        }
+    }
+
+    // Recognizes the common split table dispatch idiom:
+    //
+    // LDA hi,X / PHA / LDA lo,X / PHA / RTS
+    fn find_rts_table(history: &[Instr]) -> Option<JumpTable> {
+        let [lda_hi, pha1, lda_lo, pha2, _rts] = history.last_chunk::<5>()?;
+
+        let is_indexed_lda = |i: &Instr| {
+            matches!(i.opcode, op::LDA_ABS_X | op::LDA_ABS_Y) && i.operand_addr().is_some()
+        };
+        if !is_indexed_lda(lda_hi) || !is_indexed_lda(lda_lo) || lda_hi.opcode != lda_lo.opcode {
+            return None;
+        }
+        if pha1.opcode != op::PHA || pha2.opcode != op::PHA {
+            return None;
+        }
+
+        // Without knowing the index range we can only guess the table length when
+        // the two halves sit back to back (which they almost always do).
+        // The guess gets cut short if it runs into code (see `Analysis::add_table`).
+        let lo = lda_lo.operand_addr()?;
+        let hi = lda_hi.operand_addr()?;
+        let len = lo.abs_diff(hi);
+        if len == 0 || len > MAX_SPLIT_TABLE_LEN {
+            return None;
+        }
+
+        Some(JumpTable {
+            addr: lo,
+            len,
+            format: TableFormat::RtsSplit(hi),
+        })
+    }
+}
+
+/// The result of tracing a binary image.
+pub struct Analysis {
+    image: Vec<u8>,
+    base: u16,
+    variant: Variant,
+    marks: Vec<Mark>,
+    labels: BTreeMap<u16, Label>,
+    tables: Vec<JumpTable>,
+}
+
+impl Analysis {
+    fn offset(&self, addr: u16) -> Option<usize> {
+        let offset = addr.wrapping_sub(self.base) as usize;
+        (offset < self.image.len()).then_some(offset)
+    }
+
+    fn byte(&self, addr: u16) -> Option<u8> {
+        self.offset(addr).map(|o| self.image[o])
+    }
+
+    fn word(&self, addr: u16) -> Option<u16> {
+        let lo = self.byte(addr)?;
+        let hi = self.byte(addr.wrapping_add(1))?;
+        Some(u16::from_le_bytes([lo, hi]))
+    }
+
+    fn mark(&self, addr: u16) -> Option<Mark> {
+        self.offset(addr).map(|o| self.marks[o])
+    }
+
+    fn set_mark(&mut self, addr: u16, mark: Mark) {
+        if let Some(offset) = self.offset(addr) {
+            self.marks[offset] = mark;
+        }
+    }
+
+    fn add_label(&mut self, addr: u16, kind: LabelKind, name: Option<&str>) {
+        let prefix = match kind {
+            LabelKind::Entry => "E",
+            LabelKind::Subroutine => "S",
+            LabelKind::Branch => "L",
+            LabelKind::JumpTable => "T",
+        };
+
+        // A location can be reached in several ways, so keep whichever kind is most notable
+        let label = Label {
+            name: name.map_or_else(|| format!("{prefix}_{addr:04X}"), Into::into),
+            kind,
+        };
+        match self.labels.get(&addr) {
+            Some(existing) if existing.kind <= kind => (),
+            _ => {
+                self.labels.insert(addr, label);
+            }
+        }
+    }
+
+    fn add_table(&mut self, mut table: JumpTable, pending: &mut Vec<u16>) {
+        let (width, halves) = match table.format {
+            TableFormat::Words | TableFormat::RtsWords => (2, vec![table.addr]),
+            TableFormat::RtsSplit(hi) => (1, vec![table.addr, hi]),
+        };
+
+        // Code that has already been traced wins over a (possibly guessed) table length,
+        // so the table ends at the first entry overlapping it
+        let entry_is_data = |i: u16| {
+            halves
+                .iter()
+                .all(|start| (0..width).all(|b| !self.is_code(start.wrapping_add(i * width + b))))
+        };
+        table.len = (0..table.len).take_while(|&i| entry_is_data(i)).count() as u16;
+        if table.len == 0 || self.tables.contains(&table) {
+            return;
+        }
+
+        for start in halves {
+            self.add_label(start, LabelKind::JumpTable, None);
+            for i in 0..table.len * width {
+                let addr = start.wrapping_add(i);
+                if self.mark(addr) == Some(Mark::Unknown) {
+                    self.set_mark(addr, Mark::Pointer);
+                }
+            }
+        }
+
+        for target in table.entries(self) {
+            self.add_label(target, LabelKind::Branch, None);
+            pending.push(target);
+        }
+        self.tables.push(table);
+    }
+
+    /// Returns the address the image is loaded at.
+    pub fn base(&self) -> u16 {
+        self.base
+    }
+
+    /// Returns whether the byte at the given address was traced as code.
+    pub fn is_code(&self, addr: u16) -> bool {
+        matches!(self.mark(addr), Some(Mark::Opcode | Mark::Operand))
+    }
+
+    /// Returns all labelled locations, including ones outside the image.
+    pub fn labels(&self) -> &BTreeMap<u16, Label> {
+        &self.labels
+    }
+
+    /// Returns the address of every discovered subroutine.
+    pub fn subroutines(&self) -> impl Iterator<Item = u16> + '_ {
+        self.labels
+            .iter()
+            .filter(|(_, label)| label.kind == LabelKind::Subroutine)
+            .map(|(addr, _)| *addr)
+    }
+
+    /// Returns every discovered jump table.
+    pub fn jump_tables(&self) -> &[JumpTable] {
+        &self.tables
+    }
+
+    /// Returns every traced instruction in address order.
+    pub fn instructions(&self) -> impl Iterator<Item = Instr> + '_ {
+        self.marks
+            .iter()
+            .enumerate()
+            .filter(|(_, mark)| **mark == Mark::Opcode)
+            .filter_map(|(offset, _)| {
+                let addr = self.base.wrapping_add(offset as u16);
+                decode(addr, self.variant, |a| self.byte(a))
+            })
+    }
+
+    fn label_or_addr(&self, addr: u16) -> String {
+        match self.labels.get(&addr) {
+            Some(label) => label.name.clone(),
+            None if addr < 0x100 => format!("${addr:02X}"),
+            None => format!("${addr:04X}"),
+        }
+    }
+
+    fn write_line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        addr: u16,
+        bytes: &[u8],
+        text: &str,
+    ) -> fmt::Result {
+        let hex: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+        let label = self.labels.get(&addr).map_or("", |l| l.name.as_str());
+
+        // Long data lines don't leave room for the bytes column, which is fine
+        let hex = hex.join(" ");
+        writeln!(f, "{addr:04X}: {hex:<8}  {label:<10} {text}")
+    }
+}
+
+/// Writes a labelled disassembly listing of the whole image.
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // External references (ROM routines, I/O locations, etc) get equates up front
+        for (addr, label) in &self.labels {
+            if self.offset(*addr).is_none() {
+                writeln!(f, "{:<10} EQU ${addr:04X}", label.name)?;
+            }
+        }
+
+        let mut offset = 0;
+        while offset < self.image.len() {
+            let addr = self.base.wrapping_add(offset as u16);
+
+            match self.marks[offset] {
+                Mark::Opcode => {
+                    let instr = decode(addr, self.variant, |a| self.byte(a)).unwrap();
+                    let len = instr.len as usize;
+                    let text = instr.format(self);
+                    self.write_line(f, addr, &self.image[offset..offset + len], &text)?;
+                    offset += len;
+                }
+                Mark::Pointer => {
+                    let split = self.tables.iter().any(|t| {
+                        matches!(t.format, TableFormat::RtsSplit(_))
+                            && addr.wrapping_sub(t.addr) < t.len * 2
+                    });
+                    let is_word = !split
+                        && self.marks.get(offset + 1) == Some(&Mark::Pointer)
+                        && !self.labels.contains_key(&addr.wrapping_add(1));
+
+                    if is_word {
+                        let word = self.word(addr).unwrap();
+                        let text = format!(".WORD {}", self.label_or_addr(word));
+                        self.write_line(f, addr, &self.image[offset..offset + 2], &text)?;
+                        offset += 2;
+                    } else {
+                        let byte = self.image[offset];
+                        self.write_line(f, addr, &[byte], &format!(".BYTE ${byte:02X}"))?;
+                        offset += 1;
+                    }
+                }
+                Mark::Unknown | Mark::Operand => {
+                    // Group up runs of data, breaking at labels so they stay visible
+                    let mut end = offset + 1;
+                    while end < self.image.len()
+                        && end - offset < BYTES_PER_LINE
+                        && matches!(self.marks[end], Mark::Unknown)
+                        && !self
+                            .labels
+                            .contains_key(&self.base.wrapping_add(end as u16))
+                    {
+                        end += 1;
+                    }
+
+                    let bytes = &self.image[offset..end];
+                    let text: Vec<String> = bytes.iter().map(|b| format!("${b:02X}")).collect();
+                    let text = format!(".BYTE {}", text.join(","));
+                    self.write_line(f, addr, &bytes[..bytes.len().min(3)], &text)?;
+                    offset = end;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -7,6 +7,10 @@
 //! This is technailly more than just the bus, as it includes other pins/signals
 //! from the 6502 cpu, but `Bus` still seems like best overall name.
 
+pub mod checked;
+
+use crate::state::{StateReader, StateWriter};
+
 /// The operation currently being performed on the bus.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Op {
@@ -100,6 +104,11 @@ pub trait Bus {
     /// Typically used to update edge state of pins.
     fn tick(&mut self);
 
+    /// Identify the device that is about to access the bus.
+    ///
+    /// This is purely a debugging aid (see [`checked::CheckedBus`]), so does nothing by default.
+    fn set_device(&mut self, _device: &'static str) {}
+
     /// Helper method to start a read operation on the bus.
     fn start_read(&mut self, addr: u16) {
         self.set_addr(addr);
@@ -140,6 +149,28 @@ impl Pin {
         };
         self.prev_level = self.level;
     }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.prev_level);
+        state.bool(self.level);
+        state.u8(match self.edge {
+            None => 0,
+            Some(false) => 1,
+            Some(true) => 2,
+        });
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.prev_level = state.bool()?;
+        self.level = state.bool()?;
+        self.edge = match state.u8()? {
+            0 => None,
+            1 => Some(false),
+            2 => Some(true),
+            _ => return Err("Save state has an invalid pin edge"),
+        };
+        Ok(())
+    }
 }
 
 /// A simple implementation of the `Bus` trait that just stores the current state of the bus in fields.
@@ -177,6 +208,36 @@ impl SimpleBus {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Save the current state of the bus lines and pins.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u16(self.addr);
+        state.u8(self.data);
+        state.bool(self.op == Op::Read);
+        for pin in [
+            &self.sync, &self.res, &self.irq, &self.nmi, &self.so, &self.rdy,
+        ] {
+            pin.save_state(state);
+        }
+    }
+
+    /// Restore state previously saved by [`SimpleBus::save_state`].
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.addr = state.u16()?;
+        self.data = state.u8()?;
+        self.op = if state.bool()? { Op::Read } else { Op::Write };
+        for pin in [
+            &mut self.sync,
+            &mut self.res,
+            &mut self.irq,
+            &mut self.nmi,
+            &mut self.so,
+            &mut self.rdy,
+        ] {
+            pin.load_state(state)?;
+        }
+        Ok(())
+    }
 }
 
 impl Bus for SimpleBus {
@@ -0,0 +1,285 @@
+//! A bus wrapper for catching misbehaving hardware.
+//!
+//! [`SimpleBus`] happily lets the last device to call `set_data` win, which is convenient
+//! but hides real bugs such as two devices driving the data lines at the same time.
+//! [`CheckedBus`] keeps track of which device drove the data lines during each phase
+//! and reports anything suspicious through a callback.
+//!
+//! Devices identify themselves with [`Bus::set_device`] before touching the bus.
+//! A new phase begins whenever an address is put on the bus.
+
+use super::{Bus, Op, SimpleBus};
+use core::ops::RangeInclusive;
+
+/// Name used to identify a device on the bus.
+pub type Device = &'static str;
+
+/// Suspicious bus activity detected by a [`CheckedBus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// More than one device drove the data lines during the same phase.
+    Contention {
+        /// Address on the bus at the time.
+        addr: u16,
+        /// Operation on the bus at the time.
+        op: Op,
+        /// The device that drove the data lines first, and the value it drove.
+        first: (Device, u8),
+        /// The device that also drove the data lines, and the value it drove.
+        second: (Device, u8),
+    },
+    /// A read completed without any device driving the data lines.
+    Floating {
+        /// Address that was read.
+        addr: u16,
+        /// The (stale) value left on the data lines.
+        data: u8,
+    },
+    /// A write to a read-only region was not accepted by any device.
+    ReadOnlyWrite {
+        /// Address that was written.
+        addr: u16,
+        /// Value that was written.
+        data: u8,
+        /// The device performing the write.
+        device: Device,
+    },
+}
+
+// At most this many distinct drivers are tracked per phase
+// (any more than two is already a fault, so this is plenty)
+const MAX_DRIVERS: usize = 4;
+
+/// A [`Bus`] that checks for contention, floating reads and writes to read-only regions.
+///
+/// Checking is only performed while a fault handler is installed,
+/// otherwise this behaves exactly like the wrapped bus.
+pub struct CheckedBus<B: Bus = SimpleBus> {
+    inner: B,
+    device: Device,
+    writer: Device,
+    drivers: [Option<(Device, u8)>; MAX_DRIVERS],
+    claimed: bool,
+    read_only: Vec<RangeInclusive<u16>>,
+    handler: Option<Box<dyn FnMut(Fault)>>,
+}
+
+impl<B: Bus + Default> Default for CheckedBus<B> {
+    fn default() -> Self {
+        Self::new(B::default())
+    }
+}
+
+impl<B: Bus> CheckedBus<B> {
+    /// Wrap the given bus.
+    pub fn new(inner: B) -> Self {
+        Self {
+            inner,
+            device: "unknown",
+            writer: "unknown",
+            drivers: [None; MAX_DRIVERS],
+            claimed: false,
+            read_only: Vec::new(),
+            handler: None,
+        }
+    }
+
+    /// Install a handler that is called for every fault detected.
+    pub fn set_handler(&mut self, handler: impl FnMut(Fault) + 'static) {
+        self.handler = Some(Box::new(handler));
+    }
+
+    /// Remove the fault handler, disabling checking.
+    pub fn clear_handler(&mut self) {
+        self.handler = None;
+    }
+
+    /// Mark an address range as read-only.
+    ///
+    /// Writes to this range are reported unless a device [`claim`](Self::claim)s them.
+    pub fn add_read_only(&mut self, range: RangeInclusive<u16>) {
+        self.read_only.push(range);
+    }
+
+    /// Mark the current phase as accepted by some device.
+    ///
+    /// Used for things like bank-switched RAM overlaying a read-only region.
+    pub fn claim(&mut self) {
+        self.claimed = true;
+    }
+
+    /// Returns a reference to the wrapped bus.
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped bus.
+    pub fn inner_mut(&mut self) -> &mut B {
+        &mut self.inner
+    }
+
+    fn report(&mut self, fault: Fault) {
+        if let Some(handler) = &mut self.handler {
+            handler(fault);
+        }
+    }
+
+    // Check everything that can only be known once the phase is over, then start a new one
+    fn end_phase(&mut self) {
+        let (addr, data, op) = (self.inner.addr(), self.inner.data(), self.inner.op());
+
+        match op {
+            Op::Read if self.drivers[0].is_none() => self.report(Fault::Floating { addr, data }),
+            Op::Write if !self.claimed && self.read_only.iter().any(|r| r.contains(&addr)) => {
+                let device = self.writer;
+                self.report(Fault::ReadOnlyWrite { addr, data, device });
+            }
+            _ => (),
+        }
+
+        self.drivers = [None; MAX_DRIVERS];
+        self.claimed = false;
+    }
+
+    fn record_driver(&mut self, data: u8) {
+        let device = self.device;
+        let Some(first) = self.drivers[0] else {
+            self.drivers[0] = Some((device, data));
+            return;
+        };
+
+        // A device is free to change its mind about what it drives
+        if let Some(slot) = self
+            .drivers
+            .iter_mut()
+            .find(|d| d.is_some_and(|(dev, _)| dev == device))
+        {
+            *slot = Some((device, data));
+            return;
+        }
+
+        if let Some(slot) = self.drivers.iter_mut().find(|d| d.is_none()) {
+            *slot = Some((device, data));
+        }
+        let fault = Fault::Contention {
+            addr: self.inner.addr(),
+            op: self.inner.op(),
+            first,
+            second: (device, data),
+        };
+        self.report(fault);
+    }
+}
+
+impl<B: Bus> Bus for CheckedBus<B> {
+    fn set_addr(&mut self, addr: u16) {
+        if self.handler.is_some() {
+            self.end_phase();
+            self.writer = self.device;
+        }
+        self.inner.set_addr(addr);
+    }
+
+    fn addr(&self) -> u16 {
+        self.inner.addr()
+    }
+
+    fn set_data(&mut self, data: u8) {
+        if self.handler.is_some() {
+            self.record_driver(data);
+        }
+        self.inner.set_data(data);
+    }
+
+    fn data(&self) -> u8 {
+        self.inner.data()
+    }
+
+    fn set_op(&mut self, op: Op) {
+        self.inner.set_op(op);
+    }
+
+    fn op(&self) -> Op {
+        self.inner.op()
+    }
+
+    fn set_sync(&mut self, active: bool) {
+        self.inner.set_sync(active);
+    }
+
+    fn sync(&self) -> bool {
+        self.inner.sync()
+    }
+
+    fn sync_edge(&self) -> Option<bool> {
+        self.inner.sync_edge()
+    }
+
+    fn set_res(&mut self, active: bool) {
+        self.inner.set_res(active);
+    }
+
+    fn res(&self) -> bool {
+        self.inner.res()
+    }
+
+    fn res_edge(&self) -> Option<bool> {
+        self.inner.res_edge()
+    }
+
+    fn set_irq(&mut self, active: bool) {
+        self.inner.set_irq(active);
+    }
+
+    fn irq(&self) -> bool {
+        self.inner.irq()
+    }
+
+    fn irq_edge(&self) -> Option<bool> {
+        self.inner.irq_edge()
+    }
+
+    fn set_nmi(&mut self, active: bool) {
+        self.inner.set_nmi(active);
+    }
+
+    fn nmi(&self) -> bool {
+        self.inner.nmi()
+    }
+
+    fn nmi_edge(&self) -> Option<bool> {
+        self.inner.nmi_edge()
+    }
+
+    fn set_so(&mut self, active: bool) {
+        self.inner.set_so(active);
+    }
+
+    fn so(&self) -> bool {
+        self.inner.so()
+    }
+
+    fn so_edge(&self) -> Option<bool> {
+        self.inner.so_edge()
+    }
+
+    fn set_rdy(&mut self, active: bool) {
+        self.inner.set_rdy(active);
+    }
+
+    fn rdy(&self) -> bool {
+        self.inner.rdy()
+    }
+
+    fn rdy_edge(&self) -> Option<bool> {
+        self.inner.rdy_edge()
+    }
+
+    fn tick(&mut self) {
+        self.inner.tick();
+    }
+
+    fn set_device(&mut self, device: Device) {
+        self.device = device;
+    }
+}
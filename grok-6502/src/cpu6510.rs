@@ -0,0 +1,357 @@
+//! MOS 6510 emulator.
+//!
+//! The 6510 is a 6502 with an on-chip 8-bit I/O port mapped at `$0000` (data direction register)
+//! and `$0001` (data register), most famously used by the Commodore 64 for banking and the cassette.
+//!
+//! Only six of the port bits (P0-P5) have pins. Pins configured as inputs read whatever external
+//! hardware drives them to, with undriven pins pulled high. The remaining two bits have no pins, so
+//! when configured as inputs they read back the last value they were driven to, which "fades" to 0
+//! after a while as the charge leaks away (some C64 copy protections rely on this).
+//!
+//! All other addresses (and the port addresses themselves, since the address still appears on the
+//! bus) behave exactly like the 6502 and go through the usual [`Bus`].
+
+use crate::bus::{Bus, Op};
+use crate::state::{StateReader, StateWriter};
+use crate::{Cpu, State};
+
+const DDR_ADDR: u16 = 0x0000;
+const DATA_ADDR: u16 = 0x0001;
+
+// Bits of the port that are actually connected to pins
+const PIN_MASK: u8 = 0x3F;
+
+// Roughly how many cycles an unconnected bit holds its charge on a real C64
+const DEFAULT_FADE_CYCLES: u64 = 350_000;
+
+/// External hardware connected to the 6510 I/O port.
+pub trait Port {
+    /// Returns the levels external hardware is driving the port pins to.
+    ///
+    /// Only pins configured as inputs are used. Pins that nothing is driving should read
+    /// high thanks to pull-ups, which is what the default does.
+    fn input(&mut self) -> u8 {
+        0xFF
+    }
+
+    /// Called whenever the port data or data direction registers change.
+    ///
+    /// Only bits set in `ddr` are actually being driven onto the pins.
+    fn output(&mut self, _data: u8, _ddr: u8) {}
+}
+
+/// A port with nothing connected.
+impl Port for () {}
+
+// The port registers, along with the state of the unconnected bits
+struct ProcessorPort<P: Port> {
+    port: P,
+    ddr: u8,
+    data: u8,
+    charge: u8,
+    fade_at: [u64; 8],
+    fade_cycles: u64,
+    cycles: u64,
+}
+
+impl<P: Port> ProcessorPort<P> {
+    fn read(&mut self, addr: u16) -> u8 {
+        match addr {
+            DDR_ADDR => self.ddr,
+            _ => {
+                // Unconnected input bits lose their charge once enough time has passed
+                for bit in 0..8 {
+                    if self.cycles >= self.fade_at[bit] {
+                        self.charge &= !(1 << bit);
+                    }
+                }
+
+                let inputs = (self.port.input() & PIN_MASK) | (self.charge & !PIN_MASK);
+                (self.data & self.ddr) | (inputs & !self.ddr)
+            }
+        }
+    }
+
+    fn write(&mut self, addr: u16, data: u8) {
+        let (prev_ddr, prev_data) = (self.ddr, self.data);
+        match addr {
+            DDR_ADDR => self.ddr = data,
+            _ => self.data = data,
+        }
+
+        // Unconnected bits driven as outputs charge up to the output value,
+        // and once switched to inputs they hold on to it until it fades away
+        let driven = self.ddr & !PIN_MASK;
+        self.charge = (self.charge & !driven) | (self.data & driven);
+        for bit in 0..8 {
+            let mask = 1 << bit;
+            if driven & mask != 0 {
+                self.fade_at[bit] = u64::MAX;
+            } else if prev_ddr & mask != 0 {
+                self.fade_at[bit] = self.cycles + self.fade_cycles;
+            }
+        }
+
+        if (self.ddr, self.data) != (prev_ddr, prev_data) {
+            self.port.output(self.data, self.ddr);
+        }
+    }
+
+    fn reset(&mut self) {
+        // All bits become inputs on reset
+        self.write(DDR_ADDR, 0);
+    }
+}
+
+// Returns the port register being accessed on the bus, if any
+fn port_addr(bus: &dyn Bus) -> Option<u16> {
+    matches!(bus.addr(), DDR_ADDR | DATA_ADDR).then(|| bus.addr())
+}
+
+// Wraps the real bus so the CPU reads the port registers instead of whatever is on the data lines
+struct PortBus<'a> {
+    bus: &'a mut dyn Bus,
+    port_data: Option<u8>,
+}
+
+impl Bus for PortBus<'_> {
+    fn set_addr(&mut self, addr: u16) {
+        self.bus.set_addr(addr);
+    }
+
+    fn addr(&self) -> u16 {
+        self.bus.addr()
+    }
+
+    fn set_data(&mut self, data: u8) {
+        self.bus.set_data(data);
+    }
+
+    fn data(&self) -> u8 {
+        self.port_data.unwrap_or_else(|| self.bus.data())
+    }
+
+    fn set_op(&mut self, op: Op) {
+        self.bus.set_op(op);
+    }
+
+    fn op(&self) -> Op {
+        self.bus.op()
+    }
+
+    fn set_sync(&mut self, active: bool) {
+        self.bus.set_sync(active);
+    }
+
+    fn sync(&self) -> bool {
+        self.bus.sync()
+    }
+
+    fn sync_edge(&self) -> Option<bool> {
+        self.bus.sync_edge()
+    }
+
+    fn set_res(&mut self, active: bool) {
+        self.bus.set_res(active);
+    }
+
+    fn res(&self) -> bool {
+        self.bus.res()
+    }
+
+    fn res_edge(&self) -> Option<bool> {
+        self.bus.res_edge()
+    }
+
+    fn set_irq(&mut self, active: bool) {
+        self.bus.set_irq(active);
+    }
+
+    fn irq(&self) -> bool {
+        self.bus.irq()
+    }
+
+    fn irq_edge(&self) -> Option<bool> {
+        self.bus.irq_edge()
+    }
+
+    fn set_nmi(&mut self, active: bool) {
+        self.bus.set_nmi(active);
+    }
+
+    fn nmi(&self) -> bool {
+        self.bus.nmi()
+    }
+
+    fn nmi_edge(&self) -> Option<bool> {
+        self.bus.nmi_edge()
+    }
+
+    fn set_so(&mut self, active: bool) {
+        self.bus.set_so(active);
+    }
+
+    fn so(&self) -> bool {
+        self.bus.so()
+    }
+
+    fn so_edge(&self) -> Option<bool> {
+        self.bus.so_edge()
+    }
+
+    fn set_rdy(&mut self, active: bool) {
+        self.bus.set_rdy(active);
+    }
+
+    fn rdy(&self) -> bool {
+        self.bus.rdy()
+    }
+
+    fn rdy_edge(&self) -> Option<bool> {
+        self.bus.rdy_edge()
+    }
+
+    fn tick(&mut self) {
+        self.bus.tick();
+    }
+
+    fn set_device(&mut self, device: &'static str) {
+        self.bus.set_device(device);
+    }
+}
+
+/// A clock-phase stepped MOS 6510 emulator.
+///
+/// This behaves exactly like [`Cpu`] aside from the I/O port, which external hardware
+/// is connected to through a [`Port`].
+pub struct Cpu6510<P: Port = ()> {
+    cpu: Cpu,
+    port: ProcessorPort<P>,
+}
+
+impl<P: Port> Cpu6510<P> {
+    /// Create a new instance of the CPU in the [`State::Run`] state, with `port` connected to the I/O port.
+    ///
+    /// The reset sequence should be performed to initialize CPU.
+    pub fn new(port: P) -> Self {
+        Self {
+            cpu: Cpu::new(),
+            port: ProcessorPort {
+                port,
+                ddr: 0,
+                data: 0,
+                charge: 0,
+                fade_at: [u64::MAX; 8],
+                fade_cycles: DEFAULT_FADE_CYCLES,
+                cycles: 0,
+            },
+        }
+    }
+
+    /// Set how many cycles the unconnected port bits hold their value once switched to inputs.
+    pub fn set_fade_cycles(&mut self, cycles: u64) {
+        self.port.fade_cycles = cycles;
+    }
+
+    /// Put the CPU in the [`State::Reset`] state (see [`Cpu::reset`]).
+    ///
+    /// This also turns all I/O port bits into inputs.
+    pub fn reset(&mut self, bus: &mut dyn Bus) {
+        self.port.reset();
+        self.cpu.reset(bus);
+    }
+
+    /// Put the CPU in the [`State::Halt`] state (see [`Cpu::halt`]).
+    pub fn halt(&mut self) {
+        self.cpu.halt();
+    }
+
+    /// Advance the CPU state by one clock phase (half-cycle) (see [`Cpu::tick`]).
+    pub fn tick(&mut self, bus: &mut dyn Bus) {
+        if bus.res_edge() == Some(false) {
+            self.port.reset();
+        }
+
+        // On the second phase of a port read the CPU latches the port register instead
+        // (external hardware may respond on the data lines too, but the CPU doesn't listen)
+        let second_phase = !self.cpu.hcycle.is_multiple_of(2);
+        let port_data = match port_addr(bus) {
+            Some(addr) if second_phase && bus.op() == Op::Read => Some(self.port.read(addr)),
+            _ => None,
+        };
+
+        self.cpu.tick(&mut PortBus { bus, port_data });
+
+        // Writes go to the port (as well as out on the bus, just like the real chip)
+        if bus.op() == Op::Write
+            && let Some(addr) = port_addr(bus)
+        {
+            self.port.write(addr, bus.data());
+        }
+
+        if second_phase {
+            self.port.cycles += 1;
+        }
+    }
+
+    /// Returns the current [`State`] of the CPU.
+    pub fn state(&self) -> State {
+        self.cpu.state()
+    }
+
+    /// Returns whether the CPU was stalled by RDY on its last tick.
+    pub fn stalled(&self) -> bool {
+        self.cpu.stalled()
+    }
+
+    /// Returns the current value of the data direction register (`$0000`).
+    pub fn ddr(&self) -> u8 {
+        self.port.ddr
+    }
+
+    /// Returns the current value of the data register (`$0001`).
+    pub fn data(&self) -> u8 {
+        self.port.data
+    }
+
+    /// Returns a reference to the hardware connected to the I/O port.
+    pub fn port(&self) -> &P {
+        &self.port.port
+    }
+
+    /// Returns a mutable reference to the hardware connected to the I/O port.
+    pub fn port_mut(&mut self) -> &mut P {
+        &mut self.port.port
+    }
+
+    /// Save the complete state of the CPU and its I/O port, including the charge held by the unconnected bits.
+    ///
+    /// The hardware connected to the port isn't included, it has to save its own state.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        self.cpu.save_state(state);
+        state.u8(self.port.ddr);
+        state.u8(self.port.data);
+        state.u8(self.port.charge);
+        for fade_at in self.port.fade_at {
+            state.u64(fade_at);
+        }
+        state.u64(self.port.fade_cycles);
+        state.u64(self.port.cycles);
+    }
+
+    /// Restore state previously saved by [`Cpu6510::save_state`].
+    ///
+    /// [`Port::output`] isn't called, since the connected hardware is expected to restore its own state.
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.cpu.load_state(state)?;
+        self.port.ddr = state.u8()?;
+        self.port.data = state.u8()?;
+        self.port.charge = state.u8()?;
+        for fade_at in &mut self.port.fade_at {
+            *fade_at = state.u64()?;
+        }
+        self.port.fade_cycles = state.u64()?;
+        self.port.cycles = state.u64()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+//! DMA bus mastering.
+//!
+//! Other devices can take over the bus by pulling RDY low to stall the CPU.
+//! Like the real NMOS 6502, the CPU ignores RDY during write cycles, so a device must wait
+//! until the CPU actually stops (at its next read cycle, which can be up to three cycles
+//! later, e.g. during the pushes of a `JSR`) before it can safely drive the bus.
+//!
+//! [`Dma`] takes care of that handshake along with saving and restoring the CPU's
+//! pending read so it resumes cleanly when the bus is released.
+
+use crate::Cpu;
+use crate::bus::{Bus, Op};
+use crate::state::{StateReader, StateWriter};
+
+/// The state of a DMA transfer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum DmaState {
+    /// The CPU owns the bus.
+    #[default]
+    Idle,
+    /// RDY has been pulled low, but the CPU hasn't stopped yet.
+    Requested,
+    /// The requesting device owns the bus.
+    Granted,
+}
+
+/// Arbitrates ownership of the bus between the CPU and a DMA device.
+///
+/// Each cycle, after the CPU's first clock phase, call [`tick`](Dma::tick).
+/// If it returns `true`, the DMA device owns the bus for that cycle and is free to
+/// perform a read or write in place of the CPU.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dma {
+    state: DmaState,
+    cpu_addr: u16,
+    cpu_op: Op,
+}
+
+impl Dma {
+    /// Create a new DMA arbiter in the [`DmaState::Idle`] state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current [`DmaState`].
+    pub fn state(&self) -> DmaState {
+        self.state
+    }
+
+    /// Request ownership of the bus by pulling RDY low.
+    ///
+    /// The bus will be granted once the CPU stalls on its next read cycle.
+    pub fn request(&mut self, bus: &mut dyn Bus) {
+        if self.state == DmaState::Idle {
+            bus.set_rdy(false);
+            self.state = DmaState::Requested;
+        }
+    }
+
+    /// Give the bus back to the CPU.
+    ///
+    /// This should be called at the start of a cycle (before the bus is decoded), since the
+    /// CPU's pending read is put back on the bus so it can complete during this cycle.
+    pub fn release(&mut self, bus: &mut dyn Bus) {
+        if self.state == DmaState::Granted {
+            bus.set_addr(self.cpu_addr);
+            bus.set_op(self.cpu_op);
+        }
+
+        bus.set_rdy(true);
+        self.state = DmaState::Idle;
+    }
+
+    /// Update arbitration for this cycle, returning whether the DMA device owns the bus.
+    pub fn tick(&mut self, cpu: &Cpu, bus: &dyn Bus) -> bool {
+        if self.state == DmaState::Requested && cpu.stalled() {
+            // Remember what the CPU was in the middle of so it can be restored on release
+            self.cpu_addr = bus.addr();
+            self.cpu_op = bus.op();
+            self.state = DmaState::Granted;
+        }
+
+        self.state == DmaState::Granted
+    }
+
+    /// Save the current arbitration state.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.state as u8);
+        state.u16(self.cpu_addr);
+        state.bool(self.cpu_op == Op::Read);
+    }
+
+    /// Restore state previously saved by [`Dma::save_state`].
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.state = match state.u8()? {
+            0 => DmaState::Idle,
+            1 => DmaState::Requested,
+            2 => DmaState::Granted,
+            _ => return Err("Save state has an invalid DMA state"),
+        };
+        self.cpu_addr = state.u16()?;
+        self.cpu_op = if state.bool()? { Op::Read } else { Op::Write };
+        Ok(())
+    }
+}
@@ -1,17 +1,36 @@
 //! MOS 6502 emulator.
 
+pub mod analyzer;
 pub mod bus;
+pub mod cpu6510;
+pub mod dma;
 mod opcodes;
+pub mod state;
 #[cfg(test)]
 mod tests;
+pub mod vcd;
 
 use bitflags::bitflags;
 use bus::Bus;
+use state::{StateReader, StateWriter};
 
 const STACK_OFFSET: u16 = 0x0100;
 const RESET_VECTOR: u16 = 0xFFFC;
 const INTR_VECTOR: u16 = 0xFFFE;
 
+/// The variant of 6502 being emulated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// The original NMOS 6502, including its undocumented opcodes.
+    #[default]
+    Nmos,
+    /// The CMOS 65C02 (without the Rockwell/WDC bit manipulation instructions).
+    ///
+    /// This adds a handful of new instructions and addressing modes, turns all undocumented
+    /// opcodes into NOPs, and fixes a few bugs (such as the `JMP ($xxFF)` page wrap).
+    Cmos,
+}
+
 #[derive(Default, Clone, Copy, PartialEq, Eq)]
 pub enum State {
     Reset,
@@ -45,11 +64,13 @@ enum AddrMode {
     AbsY, // Absolute Indexed with Y
     Imm0, // Immediate
     Imp0, // Implied
+    Imp1, // Implied, but only takes a single cycle (65C02 only)
     Ind0, // Indirect
     IndX, // Indirect Indexed with X
     IndY, // Indirect Indexed with Y
     Rel0, // Relative
     Zpg0, // Zero Page
+    ZpgI, // Zero Page Indirect (65C02 only)
     ZpgX, // Zero Page Indexed Indirect with X
     ZpgY, // Zero Page Indexed Indirect with Y
 }
@@ -110,9 +131,12 @@ struct Registers {
 /// A clock-phase stepped MOS 6502 emulator.
 #[derive(Default)]
 pub struct Cpu {
+    variant: Variant,
     state: State,
     registers: Registers,
     hcycle: u8,
+    stalled: bool,
+    decimal_cycle: bool,
 }
 
 impl Cpu {
@@ -123,6 +147,19 @@ impl Cpu {
         Self::default()
     }
 
+    /// Create a new instance of the given [`Variant`] of CPU in the [`State::Run`] state.
+    pub fn with_variant(variant: Variant) -> Self {
+        Self {
+            variant,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the [`Variant`] of CPU being emulated.
+    pub fn variant(&self) -> Variant {
+        self.variant
+    }
+
     /// Put the CPU in the [`State::Reset`] state, which will cause it
     /// to begin the 7 cycle (14 tick) reset sequence on next tick.
     ///
@@ -131,6 +168,7 @@ impl Cpu {
     pub fn reset(&mut self, bus: &mut dyn Bus) {
         self.state = State::Reset;
         self.hcycle = 0;
+        self.decimal_cycle = false;
 
         // Disable interrupts flag and extension bit should be set
         self.registers.p = StatusFlags::E | StatusFlags::I;
@@ -152,6 +190,8 @@ impl Cpu {
     /// (such as memory placing data on the bus) which the CPU can then react to
     /// in the second clock phase.
     pub fn tick(&mut self, bus: &mut dyn Bus) {
+        bus.set_device("cpu");
+
         // When RST goes active, the CPU enters a halt state
         // Only when it goes back inactive does the reset sequence actually begin
         match bus.res_edge() {
@@ -167,7 +207,13 @@ impl Cpu {
 
         // If RDY is inactive and we are on the second clock phase of a read cycle,
         // we need to pause until it goes active again
-        if !self.hcycle.is_multiple_of(2) && !bus.rdy() && bus.op() == bus::Op::Read {
+        //
+        // Note RDY is ignored during write cycles, just like on the NMOS 6502
+        // (and once stalled, whatever another bus master does to the bus in the meantime doesn't matter)
+        self.stalled = !self.hcycle.is_multiple_of(2)
+            && !bus.rdy()
+            && (self.stalled || bus.op() == bus::Op::Read);
+        if self.stalled {
             return;
         }
 
@@ -210,6 +256,15 @@ impl Cpu {
 
                 _ => unreachable!(),
             },
+            // The 65C02 takes an extra cycle to fix up flags after decimal arithmetic
+            State::Run if self.decimal_cycle => match self.hcycle {
+                1 => bus.start_read(bus.addr()),
+                2 => {
+                    self.decimal_cycle = false;
+                    self.end_instruction();
+                }
+                _ => unreachable!(),
+            },
             State::Run => {
                 // SYNC should be active for the first cycle (half-cycles 1 and 2)
                 // then go inactive on the next cycle (3rd half-cycle)
@@ -237,6 +292,76 @@ impl Cpu {
         self.state
     }
 
+    /// Returns whether the CPU was stalled by RDY on its last tick.
+    ///
+    /// While stalled, other devices are free to take over the bus (see [`dma::Dma`]).
+    pub fn stalled(&self) -> bool {
+        self.stalled
+    }
+
+    /// Save the complete state of the CPU, including progress through the current instruction.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.variant as u8);
+        state.u8(self.state as u8);
+        state.u16(self.registers.pc);
+        state.u8(self.registers.s);
+        state.u8(self.registers.a);
+        state.u8(self.registers.x);
+        state.u8(self.registers.y);
+        state.u8(self.registers.p.bits());
+        state.bytes(&self.registers.internal.scratch);
+        state.bytes(&self.registers.internal.scratch2);
+
+        // A CPU that has never fetched anything may still hold the NMOS default opcode,
+        // which doesn't matter since it will be replaced before it is used
+        let ir = self
+            .opcodes()
+            .iter()
+            .position(|opcode| core::ptr::eq(opcode, self.registers.internal.ir))
+            .unwrap_or(0);
+        state.u8(ir as u8);
+
+        state.u8(self.hcycle);
+        state.bool(self.stalled);
+        state.bool(self.decimal_cycle);
+    }
+
+    /// Restore state previously saved by [`Cpu::save_state`].
+    ///
+    /// The state must have been saved from the same [`Variant`] of CPU.
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        if state.u8()? != self.variant as u8 {
+            return Err("Save state is for a different CPU variant");
+        }
+
+        self.state = match state.u8()? {
+            0 => State::Reset,
+            1 => State::Run,
+            2 => State::Halt,
+            _ => return Err("Save state has an invalid CPU state"),
+        };
+        self.registers.pc = state.u16()?;
+        self.registers.s = state.u8()?;
+        self.registers.a = state.u8()?;
+        self.registers.x = state.u8()?;
+        self.registers.y = state.u8()?;
+        self.registers.p = StatusFlags::from_bits_retain(state.u8()?);
+        state.fill(&mut self.registers.internal.scratch)?;
+        state.fill(&mut self.registers.internal.scratch2)?;
+        self.registers.internal.ir = &self.opcodes()[state.u8()? as usize];
+        self.hcycle = state.u8()?;
+        self.stalled = state.bool()?;
+        self.decimal_cycle = state.bool()?;
+        Ok(())
+    }
+
+    fn opcodes(&self) -> &'static [opcodes::Opcode; 256] {
+        match self.variant {
+            Variant::Nmos => &opcodes::OPCODES,
+            Variant::Cmos => &opcodes::OPCODES_65C02,
+        }
+    }
+
     fn end_instruction(&mut self) {
         self.hcycle = 0;
     }
@@ -256,11 +381,27 @@ impl Cpu {
         self.registers.pc = self.registers.pc.wrapping_add(1);
     }
 
+    // The NMOS 6502 writes back the unmodified value during read-modify-write instructions,
+    // but the 65C02 just reads it again
+    fn rmw_dummy(&mut self, bus: &mut dyn Bus, addr: u16, data: u8) {
+        match self.variant {
+            Variant::Nmos => bus.start_write(addr, data),
+            Variant::Cmos => bus.start_read(addr),
+        }
+    }
+
     fn fetch(&mut self, bus: &mut dyn Bus) {
         match self.hcycle {
             // T0 (Fetch opcode)
             1 => self.fetch_pc(bus),
-            2 => self.registers.internal.ir = &opcodes::OPCODES[bus.data() as usize],
+            2 => {
+                self.registers.internal.ir = &self.opcodes()[bus.data() as usize];
+
+                // Single cycle instructions are already done
+                if let AddrMode::Imp1 = self.registers.internal.ir.mode {
+                    self.end_instruction();
+                }
+            }
             _ => unreachable!(),
         }
     }
@@ -330,7 +471,7 @@ impl Cpu {
                         8 => self.registers.internal.scratch2[0] = bus.data(),
 
                         // T4 (Dummy write back unmodified data)
-                        9 => bus.start_write(addr, self.registers.internal.scratch2[0]),
+                        9 => self.rmw_dummy(bus, addr, self.registers.internal.scratch2[0]),
                         10 => (),
 
                         // T5 (Execute + write back modified data)
@@ -429,19 +570,37 @@ impl Cpu {
                     self.registers.internal.scratch[0].wrapping_add(offset),
                     self.registers.internal.scratch[1],
                 ]);
-                bus.start_read(addr);
+                let eff_addr =
+                    u16::from_le_bytes(self.registers.internal.scratch).wrapping_add(offset as u16);
+
+                // The 65C02 never reads from the unfixed address, instead reading
+                // the last operand byte again if a page was crossed
+                match self.variant {
+                    Variant::Cmos if addr != eff_addr => {
+                        bus.start_read(self.registers.pc.wrapping_sub(1))
+                    }
+                    _ => bus.start_read(addr),
+                }
             }
             8 => {
-                // Only `Read` instructions can exit early here
-                if let Instruction::Read(exec) = opcode.instr {
-                    let addr = u16::from_le_bytes(self.registers.internal.scratch);
-                    let eff_addr = addr.wrapping_add(offset as u16);
+                let addr = u16::from_le_bytes(self.registers.internal.scratch);
+                let eff_addr = addr.wrapping_add(offset as u16);
+                let page_crossed = (eff_addr & 0xFF00) != (addr & 0xFF00);
 
+                match opcode.instr {
                     // No page was crossed, so end here
-                    if (eff_addr & 0xFF00) == (addr & 0xFF00) {
+                    Instruction::Read(exec) if !page_crossed => {
                         exec(self, bus.data());
                         self.end_instruction();
                     }
+
+                    // The 65C02 can also skip ahead for shifts and rotates (but not INC/DEC),
+                    // since it already read the right address
+                    Instruction::Rmw(_) if opcode.fast_abs_x && !page_crossed => {
+                        self.registers.internal.scratch2[0] = bus.data();
+                        self.hcycle += 2;
+                    }
+                    _ => (),
                 }
             }
 
@@ -484,7 +643,7 @@ impl Cpu {
                         10 => self.registers.internal.scratch2[0] = bus.data(),
 
                         // T5 (Dummy write back unmodified data)
-                        11 => bus.start_write(eff_addr, self.registers.internal.scratch2[0]),
+                        11 => self.rmw_dummy(bus, eff_addr, self.registers.internal.scratch2[0]),
                         12 => (),
 
                         // T6 (Execute + write back modified data)
@@ -648,7 +807,16 @@ impl Cpu {
                     self.registers.internal.scratch[0].wrapping_add(self.registers.y),
                     self.registers.internal.scratch[1],
                 ]);
-                bus.start_read(addr);
+                let eff_addr = u16::from_le_bytes(self.registers.internal.scratch)
+                    .wrapping_add(self.registers.y as u16);
+
+                // Like indexed absolute, the 65C02 rereads the operand byte instead on page cross
+                match self.variant {
+                    Variant::Cmos if addr != eff_addr => {
+                        bus.start_read(self.registers.pc.wrapping_sub(1))
+                    }
+                    _ => bus.start_read(addr),
+                }
             }
             10 => {
                 // Only `Internal` instructions can exit early here
@@ -817,7 +985,7 @@ impl Cpu {
                         6 => self.registers.internal.scratch[1] = bus.data(),
 
                         // T3 (Dummy write back unmodified data)
-                        7 => bus.start_write(addr, self.registers.internal.scratch[1]),
+                        7 => self.rmw_dummy(bus, addr, self.registers.internal.scratch[1]),
                         8 => (),
 
                         // T4 (Execute + write back modified data)
@@ -835,6 +1003,54 @@ impl Cpu {
         }
     }
 
+    fn dispatch_zpgi(&mut self, bus: &mut dyn Bus) {
+        let opcode = self.registers.internal.ir;
+
+        match self.hcycle {
+            // T1 (Fetch pg0 indirect address)
+            3 => self.fetch_pc(bus),
+            4 => self.registers.internal.scratch[1] = bus.data(),
+
+            // T2 (Fetch low byte of effective address)
+            5 => bus.start_read(self.registers.internal.scratch[1] as u16),
+            6 => self.registers.internal.scratch[0] = bus.data(),
+
+            // T3 (Fetch high byte of effective address)
+            7 => {
+                let addr = self.registers.internal.scratch[1].wrapping_add(1);
+                bus.start_read(addr as u16);
+            }
+            8 => self.registers.internal.scratch[1] = bus.data(),
+
+            9.. => {
+                let eff_addr = u16::from_le_bytes(self.registers.internal.scratch);
+
+                match opcode.instr {
+                    Instruction::Read(exec) => match self.hcycle {
+                        // T4 (Fetch data at effective address + execute)
+                        9 => bus.start_read(eff_addr),
+                        10 => {
+                            exec(self, bus.data());
+                            self.end_instruction();
+                        }
+                        _ => unreachable!(),
+                    },
+                    Instruction::Write(exec) => match self.hcycle {
+                        // T4 (Execute + write data to effective address)
+                        9 => {
+                            let data = exec(self);
+                            bus.start_write(eff_addr, data);
+                        }
+                        10 => self.end_instruction(),
+                        _ => unreachable!(),
+                    },
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
     fn dispatch_zpg_offset(&mut self, bus: &mut dyn Bus, offset: u8) {
         let opcode = self.registers.internal.ir;
 
@@ -875,7 +1091,7 @@ impl Cpu {
                         8 => self.registers.internal.scratch[1] = bus.data(),
 
                         // T4 (Dummy write back unmodified data)
-                        9 => bus.start_write(eff_addr, self.registers.internal.scratch[1]),
+                        9 => self.rmw_dummy(bus, eff_addr, self.registers.internal.scratch[1]),
                         10 => (),
 
                         // T5 (Execute + write back modified data)
@@ -903,11 +1119,13 @@ impl Cpu {
             AddrMode::AbsY => self.dispatch_abs_offset(bus, self.registers.y),
             AddrMode::Imm0 => self.dispatch_imm0(bus),
             AddrMode::Imp0 => self.dispatch_imp0(bus),
+            AddrMode::Imp1 => unreachable!(),
             AddrMode::Ind0 => self.dispatch_ind0(bus),
             AddrMode::IndX => self.dispatch_indx(bus),
             AddrMode::IndY => self.dispatch_indy(bus),
             AddrMode::Rel0 => self.dispatch_rel0(bus),
             AddrMode::Zpg0 => self.dispatch_zpg0(bus),
+            AddrMode::ZpgI => self.dispatch_zpgi(bus),
             AddrMode::ZpgX => self.dispatch_zpg_offset(bus, self.registers.x),
             AddrMode::ZpgY => self.dispatch_zpg_offset(bus, self.registers.y),
         }
@@ -1,1051 +1,3126 @@
 use crate::*;
 
 pub(crate) struct Opcode {
+    pub(crate) name: &'static str,
     pub(crate) instr: Instruction,
     pub(crate) mode: AddrMode,
+    // Whether a read-modify-write with absolute,X addressing skips the cycle spent fixing up
+    // the address when no page is crossed (only the 65C02's shifts and rotates do)
+    pub(crate) fast_abs_x: bool,
 }
 
 pub(crate) static OPCODES: [Opcode; 0x100] = [
     // $00-$0F
     Opcode {
+        name: "BRK",
         instr: Instruction::Misc(Cpu::brk),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ASL",
         instr: Instruction::Rmw(Cpu::asl),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "PHP",
         instr: Instruction::Push(Cpu::php),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ASL",
         instr: Instruction::Rmw(Cpu::asl),
         mode: AddrMode::Acm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ANC",
         instr: Instruction::Read(Cpu::anc),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ASL",
         instr: Instruction::Rmw(Cpu::asl),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $10-$1F
     Opcode {
+        name: "BPL",
         instr: Instruction::Branch(Cpu::bpl),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ASL",
         instr: Instruction::Rmw(Cpu::asl),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CLC",
         instr: Instruction::SingleByte(Cpu::clc),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ORA",
         instr: Instruction::Read(Cpu::ora),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ASL",
         instr: Instruction::Rmw(Cpu::asl),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SLO",
         instr: Instruction::Rmw(Cpu::slo),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     // $20-$2F
     Opcode {
+        name: "JSR",
         instr: Instruction::Misc(Cpu::jsr),
         // Technically more like an ABS0 but follows dispatch path better as Imp0
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "BIT",
         instr: Instruction::Read(Cpu::bit),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROL",
         instr: Instruction::Rmw(Cpu::rol),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "PLP",
         instr: Instruction::Pull(Cpu::plp),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROL",
         instr: Instruction::Rmw(Cpu::rol),
         mode: AddrMode::Acm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ANC",
         instr: Instruction::Read(Cpu::anc),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "BIT",
         instr: Instruction::Read(Cpu::bit),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROL",
         instr: Instruction::Rmw(Cpu::rol),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $30-$3F
     Opcode {
+        name: "BMI",
         instr: Instruction::Branch(Cpu::bmi),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROL",
         instr: Instruction::Rmw(Cpu::rol),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SEC",
         instr: Instruction::SingleByte(Cpu::sec),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "AND",
         instr: Instruction::Read(Cpu::and),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROL",
         instr: Instruction::Rmw(Cpu::rol),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RLA",
         instr: Instruction::Rmw(Cpu::rla),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     // $40-$4F
     Opcode {
+        name: "RTI",
         instr: Instruction::Misc(Cpu::rti),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LSR",
         instr: Instruction::Rmw(Cpu::lsr),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "PHA",
         instr: Instruction::Push(Cpu::pha),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LSR",
         instr: Instruction::Rmw(Cpu::lsr),
         mode: AddrMode::Acm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ALR",
         instr: Instruction::Read(Cpu::alr),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JMP",
         instr: Instruction::Jmp(Cpu::jmp),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LSR",
         instr: Instruction::Rmw(Cpu::lsr),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $50-$5F
     Opcode {
+        name: "BVC",
         instr: Instruction::Branch(Cpu::bvc),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LSR",
         instr: Instruction::Rmw(Cpu::lsr),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CLI",
         instr: Instruction::SingleByte(Cpu::cli),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "EOR",
         instr: Instruction::Read(Cpu::eor),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LSR",
         instr: Instruction::Rmw(Cpu::lsr),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SRE",
         instr: Instruction::Rmw(Cpu::sre),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     // $60-$6F
     Opcode {
+        name: "RTS",
         instr: Instruction::Misc(Cpu::rts),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROR",
         instr: Instruction::Rmw(Cpu::ror),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "PLA",
         instr: Instruction::Pull(Cpu::pla),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROR",
         instr: Instruction::Rmw(Cpu::ror),
         mode: AddrMode::Acm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ARR",
         instr: Instruction::Read(Cpu::arr),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JMP",
         instr: Instruction::Jmp(Cpu::jmp),
         mode: AddrMode::Ind0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROR",
         instr: Instruction::Rmw(Cpu::ror),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $70-$7F
     Opcode {
+        name: "BVS",
         instr: Instruction::Branch(Cpu::bvs),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROR",
         instr: Instruction::Rmw(Cpu::ror),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SEI",
         instr: Instruction::SingleByte(Cpu::sei),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ADC",
         instr: Instruction::Read(Cpu::adc),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ROR",
         instr: Instruction::Rmw(Cpu::ror),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "RRA",
         instr: Instruction::Rmw(Cpu::rra),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     // $80-$8F
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SAX",
         instr: Instruction::Write(Cpu::sax),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STY",
         instr: Instruction::Write(Cpu::sty),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STX",
         instr: Instruction::Write(Cpu::stx),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SAX",
         instr: Instruction::Write(Cpu::sax),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEY",
         instr: Instruction::SingleByte(Cpu::dey),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TXA",
         instr: Instruction::SingleByte(Cpu::txa),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ANE",
         instr: Instruction::Read(Cpu::ane),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STY",
         instr: Instruction::Write(Cpu::sty),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STX",
         instr: Instruction::Write(Cpu::stx),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SAX",
         instr: Instruction::Write(Cpu::sax),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $90-$9F
     Opcode {
+        name: "BCC",
         instr: Instruction::Branch(Cpu::bcc),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SHA",
         instr: Instruction::Shr(Cpu::sha),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STY",
         instr: Instruction::Write(Cpu::sty),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STX",
         instr: Instruction::Write(Cpu::stx),
         mode: AddrMode::ZpgY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SAX",
         instr: Instruction::Write(Cpu::sax),
         mode: AddrMode::ZpgY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TYA",
         instr: Instruction::SingleByte(Cpu::tya),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TXS",
         instr: Instruction::SingleByte(Cpu::txs),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TAS",
         instr: Instruction::Shr(Cpu::tas),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SHY",
         instr: Instruction::Shr(Cpu::shy),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "STA",
         instr: Instruction::Write(Cpu::sta),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SHX",
         instr: Instruction::Shr(Cpu::shx),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SHA",
         instr: Instruction::Shr(Cpu::sha),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     // $A0-$AF
     Opcode {
+        name: "LDY",
         instr: Instruction::Read(Cpu::ldy),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDX",
         instr: Instruction::Read(Cpu::ldx),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDY",
         instr: Instruction::Read(Cpu::ldy),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDX",
         instr: Instruction::Read(Cpu::ldx),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TAY",
         instr: Instruction::SingleByte(Cpu::tay),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TAX",
         instr: Instruction::SingleByte(Cpu::tax),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LXA",
         instr: Instruction::Read(Cpu::lxa),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDY",
         instr: Instruction::Read(Cpu::ldy),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDX",
         instr: Instruction::Read(Cpu::ldx),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $B0-$BF
     Opcode {
+        name: "BCS",
         instr: Instruction::Branch(Cpu::bcs),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDY",
         instr: Instruction::Read(Cpu::ldy),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDX",
         instr: Instruction::Read(Cpu::ldx),
         mode: AddrMode::ZpgY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::ZpgY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CLV",
         instr: Instruction::SingleByte(Cpu::clv),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "TSX",
         instr: Instruction::SingleByte(Cpu::tsx),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAS",
         instr: Instruction::Read(Cpu::las),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDY",
         instr: Instruction::Read(Cpu::ldy),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDA",
         instr: Instruction::Read(Cpu::lda),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LDX",
         instr: Instruction::Read(Cpu::ldx),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "LAX",
         instr: Instruction::Read(Cpu::lax),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     // $C0-$CF
     Opcode {
+        name: "CPY",
         instr: Instruction::Read(Cpu::cpy),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CPY",
         instr: Instruction::Read(Cpu::cpy),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEC",
         instr: Instruction::Rmw(Cpu::dec),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INY",
         instr: Instruction::SingleByte(Cpu::iny),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEX",
         instr: Instruction::SingleByte(Cpu::dex),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBX",
         instr: Instruction::Read(Cpu::sbx),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CPY",
         instr: Instruction::Read(Cpu::cpy),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEC",
         instr: Instruction::Rmw(Cpu::dec),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $D0-$DF
     Opcode {
+        name: "BNE",
         instr: Instruction::Branch(Cpu::bne),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEC",
         instr: Instruction::Rmw(Cpu::dec),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CLD",
         instr: Instruction::SingleByte(Cpu::cld),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CMP",
         instr: Instruction::Read(Cpu::cmp),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DEC",
         instr: Instruction::Rmw(Cpu::dec),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "DCP",
         instr: Instruction::Rmw(Cpu::dcp),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     // $E0-$EF
     Opcode {
+        name: "CPX",
         instr: Instruction::Read(Cpu::cpx),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::IndX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CPX",
         instr: Instruction::Read(Cpu::cpx),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INC",
         instr: Instruction::Rmw(Cpu::inc),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::Zpg0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INX",
         instr: Instruction::SingleByte(Cpu::inx),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "USB",
         instr: Instruction::Read(Cpu::usb),
         mode: AddrMode::Imm0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "CPX",
         instr: Instruction::Read(Cpu::cpx),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INC",
         instr: Instruction::Rmw(Cpu::inc),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::Abs0,
+        fast_abs_x: false,
     },
     // $F0-$FF
     Opcode {
+        name: "BEQ",
         instr: Instruction::Branch(Cpu::beq),
         mode: AddrMode::Rel0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "JAM",
         instr: Instruction::Misc(Cpu::jam),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::IndY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INC",
         instr: Instruction::Rmw(Cpu::inc),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::ZpgX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SED",
         instr: Instruction::SingleByte(Cpu::sed),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::SingleByte(Cpu::nop),
         mode: AddrMode::Imp0,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::AbsY,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "NOP",
         instr: Instruction::Read(Cpu::nop_read),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "SBC",
         instr: Instruction::Read(Cpu::sbc),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "INC",
         instr: Instruction::Rmw(Cpu::inc),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
     },
     Opcode {
+        name: "ISC",
         instr: Instruction::Rmw(Cpu::isc),
         mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+];
+
+// The 65C02 keeps all the documented opcodes, but reuses the undocumented ones
+pub(crate) static OPCODES_65C02: [Opcode; 0x100] = [
+    // $00-$0F
+    Opcode {
+        name: "BRK",
+        instr: Instruction::Misc(Cpu::brk),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TSB",
+        instr: Instruction::Rmw(Cpu::tsb),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ASL",
+        instr: Instruction::Rmw(Cpu::asl),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PHP",
+        instr: Instruction::Push(Cpu::php),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ASL",
+        instr: Instruction::Rmw(Cpu::asl),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TSB",
+        instr: Instruction::Rmw(Cpu::tsb),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ASL",
+        instr: Instruction::Rmw(Cpu::asl),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $10-$1F
+    Opcode {
+        name: "BPL",
+        instr: Instruction::Branch(Cpu::bpl),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TRB",
+        instr: Instruction::Rmw(Cpu::trb),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ASL",
+        instr: Instruction::Rmw(Cpu::asl),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CLC",
+        instr: Instruction::SingleByte(Cpu::clc),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INC",
+        instr: Instruction::Rmw(Cpu::inc),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TRB",
+        instr: Instruction::Rmw(Cpu::trb),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ORA",
+        instr: Instruction::Read(Cpu::ora),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ASL",
+        instr: Instruction::Rmw(Cpu::asl),
+        mode: AddrMode::AbsX,
+        fast_abs_x: true,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $20-$2F
+    Opcode {
+        name: "JSR",
+        instr: Instruction::Misc(Cpu::jsr),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "BIT",
+        instr: Instruction::Read(Cpu::bit),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROL",
+        instr: Instruction::Rmw(Cpu::rol),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PLP",
+        instr: Instruction::Pull(Cpu::plp),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROL",
+        instr: Instruction::Rmw(Cpu::rol),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "BIT",
+        instr: Instruction::Read(Cpu::bit),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROL",
+        instr: Instruction::Rmw(Cpu::rol),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $30-$3F
+    Opcode {
+        name: "BMI",
+        instr: Instruction::Branch(Cpu::bmi),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "BIT",
+        instr: Instruction::Read(Cpu::bit),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROL",
+        instr: Instruction::Rmw(Cpu::rol),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SEC",
+        instr: Instruction::SingleByte(Cpu::sec),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEC",
+        instr: Instruction::Rmw(Cpu::dec),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "BIT",
+        instr: Instruction::Read(Cpu::bit),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "AND",
+        instr: Instruction::Read(Cpu::and),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROL",
+        instr: Instruction::Rmw(Cpu::rol),
+        mode: AddrMode::AbsX,
+        fast_abs_x: true,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $40-$4F
+    Opcode {
+        name: "RTI",
+        instr: Instruction::Misc(Cpu::rti),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LSR",
+        instr: Instruction::Rmw(Cpu::lsr),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PHA",
+        instr: Instruction::Push(Cpu::pha),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LSR",
+        instr: Instruction::Rmw(Cpu::lsr),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "JMP",
+        instr: Instruction::Jmp(Cpu::jmp),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LSR",
+        instr: Instruction::Rmw(Cpu::lsr),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $50-$5F
+    Opcode {
+        name: "BVC",
+        instr: Instruction::Branch(Cpu::bvc),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LSR",
+        instr: Instruction::Rmw(Cpu::lsr),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CLI",
+        instr: Instruction::SingleByte(Cpu::cli),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PHY",
+        instr: Instruction::Push(Cpu::phy),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Misc(Cpu::nop_abs8),
+        // Nothing else takes this long, so handles its own cycles
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "EOR",
+        instr: Instruction::Read(Cpu::eor),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LSR",
+        instr: Instruction::Rmw(Cpu::lsr),
+        mode: AddrMode::AbsX,
+        fast_abs_x: true,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $60-$6F
+    Opcode {
+        name: "RTS",
+        instr: Instruction::Misc(Cpu::rts),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STZ",
+        instr: Instruction::Write(Cpu::stz),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROR",
+        instr: Instruction::Rmw(Cpu::ror),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PLA",
+        instr: Instruction::Pull(Cpu::pla),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROR",
+        instr: Instruction::Rmw(Cpu::ror),
+        mode: AddrMode::Acm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "JMP",
+        instr: Instruction::Misc(Cpu::jmp_ind),
+        // Has an extra cycle none of the dispatch paths have, so handles its own cycles like JSR
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROR",
+        instr: Instruction::Rmw(Cpu::ror),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $70-$7F
+    Opcode {
+        name: "BVS",
+        instr: Instruction::Branch(Cpu::bvs),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STZ",
+        instr: Instruction::Write(Cpu::stz),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROR",
+        instr: Instruction::Rmw(Cpu::ror),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SEI",
+        instr: Instruction::SingleByte(Cpu::sei),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PLY",
+        instr: Instruction::Pull(Cpu::ply),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "JMP",
+        instr: Instruction::Misc(Cpu::jmp_ind_x),
+        // Has an extra cycle none of the dispatch paths have, so handles its own cycles like JSR
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ADC",
+        instr: Instruction::Read(Cpu::adc),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "ROR",
+        instr: Instruction::Rmw(Cpu::ror),
+        mode: AddrMode::AbsX,
+        fast_abs_x: true,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $80-$8F
+    Opcode {
+        name: "BRA",
+        instr: Instruction::Branch(Cpu::bra),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STY",
+        instr: Instruction::Write(Cpu::sty),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STX",
+        instr: Instruction::Write(Cpu::stx),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEY",
+        instr: Instruction::SingleByte(Cpu::dey),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "BIT",
+        instr: Instruction::Read(Cpu::bit_imm),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TXA",
+        instr: Instruction::SingleByte(Cpu::txa),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STY",
+        instr: Instruction::Write(Cpu::sty),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STX",
+        instr: Instruction::Write(Cpu::stx),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $90-$9F
+    Opcode {
+        name: "BCC",
+        instr: Instruction::Branch(Cpu::bcc),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STY",
+        instr: Instruction::Write(Cpu::sty),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STX",
+        instr: Instruction::Write(Cpu::stx),
+        mode: AddrMode::ZpgY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TYA",
+        instr: Instruction::SingleByte(Cpu::tya),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TXS",
+        instr: Instruction::SingleByte(Cpu::txs),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STZ",
+        instr: Instruction::Write(Cpu::stz),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STA",
+        instr: Instruction::Write(Cpu::sta),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "STZ",
+        instr: Instruction::Write(Cpu::stz),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $A0-$AF
+    Opcode {
+        name: "LDY",
+        instr: Instruction::Read(Cpu::ldy),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDX",
+        instr: Instruction::Read(Cpu::ldx),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDY",
+        instr: Instruction::Read(Cpu::ldy),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDX",
+        instr: Instruction::Read(Cpu::ldx),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TAY",
+        instr: Instruction::SingleByte(Cpu::tay),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TAX",
+        instr: Instruction::SingleByte(Cpu::tax),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDY",
+        instr: Instruction::Read(Cpu::ldy),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDX",
+        instr: Instruction::Read(Cpu::ldx),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $B0-$BF
+    Opcode {
+        name: "BCS",
+        instr: Instruction::Branch(Cpu::bcs),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDY",
+        instr: Instruction::Read(Cpu::ldy),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDX",
+        instr: Instruction::Read(Cpu::ldx),
+        mode: AddrMode::ZpgY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CLV",
+        instr: Instruction::SingleByte(Cpu::clv),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "TSX",
+        instr: Instruction::SingleByte(Cpu::tsx),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDY",
+        instr: Instruction::Read(Cpu::ldy),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDA",
+        instr: Instruction::Read(Cpu::lda),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "LDX",
+        instr: Instruction::Read(Cpu::ldx),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $C0-$CF
+    Opcode {
+        name: "CPY",
+        instr: Instruction::Read(Cpu::cpy),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CPY",
+        instr: Instruction::Read(Cpu::cpy),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEC",
+        instr: Instruction::Rmw(Cpu::dec),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INY",
+        instr: Instruction::SingleByte(Cpu::iny),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEX",
+        instr: Instruction::SingleByte(Cpu::dex),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CPY",
+        instr: Instruction::Read(Cpu::cpy),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEC",
+        instr: Instruction::Rmw(Cpu::dec),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $D0-$DF
+    Opcode {
+        name: "BNE",
+        instr: Instruction::Branch(Cpu::bne),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEC",
+        instr: Instruction::Rmw(Cpu::dec),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CLD",
+        instr: Instruction::SingleByte(Cpu::cld),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PHX",
+        instr: Instruction::Push(Cpu::phx),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CMP",
+        instr: Instruction::Read(Cpu::cmp),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "DEC",
+        instr: Instruction::Rmw(Cpu::dec),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $E0-$EF
+    Opcode {
+        name: "CPX",
+        instr: Instruction::Read(Cpu::cpx),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::IndX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CPX",
+        instr: Instruction::Read(Cpu::cpx),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INC",
+        instr: Instruction::Rmw(Cpu::inc),
+        mode: AddrMode::Zpg0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INX",
+        instr: Instruction::SingleByte(Cpu::inx),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::Imm0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "CPX",
+        instr: Instruction::Read(Cpu::cpx),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INC",
+        instr: Instruction::Rmw(Cpu::inc),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    // $F0-$FF
+    Opcode {
+        name: "BEQ",
+        instr: Instruction::Branch(Cpu::beq),
+        mode: AddrMode::Rel0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::IndY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::ZpgI,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INC",
+        instr: Instruction::Rmw(Cpu::inc),
+        mode: AddrMode::ZpgX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SED",
+        instr: Instruction::SingleByte(Cpu::sed),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::AbsY,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "PLX",
+        instr: Instruction::Pull(Cpu::plx),
+        mode: AddrMode::Imp0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::Read(Cpu::nop_read),
+        mode: AddrMode::Abs0,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "SBC",
+        instr: Instruction::Read(Cpu::sbc),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "INC",
+        instr: Instruction::Rmw(Cpu::inc),
+        mode: AddrMode::AbsX,
+        fast_abs_x: false,
+    },
+    Opcode {
+        name: "NOP",
+        instr: Instruction::SingleByte(Cpu::nop),
+        mode: AddrMode::Imp1,
+        fast_abs_x: false,
     },
 ];
 
@@ -1163,6 +3238,12 @@ impl Cpu {
         self.registers.p.set(StatusFlags::Z, bsum as u8 == 0);
 
         self.registers.a = sum as u8;
+
+        // But the 65C02 spends an extra cycle making the flags valid for decimal mode
+        if self.variant == Variant::Cmos && self.registers.p.contains(StatusFlags::D) {
+            self.update_zn_flags(self.registers.a);
+            self.decimal_cycle = true;
+        }
     }
     fn and(&mut self, data: u8) {
         self.registers.a &= data;
@@ -1250,6 +3331,12 @@ impl Cpu {
         self.registers.p.set(StatusFlags::C, bsub <= 0xFF);
 
         self.registers.a = sum as u8;
+
+        // Except on the 65C02, which spends an extra cycle making the flags valid for decimal mode
+        if self.variant == Variant::Cmos && self.registers.p.contains(StatusFlags::D) {
+            self.update_zn_flags(self.registers.a);
+            self.decimal_cycle = true;
+        }
     }
 
     // Write
@@ -1390,6 +3477,11 @@ impl Cpu {
                 self.registers.internal.scratch[1] = bus.data();
                 self.registers.pc = u16::from_le_bytes(self.registers.internal.scratch);
                 self.registers.p |= StatusFlags::I;
+
+                // The 65C02 also clears decimal mode so interrupt handlers don't have to
+                if self.variant == Variant::Cmos {
+                    self.registers.p &= !StatusFlags::D;
+                }
                 self.end_instruction();
             }
 
@@ -1498,6 +3590,111 @@ impl Cpu {
         // Intentionally do nothing
     }
 
+    // 65C02
+    fn bit_imm(&mut self, data: u8) {
+        // Immediate BIT only affects the zero flag
+        self.registers
+            .p
+            .set(StatusFlags::Z, self.registers.a & data == 0);
+    }
+    fn bra(&mut self) -> bool {
+        true
+    }
+    fn phx(&mut self) -> u8 {
+        self.registers.x
+    }
+    fn phy(&mut self) -> u8 {
+        self.registers.y
+    }
+    fn plx(&mut self, data: u8) {
+        self.registers.x = data;
+        self.update_zn_flags(self.registers.x);
+    }
+    fn ply(&mut self, data: u8) {
+        self.registers.y = data;
+        self.update_zn_flags(self.registers.y);
+    }
+    fn stz(&mut self) -> u8 {
+        0
+    }
+    fn trb(&mut self, data: u8) -> u8 {
+        self.registers
+            .p
+            .set(StatusFlags::Z, self.registers.a & data == 0);
+        data & !self.registers.a
+    }
+    fn tsb(&mut self, data: u8) -> u8 {
+        self.registers
+            .p
+            .set(StatusFlags::Z, self.registers.a & data == 0);
+        data | self.registers.a
+    }
+    fn jmp_ind(&mut self, bus: &mut dyn Bus) {
+        self.jmp_ind_offset(bus, 0);
+    }
+    fn jmp_ind_x(&mut self, bus: &mut dyn Bus) {
+        self.jmp_ind_offset(bus, self.registers.x);
+    }
+    fn jmp_ind_offset(&mut self, bus: &mut dyn Bus, offset: u8) {
+        match self.hcycle {
+            // T1 (Fetch low byte of indirect address)
+            3 => self.fetch_pc(bus),
+            4 => self.registers.internal.scratch[0] = bus.data(),
+
+            // T2 (Fetch high byte of indirect address)
+            5 => self.fetch_pc(bus),
+            6 => self.registers.internal.scratch[1] = bus.data(),
+
+            // T3 (Dummy read while adding offset)
+            7 => bus.start_read(self.registers.pc.wrapping_sub(1)),
+            8 => (),
+
+            // T4 (Fetch low byte of jump address)
+            9 => {
+                let addr =
+                    u16::from_le_bytes(self.registers.internal.scratch).wrapping_add(offset as u16);
+                bus.start_read(addr);
+            }
+            10 => self.registers.internal.scratch2[0] = bus.data(),
+
+            // T5 (Fetch high byte of jump address + jump)
+            // Unlike the NMOS 6502, this correctly carries into the next page
+            11 => {
+                let addr = u16::from_le_bytes(self.registers.internal.scratch)
+                    .wrapping_add(offset as u16)
+                    .wrapping_add(1);
+                bus.start_read(addr);
+            }
+            12 => {
+                self.registers.internal.scratch2[1] = bus.data();
+                self.registers.pc = u16::from_le_bytes(self.registers.internal.scratch2);
+                self.end_instruction();
+            }
+
+            _ => unreachable!(),
+        }
+    }
+    fn nop_abs8(&mut self, bus: &mut dyn Bus) {
+        match self.hcycle {
+            // T1 (Fetch low byte of address)
+            3 => self.fetch_pc(bus),
+            4 => self.registers.internal.scratch[0] = bus.data(),
+
+            // T2 (Fetch high byte of address)
+            5 => self.fetch_pc(bus),
+            6 => self.registers.internal.scratch[1] = bus.data(),
+
+            // T3-T7 (Dummy reads)
+            7..=15 if self.hcycle % 2 == 1 => {
+                bus.start_read(u16::from_le_bytes(self.registers.internal.scratch));
+            }
+            8..=14 => (),
+            16 => self.end_instruction(),
+
+            _ => unreachable!(),
+        }
+    }
+
     // Illegal
     // Reference: https://www.masswerk.at/nowgobang/2021/6502-illegal-opcodes
     fn alr(&mut self, data: u8) {
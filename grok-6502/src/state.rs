@@ -0,0 +1,138 @@
+//! Helpers for saving and restoring emulator state.
+//!
+//! State is written as a flat sequence of little-endian values with no field names or padding,
+//! so it must be read back in exactly the same order it was written. Anything with a variable
+//! length is written as a blob (prefixed by its length), which also lets a reader skip over
+//! state it has no use for.
+//!
+//! It is up to the user of these to include a version in their state if the layout may change.
+
+const TRUNCATED: &str = "Save state is truncated";
+const CORRUPT: &str = "Save state is corrupt";
+
+/// Writes state to a byte buffer.
+#[derive(Default)]
+pub struct StateWriter {
+    buf: Vec<u8>,
+}
+
+impl StateWriter {
+    /// Create a new writer with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write a single byte.
+    pub fn u8(&mut self, val: u8) {
+        self.buf.push(val);
+    }
+
+    /// Write a 16-bit value.
+    pub fn u16(&mut self, val: u16) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Write a 32-bit value.
+    pub fn u32(&mut self, val: u32) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Write a 64-bit value.
+    pub fn u64(&mut self, val: u64) {
+        self.buf.extend_from_slice(&val.to_le_bytes());
+    }
+
+    /// Write a bool as a single byte.
+    pub fn bool(&mut self, val: bool) {
+        self.u8(val as u8);
+    }
+
+    /// Write raw bytes, whose length the reader must already know.
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Write bytes prefixed by their length.
+    pub fn blob(&mut self, bytes: &[u8]) {
+        self.u32(bytes.len() as u32);
+        self.bytes(bytes);
+    }
+
+    /// Consume the writer, returning everything written.
+    pub fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// Reads state previously written by a [`StateWriter`].
+pub struct StateReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    /// Create a new reader over the given buffer.
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    /// Read a single byte.
+    pub fn u8(&mut self) -> Result<u8, &'static str> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    /// Read a 16-bit value.
+    pub fn u16(&mut self) -> Result<u16, &'static str> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    /// Read a 32-bit value.
+    pub fn u32(&mut self) -> Result<u32, &'static str> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    /// Read a 64-bit value.
+    pub fn u64(&mut self) -> Result<u64, &'static str> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Read a bool, failing if the byte is anything other than 0 or 1.
+    pub fn bool(&mut self) -> Result<bool, &'static str> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(CORRUPT),
+        }
+    }
+
+    /// Read `len` raw bytes.
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        if len > self.buf.len() {
+            return Err(TRUNCATED);
+        }
+
+        let (bytes, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(bytes)
+    }
+
+    /// Read raw bytes to fill the given buffer.
+    pub fn fill(&mut self, buf: &mut [u8]) -> Result<(), &'static str> {
+        buf.copy_from_slice(self.bytes(buf.len())?);
+        Ok(())
+    }
+
+    /// Read bytes prefixed by their length.
+    pub fn blob(&mut self) -> Result<&'a [u8], &'static str> {
+        let len = self.u32()?;
+        self.bytes(len as usize)
+    }
+
+    /// Check everything has been read, failing if there is anything left over.
+    pub fn finish(&self) -> Result<(), &'static str> {
+        if self.buf.is_empty() {
+            Ok(())
+        } else {
+            Err(CORRUPT)
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use crate::*;
 use bus::Bus;
+use bus::checked::{CheckedBus, Fault};
 use rstest::*;
 use serde::Deserialize;
 use std::path::PathBuf;
@@ -192,6 +193,20 @@ fn reset_cpu(cpu: &mut Cpu, bus: &mut dyn bus::Bus) {
     }
 }
 
+// Reset the CPU, then point it at `pc` with the registers cleared
+fn start_at(cpu: &mut Cpu, bus: &mut dyn bus::Bus, pc: u16) {
+    reset_cpu(cpu, bus);
+    TestCpu {
+        pc,
+        s: 0xFD,
+        a: 0,
+        x: 0,
+        y: 0,
+        p: 0x24,
+    }
+    .set_state(cpu);
+}
+
 fn opcode_test(path: &PathBuf) {
     let mut bus = bus::SimpleBus::new();
     let mut cpu = Cpu::new();
@@ -241,3 +256,576 @@ fn opcode_test(path: &PathBuf) {
 fn cpu_test(#[files("single-step-tests/*.json")] path: PathBuf) {
     opcode_test(&path);
 }
+
+#[test]
+fn checked_bus_faults() {
+    let faults = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+    let mut bus: CheckedBus = CheckedBus::default();
+    bus.add_read_only(0xF000..=0xFFFF);
+    bus.set_handler({
+        let faults = faults.clone();
+        move |fault| faults.borrow_mut().push(fault)
+    });
+
+    // Two devices driving the same read
+    bus.set_device("cpu");
+    bus.start_read(0x1234);
+    bus.set_device("ram");
+    bus.set_data(0x12);
+    bus.set_device("card");
+    bus.set_data(0x34);
+
+    // Nobody driving a read (reported once the next phase starts)
+    bus.set_device("cpu");
+    bus.start_read(0x5678);
+
+    // Writing to ROM
+    bus.start_write(0xF000, 0xAA);
+    bus.start_read(0x0000);
+
+    assert_eq!(
+        *faults.borrow(),
+        [
+            Fault::Contention {
+                addr: 0x1234,
+                op: bus::Op::Read,
+                first: ("ram", 0x12),
+                second: ("card", 0x34),
+            },
+            Fault::Floating {
+                addr: 0x5678,
+                data: 0x34,
+            },
+            Fault::ReadOnlyWrite {
+                addr: 0xF000,
+                data: 0xAA,
+                device: "cpu",
+            },
+        ]
+    );
+}
+
+#[test]
+fn dma_waits_for_read_cycle() {
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = Cpu::new();
+    let mut memory = Memory::default();
+    let mut dma = dma::Dma::new();
+
+    start_at(&mut cpu, &mut bus, 0x0200);
+
+    // JSR $0300 / LDA #$42, STA $10, JMP *
+    memory.ram[0x0200..0x0203].copy_from_slice(&[0x20, 0x00, 0x03]);
+    memory.ram[0x0300..0x0307].copy_from_slice(&[0xA9, 0x42, 0x85, 0x10, 0x4C, 0x04, 0x03]);
+
+    let mut granted = Vec::new();
+    for cycle in 0..16 {
+        cpu.tick(&mut bus);
+
+        // Request in the middle of the JSR pushes, and give the bus back after a single cycle
+        if cycle == 3 {
+            dma.request(&mut bus);
+        } else if dma.state() == dma::DmaState::Granted {
+            dma.release(&mut bus);
+        }
+
+        if dma.tick(&cpu, &bus) {
+            bus.start_write(0x0020, 0x99);
+            granted.push(cycle);
+        }
+
+        memory.tick(&mut bus);
+        bus.tick();
+        cpu.tick(&mut bus);
+    }
+
+    // The two pushes go ahead anyway and the bus is only handed over once the CPU stops reading
+    assert_eq!(granted, [6]);
+    assert_eq!(memory.ram[0x01FC..=0x01FD], [0x02, 0x02]);
+    assert_eq!(memory.ram[0x0020], 0x99);
+    assert_eq!(memory.ram[0x0010], 0x42);
+}
+
+#[test]
+fn cpu6510_port() {
+    #[derive(Default)]
+    struct Cassette {
+        sense: u8,
+        outputs: Vec<(u8, u8)>,
+    }
+
+    impl cpu6510::Port for Cassette {
+        fn input(&mut self) -> u8 {
+            // Cassette sense on P4 driven low, everything else left to the pull-ups
+            !self.sense
+        }
+
+        fn output(&mut self, data: u8, ddr: u8) {
+            self.outputs.push((data, ddr));
+        }
+    }
+
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = cpu6510::Cpu6510::new(Cassette {
+        sense: 0x10,
+        ..Default::default()
+    });
+    let mut memory = Memory::default();
+    cpu.set_fade_cycles(10);
+
+    bus.set_res(true);
+    bus.tick();
+    cpu.tick(&mut bus);
+    bus.set_res(false);
+    memory.ram[0xFFFC..=0xFFFD].copy_from_slice(&[0x00, 0x02]);
+
+    // LDA #$EF, STA $00, LDA #$C7, STA $01, LDA #$2F, STA $00, LDA $01, STA $10, (20 cycles later) LDA $01, STA $11
+    #[rustfmt::skip]
+    let program = [
+        0xA9, 0xEF, 0x85, 0x00,
+        0xA9, 0xC7, 0x85, 0x01,
+        0xA9, 0x2F, 0x85, 0x00,
+        0xA5, 0x01, 0x85, 0x10,
+        0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA,
+        0xA5, 0x01, 0x85, 0x11,
+        0x4C, 0x1E, 0x02,
+    ];
+    memory.ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+    for _ in 0..80 {
+        bus.tick();
+        cpu.tick(&mut bus);
+        memory.tick(&mut bus);
+        bus.tick();
+        cpu.tick(&mut bus);
+    }
+
+    assert_eq!(cpu.ddr(), 0x2F);
+    assert_eq!(cpu.data(), 0xC7);
+    assert_eq!(
+        cpu.port().outputs,
+        [(0x00, 0xEF), (0xC7, 0xEF), (0xC7, 0x2F)]
+    );
+
+    // Outputs read back their data, P4 is driven low and the unconnected bits 6-7 still hold their charge
+    assert_eq!(memory.ram[0x0010], 0xC7);
+
+    // But eventually it fades away
+    assert_eq!(memory.ram[0x0011], 0x07);
+
+    // Writes still make it out onto the bus
+    assert_eq!(memory.ram[0x0001], 0xC7);
+}
+
+#[test]
+fn cmos_65c02() {
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = Cpu::with_variant(Variant::Cmos);
+    let mut memory = Memory::default();
+
+    start_at(&mut cpu, &mut bus, 0x0200);
+
+    // SED, LDA #$99, CLC, ADC #$01, STA $10, CLD, STZ $11, LDA #$55, TSB $12, PHA, PLY, JMP ($02FF)
+    #[rustfmt::skip]
+    let program = [
+        0xF8, 0xA9, 0x99, 0x18, 0x69, 0x01, 0x85, 0x10,
+        0xD8, 0x64, 0x11, 0xA9, 0x55, 0x04, 0x12,
+        0x48, 0x7A, 0x6C, 0xFF, 0x02,
+    ];
+    memory.ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+    memory.ram[0x0011] = 0xFF;
+    memory.ram[0x0012] = 0x0F;
+
+    // The NMOS 6502 would fetch the high byte from $0200 instead of $0300
+    memory.ram[0x02FF] = 0x00;
+    memory.ram[0x0300] = 0x04;
+
+    // An undocumented NMOS opcode (SLO) that is a single cycle NOP on the 65C02, then JMP *
+    memory.ram[0x0400..0x0404].copy_from_slice(&[0x03, 0x4C, 0x01, 0x04]);
+
+    let mut cycles = 0;
+    while cpu.registers.pc != 0x0401 {
+        cpu.tick(&mut bus);
+        memory.tick(&mut bus);
+        bus.tick();
+        cpu.tick(&mut bus);
+        cycles += 1;
+    }
+
+    assert_eq!(memory.ram[0x0010], 0x00);
+    assert_eq!(memory.ram[0x0011], 0x00);
+    assert_eq!(memory.ram[0x0012], 0x5F);
+    assert_eq!(cpu.registers.y, 0x55);
+    assert!(cpu.registers.p.contains(StatusFlags::C));
+
+    // Decimal mode ADC costs an extra cycle
+    assert_eq!(cycles, 2 + 2 + 2 + 3 + 3 + 2 + 3 + 2 + 5 + 3 + 4 + 6 + 1);
+}
+
+// Cycles taken by a read-modify-write of `base`,X (after LDX #$01, and up to fetching the next opcode)
+fn rmw_abs_x_cycles(variant: Variant, opcode: u8, base: u16) -> usize {
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = Cpu::with_variant(variant);
+    let mut memory = Memory::default();
+    start_at(&mut cpu, &mut bus, 0x0200);
+
+    let [lo, hi] = base.to_le_bytes();
+    memory.ram[0x0200..0x0206].copy_from_slice(&[0xA2, 0x01, opcode, lo, hi, 0xEA]);
+
+    let mut cycles = 0;
+    while cpu.registers.pc != 0x0206 {
+        cpu.tick(&mut bus);
+        memory.tick(&mut bus);
+        bus.tick();
+        cpu.tick(&mut bus);
+        cycles += 1;
+    }
+    cycles - 2 - 1
+}
+
+#[test]
+fn rmw_abs_x_timing() {
+    const ASL: u8 = 0x1E;
+    const ROR: u8 = 0x7E;
+    const INC: u8 = 0xFE;
+    const DEC: u8 = 0xDE;
+
+    // The 6502 always takes 7 cycles, but the 65C02 skips fixing up the address for shifts
+    // and rotates unless a page is crossed
+    for opcode in [ASL, ROR, INC, DEC] {
+        assert_eq!(rmw_abs_x_cycles(Variant::Nmos, opcode, 0x0300), 7);
+        assert_eq!(rmw_abs_x_cycles(Variant::Cmos, opcode, 0x03FF), 7);
+    }
+    assert_eq!(rmw_abs_x_cycles(Variant::Cmos, ASL, 0x0300), 6);
+    assert_eq!(rmw_abs_x_cycles(Variant::Cmos, ROR, 0x0300), 6);
+    assert_eq!(rmw_abs_x_cycles(Variant::Cmos, INC, 0x0300), 7);
+    assert_eq!(rmw_abs_x_cycles(Variant::Cmos, DEC, 0x0300), 7);
+}
+
+#[test]
+fn save_state_round_trip() {
+    fn run(cpu: &mut Cpu, bus: &mut bus::SimpleBus, memory: &mut Memory, cycles: usize) {
+        for _ in 0..cycles {
+            cpu.tick(bus);
+            memory.tick(bus);
+            bus.tick();
+            cpu.tick(bus);
+        }
+    }
+
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = Cpu::new();
+    let mut memory = Memory::default();
+
+    start_at(&mut cpu, &mut bus, 0x0200);
+
+    // LDX #$00, loop: INC $10,X, INX, JMP loop
+    memory.ram[0x0200..0x0208].copy_from_slice(&[0xA2, 0x00, 0xF6, 0x10, 0xE8, 0x4C, 0x02, 0x02]);
+
+    // Stop somewhere in the middle of an instruction
+    run(&mut cpu, &mut bus, &mut memory, 37);
+    let mut state = state::StateWriter::new();
+    cpu.save_state(&mut state);
+    bus.save_state(&mut state);
+    let state = state.into_inner();
+    let saved_ram = memory.ram;
+
+    run(&mut cpu, &mut bus, &mut memory, 100);
+
+    let mut restored_cpu = Cpu::new();
+    let mut restored_bus = bus::SimpleBus::new();
+    let mut restored_memory = Memory { ram: saved_ram };
+    let mut reader = state::StateReader::new(&state);
+    restored_cpu.load_state(&mut reader).unwrap();
+    restored_bus.load_state(&mut reader).unwrap();
+    reader.finish().unwrap();
+
+    run(
+        &mut restored_cpu,
+        &mut restored_bus,
+        &mut restored_memory,
+        100,
+    );
+
+    assert_eq!(TestCpu::state(&restored_cpu), TestCpu::state(&cpu));
+    assert_eq!(restored_memory.ram, memory.ram);
+    assert_eq!(restored_bus.addr(), bus.addr());
+
+    // Truncated and mismatched states are rejected
+    let mut reader = state::StateReader::new(&state[..10]);
+    assert!(Cpu::new().load_state(&mut reader).is_err());
+    let mut reader = state::StateReader::new(&state);
+    assert!(
+        Cpu::with_variant(Variant::Cmos)
+            .load_state(&mut reader)
+            .is_err()
+    );
+}
+
+#[test]
+fn cpu6510_save_state_round_trip() {
+    fn run(
+        cpu: &mut cpu6510::Cpu6510,
+        bus: &mut bus::SimpleBus,
+        memory: &mut Memory,
+        cycles: usize,
+    ) {
+        for _ in 0..cycles {
+            bus.tick();
+            cpu.tick(bus);
+            memory.tick(bus);
+            bus.tick();
+            cpu.tick(bus);
+        }
+    }
+
+    let mut bus = bus::SimpleBus::new();
+    let mut cpu = cpu6510::Cpu6510::new(());
+    let mut memory = Memory::default();
+    cpu.set_fade_cycles(30);
+
+    bus.set_res(true);
+    bus.tick();
+    cpu.tick(&mut bus);
+    bus.set_res(false);
+    memory.ram[0xFFFC..=0xFFFD].copy_from_slice(&[0x00, 0x02]);
+
+    // Charge bits 6-7 then switch them to inputs, and keep reading the port as they fade
+    // LDA #$C0, STA $00, STA $01, LDA #$00, STA $00, loop: LDA $01, STA $10,X, INX, JMP loop
+    #[rustfmt::skip]
+    let program = [
+        0xA9, 0xC0, 0x85, 0x00, 0x85, 0x01,
+        0xA9, 0x00, 0x85, 0x00,
+        0xA5, 0x01, 0x95, 0x10, 0xE8, 0x4C, 0x0A, 0x02,
+    ];
+    memory.ram[0x0200..0x0200 + program.len()].copy_from_slice(&program);
+
+    // Stop while the bits still hold their charge
+    run(&mut cpu, &mut bus, &mut memory, 25);
+    let mut state = state::StateWriter::new();
+    cpu.save_state(&mut state);
+    bus.save_state(&mut state);
+    let state = state.into_inner();
+    let saved_ram = memory.ram;
+
+    run(&mut cpu, &mut bus, &mut memory, 100);
+
+    // The fade timing comes from the state, not the new CPU
+    let mut restored_cpu = cpu6510::Cpu6510::new(());
+    let mut restored_bus = bus::SimpleBus::new();
+    let mut restored_memory = Memory { ram: saved_ram };
+    let mut reader = state::StateReader::new(&state);
+    restored_cpu.load_state(&mut reader).unwrap();
+    restored_bus.load_state(&mut reader).unwrap();
+    reader.finish().unwrap();
+
+    run(
+        &mut restored_cpu,
+        &mut restored_bus,
+        &mut restored_memory,
+        100,
+    );
+
+    assert_eq!(restored_cpu.ddr(), cpu.ddr());
+    assert_eq!(restored_cpu.data(), cpu.data());
+    assert_eq!(restored_memory.ram, memory.ram);
+
+    // The port reads went from charged to faded somewhere along the way
+    let reads = &memory.ram[0x0010..0x0030];
+    assert!(reads.contains(&0xFF) && reads.contains(&0x3F));
+}
+
+// Lays out pieces of code and data in an otherwise blank 256 byte slot ROM
+fn slot_rom(base: u16, parts: &[(u16, &[u8])]) -> Vec<u8> {
+    let mut rom = vec![0; 0x100];
+    for (addr, bytes) in parts {
+        let start = (addr - base) as usize;
+        rom[start..start + bytes.len()].copy_from_slice(bytes);
+    }
+    rom
+}
+
+#[test]
+fn analyze_slot_rom() {
+    use analyzer::{Analyzer, JumpTable, LabelKind, TableFormat};
+
+    // Modelled on the Disk II boot ROM: find the slot through the Monitor's IORTS,
+    // then dispatch commands through a split RTS table
+    let rom = slot_rom(
+        0xC600,
+        &[
+            (0xC600, &[0xA2, 0x20]),       // LDX #$20
+            (0xC602, &[0xA0, 0x00]),       // LDY #$00
+            (0xC604, &[0x20, 0x58, 0xFF]), // JSR IORTS
+            (0xC607, &[0xBA]),             // TSX
+            (0xC608, &[0xBD, 0x00, 0x01]), // LDA $0100,X
+            (0xC60B, &[0x0A]),             // ASL
+            (0xC60C, &[0xAA]),             // TAX
+            (0xC60D, &[0xA0, 0x01]),       // LDY #$01
+            (0xC60F, &[0x20, 0x15, 0xC6]), // JSR DISPATCH
+            (0xC612, &[0x4C, 0x01, 0x08]), // JMP $0801
+            // DISPATCH
+            (0xC615, &[0xB9, 0x2A, 0xC6]), // LDA HI,Y
+            (0xC618, &[0x48]),             // PHA
+            (0xC619, &[0xB9, 0x27, 0xC6]), // LDA LO,Y
+            (0xC61C, &[0x48]),             // PHA
+            (0xC61D, &[0x60]),             // RTS
+            // Commands
+            (0xC61E, &[0xE6, 0x26, 0x60]), // INC $26 / RTS
+            (0xC621, &[0xC6, 0x26, 0x60]), // DEC $26 / RTS
+            (0xC624, &[0xA9, 0x00, 0x60]), // LDA #$00 / RTS
+            // LO and HI
+            (0xC627, &[0x1D, 0x20, 0x23]),
+            (0xC62A, &[0xC6, 0xC6, 0xC6]),
+        ],
+    );
+    let analysis = Analyzer::new(&rom, 0xC600).entry(0xC600, "BOOT").analyze();
+
+    assert_eq!(analysis.labels()[&0xC600].kind, LabelKind::Entry);
+    assert_eq!(analysis.subroutines().collect::<Vec<_>>(), [0xC615, 0xFF58]);
+    assert_eq!(
+        analysis.jump_tables(),
+        [JumpTable {
+            addr: 0xC627,
+            len: 3,
+            format: TableFormat::RtsSplit(0xC62A),
+        }]
+    );
+    for command in [0xC61E, 0xC621, 0xC624] {
+        assert_eq!(analysis.labels()[&command].kind, LabelKind::Branch);
+        assert!(analysis.is_code(command));
+    }
+    assert!(!analysis.is_code(0xC627) && !analysis.is_code(0xC62A));
+
+    let listing = analysis.to_string();
+    assert!(listing.contains("S_FF58     EQU $FF58"), "{listing}");
+    assert!(listing.contains("JMP L_0801"), "{listing}");
+    assert!(listing.contains("LDA T_C62A,Y"), "{listing}");
+}
+
+#[test]
+fn analyze_65c02_slot_rom() {
+    use analyzer::{Analyzer, LabelKind};
+
+    // Instructions the NMOS opcode table sizes (or follows) differently
+    let rom = slot_rom(
+        0xC200,
+        &[
+            (0xC200, &[0x5C, 0x34, 0x12]), // NOP $1234 (3 bytes)
+            (0xC203, &[0xA2, 0x00]),       // LDX #$00
+            (0xC205, &[0x7C, 0x0C, 0xC2]), // JMP (TABLE,X)
+            (0xC20C, &[0x10, 0xC2]),       // TABLE
+            (0xC210, &[0xE8]),             // INX
+            (0xC211, &[0x80, 0xED]),       // BRA $C200
+            (0xC213, &[0xFF]),             // Never reached
+        ],
+    );
+    let analysis = Analyzer::new(&rom, 0xC200)
+        .variant(Variant::Cmos)
+        .entry(0xC200, "ENTRY")
+        .analyze();
+
+    let instrs: Vec<_> = analysis
+        .instructions()
+        .map(|instr| (instr.addr, instr.mnemonic(), instr.len))
+        .collect();
+    assert_eq!(
+        instrs,
+        [
+            (0xC200, "NOP", 3),
+            (0xC203, "LDX", 2),
+            (0xC205, "JMP", 3),
+            (0xC210, "INX", 1),
+            (0xC211, "BRA", 2),
+        ]
+    );
+    assert!(!analysis.is_code(0xC213));
+    assert_eq!(analysis.labels()[&0xC20C].kind, LabelKind::JumpTable);
+    assert_eq!(analysis.labels()[&0xC210].kind, LabelKind::Branch);
+
+    let listing = analysis.to_string();
+    assert!(listing.contains("JMP (T_C20C,X)"), "{listing}");
+    assert!(listing.contains("BRA ENTRY"), "{listing}");
+}
+
+#[test]
+fn analyze_table_stops_at_code() {
+    use analyzer::Analyzer;
+
+    // The guessed length of the split table runs into a routine that was already traced
+    let rom = slot_rom(
+        0xC600,
+        &[
+            (0xC600, &[0xB9, 0x34, 0xC6]), // LDA HI,Y
+            (0xC603, &[0x48]),             // PHA
+            (0xC604, &[0xB9, 0x30, 0xC6]), // LDA LO,Y
+            (0xC607, &[0x48]),             // PHA
+            (0xC608, &[0x60]),             // RTS
+            (0xC609, &[0x60, 0x60, 0x60]), // Commands
+            (0xC630, &[0x08, 0x09, 0x0A]), // LO
+            (0xC633, &[0x60]),             // OTHER: RTS
+            (0xC634, &[0xC6, 0xC6, 0xC6, 0xC6]),
+        ],
+    );
+    let analysis = Analyzer::new(&rom, 0xC600)
+        .entry(0xC600, "DISPATCH")
+        .entry(0xC633, "OTHER")
+        .analyze();
+
+    assert_eq!(analysis.jump_tables()[0].len, 3);
+    assert!(analysis.is_code(0xC633));
+    assert!(!analysis.labels().contains_key(&0xC661));
+    assert!(analysis.to_string().contains("OTHER      RTS"));
+}
+
+#[test]
+fn vcd_recording() {
+    use vcd::VcdRecorder;
+
+    let mut bus = bus::SimpleBus::new();
+    let mut vcd = VcdRecorder::new(Vec::new(), 1_000_000).unwrap();
+
+    // A read cycle, then a write cycle with RES pulled (sampled for both phases each)
+    bus.start_read(0x1234);
+    bus.set_data(0xAB);
+    vcd.sample(&bus).unwrap();
+    vcd.sample(&bus).unwrap();
+    bus.start_write(0x1235, 0xCD);
+    bus.set_res(true);
+    vcd.sample(&bus).unwrap();
+    vcd.sample(&bus).unwrap();
+
+    // Everything is written for the first sample, then only what changed (at 500ns a phase)
+    let out = String::from_utf8(vcd.into_inner()).unwrap();
+    let (header, changes) = out.split_once("$enddefinitions $end\n").unwrap();
+    assert!(header.starts_with("$version grok-6502 $end\n$timescale 1ns $end\n"));
+    assert!(header.contains("$var wire 1 # RES_N $end\n"));
+    assert!(header.contains("$var wire 16 ) A[15:0] $end\n"));
+    assert!(header.contains("$var wire 8 * D[7:0] $end\n"));
+    assert_eq!(
+        changes.lines().collect::<Vec<_>>(),
+        [
+            "#0",
+            "0!",
+            "0\"",
+            "1#",
+            "1$",
+            "1%",
+            "1&",
+            "1'",
+            "1(",
+            "b0001001000110100 )",
+            "b10101011 *",
+            "#500",
+            "1!",
+            "#1000",
+            "0!",
+            "0#",
+            "0(",
+            "b0001001000110101 )",
+            "b11001101 *",
+            "#1500",
+            "1!",
+        ]
+    );
+}
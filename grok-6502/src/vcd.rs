@@ -0,0 +1,153 @@
+//! Value Change Dump (VCD) recording of bus activity.
+//!
+//! Samples the state of a [`Bus`] once per clock phase and writes any changes in the
+//! standard VCD format understood by waveform viewers such as GTKWave.
+//!
+//! Signals are written at their real pin polarity (so RES, IRQ, NMI and S.O. are active low)
+//! which makes it easier to line up recordings against logic analyzer captures of real hardware.
+
+use crate::bus::{Bus, Op};
+use std::io::{self, Write};
+
+// VCD identifier codes for each signal
+mod id {
+    pub const PHI2: char = '!';
+    pub const SYNC: char = '"';
+    pub const RES: char = '#';
+    pub const IRQ: char = '$';
+    pub const NMI: char = '%';
+    pub const SO: char = '&';
+    pub const RDY: char = '\'';
+    pub const RW: char = '(';
+    pub const ADDR: char = ')';
+    pub const DATA: char = '*';
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sample {
+    phi2: bool,
+    sync: bool,
+    res: bool,
+    irq: bool,
+    nmi: bool,
+    so: bool,
+    rdy: bool,
+    rw: bool,
+    addr: u16,
+    data: u8,
+}
+
+impl Sample {
+    fn new(bus: &dyn Bus, phi2: bool) -> Self {
+        Self {
+            phi2,
+            sync: bus.sync(),
+            res: !bus.res(),
+            irq: !bus.irq(),
+            nmi: !bus.nmi(),
+            so: !bus.so(),
+            rdy: bus.rdy(),
+            rw: bus.op() == Op::Read,
+            addr: bus.addr(),
+            data: bus.data(),
+        }
+    }
+}
+
+/// Records bus activity as a Value Change Dump.
+pub struct VcdRecorder<W: Write> {
+    out: W,
+    clk_hz: u64,
+    phase: u64,
+    prev: Option<Sample>,
+}
+
+impl<W: Write> VcdRecorder<W> {
+    /// Create a new recorder for a CPU running at `clk_hz`, writing the VCD header immediately.
+    pub fn new(mut out: W, clk_hz: u32) -> io::Result<Self> {
+        writeln!(out, "$version grok-6502 $end")?;
+        writeln!(out, "$timescale 1ns $end")?;
+        writeln!(out, "$scope module cpu $end")?;
+        for (id, width, name) in [
+            (id::PHI2, 1, "PHI2"),
+            (id::SYNC, 1, "SYNC"),
+            (id::RES, 1, "RES_N"),
+            (id::IRQ, 1, "IRQ_N"),
+            (id::NMI, 1, "NMI_N"),
+            (id::SO, 1, "SO_N"),
+            (id::RDY, 1, "RDY"),
+            (id::RW, 1, "RW"),
+            (id::ADDR, 16, "A[15:0]"),
+            (id::DATA, 8, "D[7:0]"),
+        ] {
+            writeln!(out, "$var wire {width} {id} {name} $end")?;
+        }
+        writeln!(out, "$upscope $end")?;
+        writeln!(out, "$enddefinitions $end")?;
+
+        Ok(Self {
+            out,
+            clk_hz: clk_hz as u64,
+            phase: 0,
+            prev: None,
+        })
+    }
+
+    /// Record the current state of the bus.
+    ///
+    /// This should be called once per clock phase (twice per cycle), after the bus has settled.
+    /// PHI2 is low for the first phase of each cycle and high for the second.
+    pub fn sample(&mut self, bus: &dyn Bus) -> io::Result<()> {
+        let sample = Sample::new(bus, self.phase % 2 == 1);
+
+        if self.prev != Some(sample) {
+            // Work out the timestamp from the phase count each time so rounding errors don't add up
+            let time = self.phase * 1_000_000_000 / (self.clk_hz * 2);
+            writeln!(self.out, "#{time}")?;
+            self.write_changes(sample)?;
+            self.prev = Some(sample);
+        }
+
+        self.phase += 1;
+        Ok(())
+    }
+
+    fn write_changes(&mut self, new: Sample) -> io::Result<()> {
+        let prev = self.prev;
+        let changed = |f: fn(&Sample) -> bool| prev.is_none_or(|p| f(&p) != f(&new));
+
+        for (id, get) in [
+            (id::PHI2, (|s| s.phi2) as fn(&Sample) -> bool),
+            (id::SYNC, |s| s.sync),
+            (id::RES, |s| s.res),
+            (id::IRQ, |s| s.irq),
+            (id::NMI, |s| s.nmi),
+            (id::SO, |s| s.so),
+            (id::RDY, |s| s.rdy),
+            (id::RW, |s| s.rw),
+        ] {
+            if changed(get) {
+                writeln!(self.out, "{}{id}", get(&new) as u8)?;
+            }
+        }
+
+        if prev.is_none_or(|p| p.addr != new.addr) {
+            writeln!(self.out, "b{:016b} {}", new.addr, id::ADDR)?;
+        }
+        if prev.is_none_or(|p| p.data != new.data) {
+            writeln!(self.out, "b{:08b} {}", new.data, id::DATA)?;
+        }
+
+        Ok(())
+    }
+
+    /// Flush any buffered output.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+
+    /// Consume the recorder, returning the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
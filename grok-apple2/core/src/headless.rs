@@ -0,0 +1,248 @@
+//! Support for running without a display or audio device, such as for regression tests in CI.
+//!
+//! Frames come from [`crate::Apple2::video_frame`] (or a peripheral's own video output from
+//! [`crate::Apple2::peripheral_video`]) as `0x00RRGGBB` pixels, and can be written out as
+//! PPM or PNG images for comparing against known good output.
+//! Sound can be written out as a WAV file with [`WavAudio`], either alone or alongside
+//! the host's audio device with [`TeeAudio`].
+
+use crate::Audio;
+use crate::peripheral::VideoFrame;
+use std::io::{self, Seek, SeekFrom, Write};
+
+// Largest amount of data a single uncompressed deflate block can hold
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+// The RIFF chunk size has to fit the rest of the header as well as the samples
+const WAV_HEADER_SIZE: u32 = 36;
+const MAX_WAV_DATA_SIZE: u32 = (u32::MAX - WAV_HEADER_SIZE) & !1;
+
+/// Audio output that throws all samples away.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullAudio;
+
+impl Audio for NullAudio {
+    fn feed_samples(&mut self, _samples: &[f32]) {}
+}
+
+/// Audio output that writes all samples to a 16-bit mono WAV file.
+///
+/// The header is updated after every frame, so the file is always valid even if the emulator
+/// never gets to shut down cleanly. Samples are converted the same way every time,
+/// so the output can be hashed to check for regressions.
+///
+/// A WAV file can't hold more than 4 GiB, so anything past that (over 12 hours at 48 kHz) is dropped.
+pub struct WavAudio<W: Write + Seek> {
+    out: W,
+    sample_rate: u32,
+    data_size: u32,
+    // Samples are fed from inside the emulator, so errors are held onto until asked for
+    error: Option<io::Error>,
+}
+
+impl<W: Write + Seek> WavAudio<W> {
+    /// Start a WAV file at `sample_rate`, which should match any audio output it is teed with.
+    pub fn new(mut out: W, sample_rate: u32) -> io::Result<Self> {
+        out.write_all(b"RIFF")?;
+        out.write_all(&WAV_HEADER_SIZE.to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&sample_rate.to_le_bytes())?;
+        out.write_all(&(sample_rate * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&16u16.to_le_bytes())?;
+
+        out.write_all(b"data")?;
+        out.write_all(&0u32.to_le_bytes())?;
+
+        Ok(WavAudio {
+            out,
+            sample_rate,
+            data_size: 0,
+            error: None,
+        })
+    }
+
+    /// The file being written to.
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Flush the file, returning the first error hit while writing samples (if any).
+    ///
+    /// Once an error is hit, no more samples are written and every flush fails the same way.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if let Some(e) = &self.error {
+            return Err(io::Error::new(e.kind(), e.to_string()));
+        }
+        self.out.flush()
+    }
+
+    fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        let room = ((MAX_WAV_DATA_SIZE - self.data_size) / 2) as usize;
+        let samples = &samples[..samples.len().min(room)];
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = Vec::with_capacity(samples.len() * 2);
+        for sample in samples {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        self.out.write_all(&data)?;
+        self.data_size += data.len() as u32;
+
+        // Patch up the sizes in the header, then carry on from the end
+        self.out.seek(SeekFrom::Start(4))?;
+        self.out
+            .write_all(&(WAV_HEADER_SIZE + self.data_size).to_le_bytes())?;
+        self.out.seek(SeekFrom::Start(40))?;
+        self.out.write_all(&self.data_size.to_le_bytes())?;
+        self.out.seek(SeekFrom::End(0))?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Audio for WavAudio<W> {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn feed_samples(&mut self, samples: &[f32]) {
+        if self.error.is_none()
+            && let Err(e) = self.write_samples(samples)
+        {
+            self.error = Some(e);
+        }
+    }
+}
+
+/// Audio output that sends every sample to two outputs, such as an audio device and a [`WavAudio`].
+///
+/// The first output picks the sample rate and says how much is buffered,
+/// so the second should be created with the same sample rate.
+pub struct TeeAudio<A: Audio, B: Audio>(pub A, pub B);
+
+impl<A: Audio, B: Audio> Audio for TeeAudio<A, B> {
+    fn sample_rate(&self) -> u32 {
+        self.0.sample_rate()
+    }
+
+    fn feed_samples(&mut self, samples: &[f32]) {
+        self.0.feed_samples(samples);
+        self.1.feed_samples(samples);
+    }
+
+    fn buffered(&self) -> Option<usize> {
+        self.0.buffered()
+    }
+}
+
+// The pixels must fill the frame exactly, and there must be some
+fn check_size(frame: &VideoFrame) -> io::Result<()> {
+    let size = frame.width as u64 * frame.height as u64;
+    if size == 0 || frame.pixels.len() as u64 != size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "frame size doesn't match its pixels",
+        ));
+    }
+    Ok(())
+}
+
+fn rgb(frame: &[u32]) -> impl Iterator<Item = [u8; 3]> + '_ {
+    frame.iter().map(|px| {
+        let [_, r, g, b] = px.to_be_bytes();
+        [r, g, b]
+    })
+}
+
+/// Write a frame as a binary PPM (P6) image.
+///
+/// Returns an [`io::ErrorKind::InvalidInput`] error if the pixels don't match the frame's size.
+pub fn write_ppm(frame: VideoFrame, out: &mut impl Write) -> io::Result<()> {
+    check_size(&frame)?;
+    write!(out, "P6\n{} {}\n255\n", frame.width, frame.height)?;
+    for px in rgb(frame.pixels) {
+        out.write_all(&px)?;
+    }
+    Ok(())
+}
+
+/// Write a frame as a PNG image.
+///
+/// The image data is stored uncompressed to avoid pulling in a compression library,
+/// so the files are about the same size as PPM but can be viewed almost anywhere.
+///
+/// Returns an [`io::ErrorKind::InvalidInput`] error if the pixels don't match the frame's size.
+pub fn write_png(frame: VideoFrame, out: &mut impl Write) -> io::Result<()> {
+    check_size(&frame)?;
+    out.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    // 8-bit RGB, no interlacing
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&frame.width.to_be_bytes());
+    ihdr.extend_from_slice(&frame.height.to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_png_chunk(out, b"IHDR", &ihdr)?;
+
+    // Each scanline starts with its filter type (0 = none)
+    let mut raw = Vec::new();
+    for line in frame.pixels.chunks_exact(frame.width as usize) {
+        raw.push(0);
+        raw.extend(rgb(line).flatten());
+    }
+
+    // Wrap it in a zlib stream made up of stored (uncompressed) deflate blocks
+    let mut idat = vec![0x78, 0x01];
+    let blocks = raw.chunks(MAX_STORED_BLOCK);
+    let last = blocks.len() - 1;
+    for (i, block) in blocks.enumerate() {
+        let len = block.len() as u16;
+        idat.push((i == last) as u8);
+        idat.extend_from_slice(&len.to_le_bytes());
+        idat.extend_from_slice(&(!len).to_le_bytes());
+        idat.extend_from_slice(block);
+    }
+    idat.extend_from_slice(&adler32(&raw).to_be_bytes());
+    write_png_chunk(out, b"IDAT", &idat)?;
+
+    write_png_chunk(out, b"IEND", &[])
+}
+
+fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+// Reference: https://www.w3.org/TR/png/#D-CRCAppendix
+pub(crate) fn crc32<'a>(data: impl Iterator<Item = &'a u8>) -> u32 {
+    let crc = data.fold(u32::MAX, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 != 0 {
+                0xEDB88320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            }
+        })
+    });
+    !crc
+}
+
+// Reference: RFC 1950, section 8
+pub(crate) fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
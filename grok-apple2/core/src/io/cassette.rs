@@ -0,0 +1,165 @@
+//! The cassette port: a 1-bit output toggled by $C020, and a 1-bit input read from $C060.
+use crate::mem_map;
+use crate::tape::Tape;
+use grok_6502::bus::Bus;
+use grok_6502::state::{StateReader, StateWriter};
+
+#[derive(Default)]
+pub(crate) struct Cassette {
+    // Playback
+    tape: Option<Tape>,
+    edge_idx: usize,
+    until_edge: u32,
+    input: bool,
+
+    fast_load: bool,
+
+    // Recording
+    output: bool,
+    recording: Option<Vec<u32>>,
+    since_toggle: u32,
+}
+
+impl Cassette {
+    pub(crate) fn play(&mut self, tape: Tape) {
+        self.edge_idx = 0;
+        self.until_edge = tape.edges.first().copied().unwrap_or(0);
+        self.input = false;
+        self.tape = Some(tape);
+    }
+
+    pub(crate) fn stop(&mut self) {
+        self.tape = None;
+    }
+
+    pub(crate) fn is_playing(&self) -> bool {
+        self.tape.is_some()
+    }
+
+    pub(crate) fn set_fast_load(&mut self, enabled: bool) {
+        self.fast_load = enabled;
+    }
+
+    pub(crate) fn fast_load(&self) -> bool {
+        self.fast_load && self.tape.is_some()
+    }
+
+    /// Take the next record straight off the tape (skipping ahead past it), but only if it
+    /// has at least `len` bytes with a good checksum. Otherwise it's left to be read normally.
+    pub(crate) fn take_record(&mut self, len: usize) -> Option<Vec<u8>> {
+        let tape = self.tape.as_ref()?;
+        let mut record = tape.decode_record(self.edge_idx)?;
+        if !record.checksum_ok(len) {
+            return None;
+        }
+
+        // Every edge skipped flips the input
+        self.input ^= (record.end - self.edge_idx) % 2 == 1;
+        self.edge_idx = record.end;
+        match tape.edges.get(self.edge_idx) {
+            Some(&edge) => self.until_edge = edge,
+            None => self.tape = None,
+        }
+
+        record.data.truncate(len);
+        Some(record.data)
+    }
+
+    /// The tape's signal this cycle while it's playing, for listening in on.
+    pub(crate) fn output(&self) -> f32 {
+        match (&self.tape, self.input) {
+            (None, _) => 0.0,
+            (Some(_), true) => 0.5,
+            (Some(_), false) => -0.5,
+        }
+    }
+
+    pub(crate) fn start_recording(&mut self) {
+        self.recording = Some(Vec::new());
+        self.since_toggle = 0;
+    }
+
+    pub(crate) fn stop_recording(&mut self) -> Option<Tape> {
+        self.recording.take().map(Tape::from_edges)
+    }
+
+    pub(crate) fn tick(&mut self) {
+        self.since_toggle = self.since_toggle.saturating_add(1);
+
+        let Some(tape) = &self.tape else {
+            return;
+        };
+
+        // Counting down before checking lands each edge exactly `edge` cycles after the last
+        self.until_edge = self.until_edge.saturating_sub(1);
+        if self.until_edge > 0 {
+            return;
+        }
+
+        self.input = !self.input;
+        self.edge_idx += 1;
+        match tape.edges.get(self.edge_idx) {
+            Some(&edge) => self.until_edge = edge,
+            // Stop once the end of the tape is reached
+            None => self.tape = None,
+        }
+    }
+
+    pub(crate) fn power_on(&mut self) {
+        self.output = false;
+    }
+
+    // The tape being played is saved too, since the position is meaningless without it.
+    // Recording belongs to the host though, so isn't saved.
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.input);
+        state.bool(self.output);
+        state.bool(self.tape.is_some());
+        if let Some(tape) = &self.tape {
+            state.u32(self.edge_idx as u32);
+            state.u32(self.until_edge);
+            state.u32(tape.edges.len() as u32);
+            for &edge in &tape.edges {
+                state.u32(edge);
+            }
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.input = state.bool()?;
+        self.output = state.bool()?;
+        self.tape = None;
+        if state.bool()? {
+            self.edge_idx = state.u32()? as usize;
+            self.until_edge = state.u32()?;
+            let len = state.u32()? as usize;
+            if self.edge_idx >= len {
+                return Err("Save state has an invalid tape position");
+            }
+
+            let edges = state.bytes(len.saturating_mul(4))?;
+            let edges = edges
+                .chunks_exact(4)
+                .map(|edge| u32::from_le_bytes(edge.try_into().unwrap()))
+                .collect();
+            self.tape = Some(Tape::from_edges(edges));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
+        match bus.addr() {
+            // Any access toggles the output
+            mem_map::CASSETTE_TOGGLE..mem_map::SPEAKER => {
+                self.output = !self.output;
+                if let Some(recording) = &mut self.recording {
+                    recording.push(self.since_toggle);
+                }
+                self.since_toggle = 0;
+            }
+
+            // Only bit 7 is driven by the input, and the rest float
+            _ => bus.set_data(((self.input as u8) << 7) | (bus.data() & 0x7F)),
+        }
+    }
+}
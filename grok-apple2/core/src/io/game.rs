@@ -1,16 +1,154 @@
-//! TODO. Just here as a stub to drive pushbutton and controller input low for now
-//! so games don't get confused and think they are pressed.
+//! The game I/O connector: four paddles (read through a 558 quad timer), three pushbuttons
+//! and four annunciator outputs.
+//!
+//! A joystick is just two paddles (X and Y) and a couple of buttons wired to the same connector.
+//! The annunciators are TTL outputs any device on the connector can use, set by $C058-$C05F.
 use crate::mem_map;
 use grok_6502::bus::Bus;
+use grok_6502::state::{StateReader, StateWriter};
+
+pub(crate) const NUM_PADDLES: usize = 4;
+pub(crate) const NUM_BUTTONS: usize = 3;
+pub(crate) const NUM_ANNUNCIATORS: usize = 4;
+
+// How long each paddle's timer runs per step of its position.
+// This matches the 11 cycle loop in the Monitor's PREAD, so it reads back the position it was set to.
+const CYCLES_PER_STEP: u32 = 11;
+
+/// An annunciator output changing state (see [`crate::Apple2::set_annunciator_handler`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnnunciatorEvent {
+    /// Which annunciator changed (0-3).
+    pub annunciator: usize,
+    /// Whether it is now on.
+    pub on: bool,
+    /// The CPU cycle it changed on, counted from power on (see [`crate::Apple2::cycles`]).
+    pub cycle: u64,
+}
 
 #[derive(Default)]
-pub(crate) struct Game {}
+pub(crate) struct Game {
+    paddles: [u8; NUM_PADDLES],
+    timers: [u32; NUM_PADDLES],
+    buttons: [bool; NUM_BUTTONS],
+    shift_key_mod: bool,
+    shift: bool,
+    annunciators: [bool; NUM_ANNUNCIATORS],
+    annunciator_handler: Option<Box<dyn FnMut(AnnunciatorEvent)>>,
+}
 
 impl Game {
-    pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
-        #[allow(clippy::single_match)]
+    pub(crate) fn set_paddle(&mut self, paddle: usize, value: u8) {
+        self.paddles[paddle] = value;
+    }
+
+    pub(crate) fn set_button(&mut self, button: usize, pressed: bool) {
+        self.buttons[button] = pressed;
+    }
+
+    /// The shift key mod wires the shift key to PB2, so programs can tell upper and lowercase
+    /// apart on the II+. With it, PB2 reads low while shift is held and high otherwise.
+    pub(crate) fn set_shift_key_mod(&mut self, enabled: bool) {
+        self.shift_key_mod = enabled;
+    }
+
+    pub(crate) fn set_shift(&mut self, pressed: bool) {
+        self.shift = pressed;
+    }
+
+    pub(crate) fn annunciators(&self) -> [bool; NUM_ANNUNCIATORS] {
+        self.annunciators
+    }
+
+    pub(crate) fn set_annunciator_handler(
+        &mut self,
+        handler: impl FnMut(AnnunciatorEvent) + 'static,
+    ) {
+        self.annunciator_handler = Some(Box::new(handler));
+    }
+
+    pub(crate) fn clear_annunciator_handler(&mut self) {
+        self.annunciator_handler = None;
+    }
+
+    // The annunciator latch isn't connected to RESET, so they only get cleared on power on
+    pub(crate) fn power_on(&mut self) {
+        self.timers = [0; NUM_PADDLES];
+        for annunciator in 0..NUM_ANNUNCIATORS {
+            self.set_annunciator(annunciator, false, 0);
+        }
+    }
+
+    pub(crate) fn tick(&mut self) {
+        for timer in &mut self.timers {
+            *timer = timer.saturating_sub(1);
+        }
+    }
+
+    fn set_annunciator(&mut self, annunciator: usize, on: bool, cycle: u64) {
+        if self.annunciators[annunciator] == on {
+            return;
+        }
+
+        self.annunciators[annunciator] = on;
+        if let Some(handler) = &mut self.annunciator_handler {
+            handler(AnnunciatorEvent {
+                annunciator,
+                on,
+                cycle,
+            });
+        }
+    }
+
+    // Loading doesn't call the annunciator handler, so hosts should check the outputs afterwards
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        for timer in self.timers {
+            state.u32(timer);
+        }
+        for on in self.annunciators {
+            state.bool(on);
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        for timer in &mut self.timers {
+            *timer = state.u32()?;
+        }
+        for on in &mut self.annunciators {
+            *on = state.bool()?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn decode(&mut self, bus: &mut dyn Bus, cycle: u64) {
+        // Only bit 7 is driven by the inputs, and the rest float
+        // ($C068-$C06F mirror $C060-$C067)
         match bus.addr() {
-            mem_map::PUSHBTN_IN..mem_map::TIMER_TRIGGER => bus.set_data(0),
+            // Any access to an even address turns an annunciator off, and odd turns it on
+            mem_map::ANNUNCIATOR..mem_map::CASSETTE_IN => {
+                let addr = bus.addr() - mem_map::ANNUNCIATOR;
+                self.set_annunciator(addr as usize / 2, addr & 1 != 0, cycle);
+            }
+
+            mem_map::CASSETTE_IN..mem_map::TIMER_TRIGGER => {
+                let input = match (bus.addr() - mem_map::CASSETTE_IN) as usize % 8 {
+                    input @ 1..=2 => self.buttons[input - 1],
+                    3 if self.shift_key_mod => self.buttons[2] || !self.shift,
+                    3 => self.buttons[2],
+                    input @ 4.. => self.timers[input - 4] > 0,
+                    // Cassette input
+                    _ => return,
+                };
+                bus.set_data(((input as u8) << 7) | (bus.data() & 0x7F));
+            }
+
+            // Any access to $C07X starts all four timers,
+            // which then each run for a time proportional to the paddle's resistance
+            mem_map::TIMER_TRIGGER.. => {
+                for (timer, paddle) in self.timers.iter_mut().zip(self.paddles) {
+                    *timer = paddle as u32 * CYCLES_PER_STEP;
+                }
+            }
             _ => (),
         }
     }
@@ -1,34 +1,134 @@
 use grok_6502::bus::{self, Bus};
+use grok_6502::state::{StateReader, StateWriter};
+use std::collections::VecDeque;
 
 const DATA: u16 = 0;
 const CLEAR: u16 = 1;
 const KEY_RIGHT: u8 = 0x95;
 const KEY_LEFT: u8 = 0x88;
+const KEY_UP: u8 = 0x8B;
+const KEY_DOWN: u8 = 0x8A;
+
+/// The arrow keys, which don't have an ASCII code.
+///
+/// Up and down are only on the IIe keyboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arrow {
+    Left,
+    Right,
+    Up,
+    Down,
+}
 
 pub(crate) struct Keyboard {
     data: u8,
+    iie: bool,
+    key_down: bool,
+
+    // Keys waiting to be typed, already encoded with the high bit set
+    queue: VecDeque<u8>,
+    type_delay: u32,
+    delay: u32,
 }
 
 impl Keyboard {
-    pub(crate) fn new() -> Self {
-        Keyboard { data: 0 }
+    /// The IIe keyboard encoder adds lowercase, up and down arrows,
+    /// and reports whether any key is held down when reading $C010.
+    pub(crate) fn new(iie: bool) -> Self {
+        Keyboard {
+            data: 0,
+            iie,
+            key_down: false,
+            queue: VecDeque::new(),
+            type_delay: 0,
+            delay: 0,
+        }
+    }
+
+    // RESET doesn't touch the keyboard latch, but it comes up clear on power on
+    pub(crate) fn power_on(&mut self) {
+        self.data = 0;
+        self.key_down = false;
+        self.delay = 0;
     }
 
     pub(crate) fn input(&mut self, char: u8, shift: bool, ctrl: bool) {
-        // Convert lowercase to uppercase
-        let mut ascii = char;
-        if ascii.is_ascii_lowercase() {
-            ascii -= 32;
+        if let Some(data) = self.encode(char, shift, ctrl) {
+            self.data = data;
+            self.key_down = true;
         }
+    }
 
-        // Get the proper ASCII character if shift held
-        if shift {
-            ascii = self.get_shift_ascii(ascii);
+    /// Queue up text to be typed one key at a time, each only once the program has
+    /// cleared the strobe for the last (and `type_delay` cycles have passed since).
+    ///
+    /// Newlines become RETURN, and characters the keyboard can't type are skipped.
+    pub(crate) fn type_text(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\r").replace('\n', "\r");
+        for char in text.chars() {
+            let Ok(ascii) = u8::try_from(char) else {
+                continue;
+            };
+
+            // Control characters are typed as the letter with CTRL held
+            let key = match ascii {
+                0x01..=0x1A if !matches!(ascii, 8 | 9 | 13) => {
+                    self.encode(ascii + b'@', false, true)
+                }
+                _ => self.encode(ascii, false, false),
+            };
+            self.queue.extend(key);
+        }
+    }
+
+    pub(crate) fn set_type_delay(&mut self, cycles: u32) {
+        self.type_delay = cycles;
+    }
+
+    pub(crate) fn is_typing(&self) -> bool {
+        !self.queue.is_empty()
+    }
+
+    pub(crate) fn cancel_typing(&mut self) {
+        self.queue.clear();
+    }
+
+    pub(crate) fn tick(&mut self) {
+        // Wait for the program to read the last key
+        if self.queue.is_empty() || self.data & (1 << 7) != 0 {
+            return;
+        }
+
+        if self.delay > 0 {
+            self.delay -= 1;
+        } else if let Some(data) = self.queue.pop_front() {
+            self.data = data;
+        }
+    }
+
+    // Returns the value to latch (with the high bit set), or `None` if not a valid key
+    fn encode(&self, char: u8, shift: bool, ctrl: bool) -> Option<u8> {
+        let mut ascii = char;
+        if self.iie {
+            // Letters stay lowercase unless shift is held
+            if shift {
+                ascii = self.get_shift_ascii(ascii.to_ascii_uppercase());
+            }
+        } else {
+            // Convert lowercase to uppercase
+            if ascii.is_ascii_lowercase() {
+                ascii -= 32;
+            }
+
+            // Get the proper ASCII character if shift held
+            if shift {
+                ascii = self.get_shift_ascii(ascii);
+            }
         }
 
         // Do nothing if not a valid Apple 2 key
         if !self.is_valid_key(ascii) {
-            return;
+            return None;
         }
 
         // Modify the value (if necessary) when CTRL is held
@@ -37,25 +137,63 @@ impl Keyboard {
         }
 
         // The Apple 2 has the high bit set for ASCII characters
-        self.data = ascii | (1 << 7);
+        Some(ascii | (1 << 7))
+    }
+
+    pub(crate) fn input_arrow(&mut self, arrow: Arrow) {
+        self.data = match arrow {
+            Arrow::Left => KEY_LEFT,
+            Arrow::Right => KEY_RIGHT,
+            Arrow::Up if self.iie => KEY_UP,
+            Arrow::Down if self.iie => KEY_DOWN,
+            _ => return,
+        };
+        self.key_down = true;
+    }
+
+    pub(crate) fn key_released(&mut self) {
+        self.key_down = false;
+    }
+
+    pub(crate) fn latch(&self) -> u8 {
+        self.data
     }
 
-    pub(crate) fn input_arrow(&mut self, right: bool) {
-        let ascii = if right { KEY_RIGHT } else { KEY_LEFT };
-        self.data = ascii;
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.data);
+        state.bool(self.key_down);
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.data = state.u8()?;
+        self.key_down = state.bool()?;
+        Ok(())
     }
 
     pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
         match (bus.addr() >> 4) & 0xF {
             DATA if bus.op() == bus::Op::Read => bus.set_data(self.data),
-            CLEAR => self.data &= !(1 << 7),
+            CLEAR => {
+                self.data &= !(1 << 7);
+                self.delay = self.type_delay;
+
+                // The IIe reports any key down in bit 7 (with the rest of the latch still readable)
+                if self.iie && bus.op() == bus::Op::Read {
+                    bus.set_data(((self.key_down as u8) << 7) | self.data);
+                }
+            }
             _ => (),
         }
     }
 
     fn is_valid_key(&self, ascii: u8) -> bool {
         // 8 = ASCII for backspace, 13 = ASCII for return/enter
-        matches!(ascii, b' '..=b'^' | b'_' | 8 | 13)
+        // The IIe adds lowercase and a few more symbols, as well as tab (9) and delete (127)
+        if self.iie {
+            matches!(ascii, b' '..=b'~' | 8 | 9 | 13 | 127)
+        } else {
+            matches!(ascii, b' '..=b'^' | b'_' | 8 | 13)
+        }
     }
 
     fn get_shift_ascii(&self, ascii: u8) -> u8 {
@@ -79,14 +217,16 @@ impl Keyboard {
             b',' => b'<',
             b'.' => b'>',
             b'/' => b'?',
+            b'\\' if self.iie => b'|',
+            b'`' if self.iie => b'~',
             _ => ascii,
         }
     }
 
     fn get_ctrl_ascii(&self, ascii: u8) -> u8 {
-        // Ctrl only modified A-Z keys by clearing the 6th bit
-        match ascii.is_ascii_uppercase() {
-            true => ascii & !(1 << 6),
+        // Ctrl only modified A-Z keys by clearing the 6th bit (and the 5th for lowercase on the IIe)
+        match ascii.is_ascii_alphabetic() {
+            true => ascii.to_ascii_uppercase() & !(1 << 6),
             false => ascii,
         }
     }
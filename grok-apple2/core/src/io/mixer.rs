@@ -0,0 +1,110 @@
+//! Mixes every sound source together each cycle and resamples the result for the host.
+use super::Audio;
+use crate::settings;
+use grok_6502::state::{StateReader, StateWriter};
+
+const NUM_SOURCES: usize = 3;
+
+/// Something on the Apple II that makes sound, which can have its volume set separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioSource {
+    /// The built-in speaker.
+    Speaker,
+    /// The tape playing into the cassette input (silent by default, like the real thing).
+    Cassette,
+    /// Sound cards and anything else on a peripheral (see [`crate::peripheral::Peripheral::audio_output`]).
+    Peripherals,
+}
+
+pub(crate) struct Mixer<A: Audio> {
+    audio: A,
+    cycles_per_sample: f32,
+    volumes: [f32; NUM_SOURCES],
+    // How far into the current sample we are (in cycles), and the output summed over that time
+    sample_time: f32,
+    sample_sum: f32,
+    // TODO: Remove std dep
+    samples: Vec<f32>,
+}
+
+impl<A: Audio> Mixer<A> {
+    pub(crate) fn new(audio: A) -> Self {
+        let sample_rate = audio.sample_rate().max(1);
+        Mixer {
+            audio,
+            cycles_per_sample: settings::CPU_CLK_SPEED as f32 / sample_rate as f32,
+            volumes: [1.0, 0.0, 1.0],
+            sample_time: 0.0,
+            sample_sum: 0.0,
+            samples: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_volume(&mut self, source: AudioSource, volume: f32) {
+        self.volumes[source as usize] = volume;
+    }
+
+    pub(crate) fn audio(&self) -> &A {
+        &self.audio
+    }
+
+    pub(crate) fn audio_mut(&mut self) -> &mut A {
+        &mut self.audio
+    }
+
+    pub(crate) fn buffered(&self) -> Option<usize> {
+        self.audio.buffered()
+    }
+
+    // Start the next sample afresh, so nothing from before the power cycle bleeds into it
+    pub(crate) fn power_on(&mut self) {
+        self.sample_time = 0.0;
+        self.sample_sum = 0.0;
+    }
+
+    // Each sample is the average output over its whole period (a box filter),
+    // so every click shows up in proportion to how long it lasted instead of aliasing
+    pub(crate) fn tick(&mut self, levels: [f32; NUM_SOURCES]) {
+        let output = levels
+            .iter()
+            .zip(self.volumes)
+            .map(|(level, volume)| level * volume)
+            .sum::<f32>()
+            .clamp(-1.0, 1.0);
+
+        let remaining = self.cycles_per_sample - self.sample_time;
+        if remaining > 1.0 {
+            self.sample_sum += output;
+            self.sample_time += 1.0;
+        } else {
+            // This cycle straddles two samples, so split it between them
+            self.sample_sum += output * remaining;
+            self.samples.push(self.sample_sum / self.cycles_per_sample);
+            self.sample_sum = output * (1.0 - remaining);
+            self.sample_time = 1.0 - remaining;
+        }
+    }
+
+    // Samples are fed to the audio output at the end of every frame, so there are none to save
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.u32(self.sample_time.to_bits());
+        state.u32(self.sample_sum.to_bits());
+    }
+
+    // The host may have picked a different sample rate, so start the next sample afresh if needed
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.sample_time = f32::from_bits(state.u32()?);
+        self.sample_sum = f32::from_bits(state.u32()?);
+        if !(0.0..=self.cycles_per_sample).contains(&self.sample_time) {
+            self.sample_time = 0.0;
+            self.sample_sum = 0.0;
+        }
+        Ok(())
+    }
+
+    // Every frame is fed (even if silent) so the host gets a continuous stream
+    pub(crate) fn feed_samples(&mut self) {
+        self.audio.feed_samples(&self.samples);
+        self.samples.clear();
+    }
+}
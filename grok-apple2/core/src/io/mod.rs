@@ -1,39 +1,83 @@
+pub(crate) mod cassette;
 pub(crate) mod game;
 pub(crate) mod keyboard;
+pub(crate) mod mixer;
 pub(crate) mod speaker;
 pub(crate) mod video;
 
 use crate::mem_map;
 use grok_6502::bus::Bus;
 
+/// Where the emulator's sound goes.
+///
+/// Sound is delivered as a continuous stream of mono samples, with every source
+/// (see [`mixer::AudioSource`]) already mixed together.
 pub trait Audio {
-    fn feed_samples(&mut self, samples: &[bool]);
+    /// The sample rate to generate sound at, which is only asked for once when the machine is created.
+    fn sample_rate(&self) -> u32 {
+        crate::settings::SAMPLE_RATE
+    }
+
+    /// Called at the end of every frame (even when silent) with the samples generated during it,
+    /// in the range -1.0 to 1.0.
+    fn feed_samples(&mut self, samples: &[f32]);
+
+    /// How many samples are waiting to be played, if known.
+    ///
+    /// Hosts can use this to pace emulation to the audio device instead of a timer,
+    /// so it never runs dry or falls behind (see [`crate::Apple2::audio_buffered`]).
+    fn buffered(&self) -> Option<usize> {
+        None
+    }
+}
+
+// No output at all if None, so an output can be optional without changing the machine's type
+impl<A: Audio> Audio for Option<A> {
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Some(audio) => audio.sample_rate(),
+            None => crate::settings::SAMPLE_RATE,
+        }
+    }
+
+    fn feed_samples(&mut self, samples: &[f32]) {
+        if let Some(audio) = self {
+            audio.feed_samples(samples);
+        }
+    }
+
+    fn buffered(&self) -> Option<usize> {
+        self.as_ref().and_then(|audio| audio.buffered())
+    }
 }
 
 pub(crate) struct Io<A: Audio> {
     pub(crate) keyboard: keyboard::Keyboard,
     pub(crate) video: video::Video,
-    pub(crate) speaker: speaker::Speaker<A>,
+    pub(crate) speaker: speaker::Speaker,
+    pub(crate) mixer: mixer::Mixer<A>,
     pub(crate) game: game::Game,
+    pub(crate) cassette: cassette::Cassette,
 }
 
 impl<A: Audio> Io<A> {
-    pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
+    // `cycle` is the machine's cycle count, for timestamping annunciator changes
+    pub(crate) fn decode(&mut self, bus: &mut dyn Bus, cycle: u64) {
         match bus.addr() {
             mem_map::KEYBOARD_EN..mem_map::KEYBOARD_CLR => self.keyboard.decode(bus),
             mem_map::KEYBOARD_CLR..mem_map::CASSETTE_TOGGLE => self.keyboard.decode(bus),
-            mem_map::CASSETTE_TOGGLE..mem_map::SPEAKER => (),
+            mem_map::CASSETTE_TOGGLE..mem_map::SPEAKER => self.cassette.decode(bus),
             mem_map::SPEAKER..mem_map::UTIL_STROBE => self.speaker.decode(),
             mem_map::UTIL_STROBE..mem_map::SCREEN_MODE => (),
             mem_map::SCREEN_MODE..mem_map::ANNUNCIATOR => self.video.decode(bus),
-            mem_map::ANNUNCIATOR..mem_map::CASSETTE_IN => self.game.decode(bus),
-            mem_map::CASSETTE_IN..mem_map::PUSHBTN_IN => (),
-            mem_map::PUSHBTN_IN..mem_map::CONTROLLER_IN => self.game.decode(bus),
-            mem_map::CONTROLLER_IN..mem_map::CASSETTE_IN_ALT => self.game.decode(bus),
-            mem_map::CASSETTE_IN_ALT..mem_map::PUSHBTN_IN_ALT => (),
-            mem_map::PUSHBTN_IN_ALT..mem_map::CONTROLLER_IN_ALT => self.game.decode(bus),
-            mem_map::CONTROLLER_IN_ALT..mem_map::TIMER_TRIGGER => self.game.decode(bus),
-            mem_map::TIMER_TRIGGER.. => self.game.decode(bus),
+            mem_map::ANNUNCIATOR..mem_map::CASSETTE_IN => self.game.decode(bus, cycle),
+            mem_map::CASSETTE_IN..mem_map::PUSHBTN_IN => self.cassette.decode(bus),
+            mem_map::PUSHBTN_IN..mem_map::CONTROLLER_IN => self.game.decode(bus, cycle),
+            mem_map::CONTROLLER_IN..mem_map::CASSETTE_IN_ALT => self.game.decode(bus, cycle),
+            mem_map::CASSETTE_IN_ALT..mem_map::PUSHBTN_IN_ALT => self.cassette.decode(bus),
+            mem_map::PUSHBTN_IN_ALT..mem_map::CONTROLLER_IN_ALT => self.game.decode(bus, cycle),
+            mem_map::CONTROLLER_IN_ALT..mem_map::TIMER_TRIGGER => self.game.decode(bus, cycle),
+            mem_map::TIMER_TRIGGER.. => self.game.decode(bus, cycle),
             _ => unreachable!(),
         }
     }
@@ -1,59 +1,56 @@
-use super::Audio;
 use crate::settings;
+use grok_6502::state::{StateReader, StateWriter};
 
-const CYCLES_PER_SAMPLE: u32 = settings::CPU_CLK_SPEED / settings::SAMPLE_RATE;
+// The speaker is AC coupled, so if left in one position it drifts back to the middle.
+// This is how much of the way it drifts back every cycle, which works out to a time constant of about 20ms.
+const DC_DECAY: f32 = 1.0 / (0.02 * settings::CPU_CLK_SPEED as f32);
 
-pub(crate) struct Speaker<A: Audio> {
-    audio: A,
-    prev_polarity: bool,
+pub(crate) struct Speaker {
     polarity: bool,
-    polarity_change: bool,
-    cycles: u32,
-    // TODO: Remove std dep
-    samples: Vec<bool>,
+    // Where the cone is being pulled back to
+    dc: f32,
+    output: f32,
 }
 
-impl<A: Audio> Speaker<A> {
-    pub(crate) fn new(audio: A) -> Self {
+impl Speaker {
+    pub(crate) fn new() -> Self {
         Speaker {
-            audio,
-            prev_polarity: false,
             polarity: false,
-            polarity_change: false,
-            cycles: 0,
-            samples: Vec::new(),
+            dc: -1.0,
+            output: 0.0,
         }
     }
 
-    pub(crate) fn reset(&mut self) {
-        self.cycles = 0;
-        self.samples.clear();
-        self.polarity_change = false;
-        self.prev_polarity = self.polarity;
+    // The cone starts off at rest against one side, with the flip-flop cleared
+    pub(crate) fn power_on(&mut self) {
+        *self = Speaker::new();
     }
 
     pub(crate) fn tick(&mut self) {
-        self.cycles += 1;
-
-        if self.cycles >= CYCLES_PER_SAMPLE {
-            self.samples.push(self.polarity);
-            self.cycles = 0;
-            if self.polarity != self.prev_polarity {
-                self.polarity_change = true;
-            }
-        }
+        let level = if self.polarity { 1.0 } else { -1.0 };
+        self.dc += (level - self.dc) * DC_DECAY;
+
+        // Halved, since it can swing from one extreme to the other
+        self.output = (level - self.dc) / 2.0;
+    }
+
+    /// Where the speaker cone is this cycle, from -1.0 to 1.0.
+    pub(crate) fn output(&self) -> f32 {
+        self.output
     }
 
     pub(crate) fn decode(&mut self) {
         self.polarity = !self.polarity;
     }
 
-    pub(crate) fn feed_samples(&mut self) {
-        // Note: Only feeding on poalrity change I think is more of an SDL issue and
-        // should likely be removed from here once that is cleaned up
-        if self.polarity_change {
-            self.audio.feed_samples(&self.samples);
-        }
-        self.reset();
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.polarity);
+        state.u32(self.dc.to_bits());
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.polarity = state.bool()?;
+        self.dc = f32::from_bits(state.u32()?);
+        Ok(())
     }
 }
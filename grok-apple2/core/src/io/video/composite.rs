@@ -0,0 +1,170 @@
+//! Renders video the way a color TV sees it, by generating the composite signal one 14M clock at a time
+//! and decoding it again.
+//!
+//! Every dot lasts two 14M clocks, and the color subcarrier lasts four,
+//! so where a dot falls within a subcarrier cycle decides the color it shows up as.
+//! Reference: Understanding the Apple II (Sather), chapter 8
+use super::{BLOCK_COLS, BLOCK_HEIGHT, BLOCK_ROWS, BLOCK_WIDTH, FrameBuf, FrameByte, Renderer};
+use crate::settings;
+
+// 14M clocks per byte, and per color subcarrier cycle
+const SAMPLES_PER_BYTE: usize = 2 * BLOCK_WIDTH;
+const SAMPLES_PER_CYCLE: usize = 4;
+pub(super) const LINE_SAMPLES: usize = BLOCK_COLS * SAMPLES_PER_BYTE;
+pub(super) const LINES: usize = BLOCK_ROWS * BLOCK_HEIGHT;
+
+// Sather 8-20: Each dot is two 14M clocks, and hires bytes with bit 7 set come out one clock late,
+// which stretches the last dot of the byte before it and cuts off the last half of this one.
+// Lores shifts its 4 bits out over and over at 14M, lined up with the subcarrier.
+//
+// This is the video signal before any color burst is added, so is also what a monochrome monitor shows.
+pub(super) fn generate_dots(bytes: &[FrameByte; BLOCK_COLS], dots: &mut [bool; LINE_SAMPLES]) {
+    let mut last = false;
+    for (col, byte) in bytes.iter().enumerate() {
+        let base = col * SAMPLES_PER_BYTE;
+        for i in 0..SAMPLES_PER_BYTE {
+            let x = base + i;
+            dots[x] = match *byte {
+                FrameByte::Lores(color) => (color >> (x % SAMPLES_PER_CYCLE)) & 1 != 0,
+                FrameByte::Hires(byte) if byte & 0x80 != 0 => match i {
+                    0 => last,
+                    i => (byte >> ((i - 1) / 2)) & 1 != 0,
+                },
+                FrameByte::Hires(byte) | FrameByte::Text(byte) => (byte >> (i / 2)) & 1 != 0,
+            };
+        }
+        last = dots[base + SAMPLES_PER_BYTE - 1];
+    }
+}
+
+/// How the composite signal is decoded back into colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CompositeFilter {
+    /// How many 14M clocks brightness is averaged over.
+    /// Four or more filters out the color subcarrier, while less leaves it showing as stripes.
+    pub luma_taps: usize,
+    /// How many 14M clocks color is averaged over, which decides how far colors bleed.
+    /// Multiples of four avoid stripes.
+    pub chroma_taps: usize,
+    /// How strong colors are, where 0.0 is black and white.
+    pub saturation: f32,
+    /// How far (in degrees) to turn the hue knob.
+    pub hue: f32,
+}
+
+impl Default for CompositeFilter {
+    fn default() -> Self {
+        CompositeFilter {
+            luma_taps: 4,
+            chroma_taps: 8,
+            saturation: 1.0,
+            hue: 0.0,
+        }
+    }
+}
+
+/// Renders 560 pixels across (one per 14M clock) from the composite signal itself,
+/// so artifact colors, color bleed and the edges between modes come out just like on a color TV.
+///
+/// Lines without a color burst (text mode) are shown in black and white at full sharpness.
+pub struct CompositeRenderer {
+    filter: CompositeFilter,
+    // The subcarrier at each point of its cycle, pre-rotated by the hue
+    carrier: [(f32, f32); SAMPLES_PER_CYCLE],
+    signal: [bool; LINE_SAMPLES],
+    buf: Vec<u32>,
+}
+
+impl Default for CompositeRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompositeRenderer {
+    /// A renderer using the default [`CompositeFilter`].
+    pub fn new() -> Self {
+        Self::with_filter(CompositeFilter::default())
+    }
+
+    /// A renderer decoding the signal with the given filter.
+    pub fn with_filter(filter: CompositeFilter) -> Self {
+        let mut renderer = CompositeRenderer {
+            filter,
+            carrier: [(0.0, 0.0); SAMPLES_PER_CYCLE],
+            signal: [false; LINE_SAMPLES],
+            buf: vec![0; LINE_SAMPLES * LINES],
+        };
+        renderer.set_filter(filter);
+        renderer
+    }
+
+    /// The filter currently used to decode the signal.
+    pub fn filter(&self) -> CompositeFilter {
+        self.filter
+    }
+
+    /// Change how the signal is decoded, which takes effect from the next frame rendered.
+    pub fn set_filter(&mut self, filter: CompositeFilter) {
+        // Lores color 1 (just the first 14M clock of each cycle) should come out magenta
+        const BASE_HUE: f32 = 33.0;
+
+        self.filter = filter;
+        self.carrier = core::array::from_fn(|i| {
+            let angle = (BASE_HUE + filter.hue + 90.0 * i as f32).to_radians();
+            (angle.cos(), angle.sin())
+        });
+    }
+
+    // Averages over a window centered on x, treating everything off the edges as black
+    fn window(&self, x: usize, taps: usize, weight: impl Fn(usize) -> f32) -> f32 {
+        let taps = taps.max(1);
+        let start = x as isize - (taps / 2) as isize;
+        let sum: f32 = (start..start + taps as isize)
+            .filter(|&x| x >= 0 && (x as usize) < LINE_SAMPLES && self.signal[x as usize])
+            .map(|x| weight(x as usize))
+            .sum();
+        sum / taps as f32
+    }
+
+    fn decode(&self, x: usize, color_burst: bool) -> u32 {
+        if !color_burst {
+            return if self.signal[x] { 0xFFFFFF } else { 0x000000 };
+        }
+
+        let y = self.window(x, self.filter.luma_taps, |_| 1.0);
+
+        let saturation = self.filter.saturation;
+        let taps = self.filter.chroma_taps;
+        let i = saturation * self.window(x, taps, |x| self.carrier[x % SAMPLES_PER_CYCLE].0);
+        let q = saturation * self.window(x, taps, |x| self.carrier[x % SAMPLES_PER_CYCLE].1);
+
+        // YIQ to RGB (FCC NTSC)
+        let r = y + 0.956 * i + 0.621 * q;
+        let g = y - 0.272 * i - 0.647 * q;
+        let b = y - 1.106 * i + 1.703 * q;
+        let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (channel(r) << 16) | (channel(g) << 8) | channel(b)
+    }
+}
+
+impl Renderer for CompositeRenderer {
+    fn width(&self) -> u32 {
+        2 * settings::DISP_WIDTH
+    }
+
+    fn render(&mut self, frame: &FrameBuf) -> &[u32] {
+        for (row, line) in frame.iter().enumerate() {
+            generate_dots(&line.bytes, &mut self.signal);
+            for x in 0..LINE_SAMPLES {
+                self.buf[row * LINE_SAMPLES + x] = self.decode(x, line.color_burst);
+            }
+        }
+
+        &self.buf
+    }
+
+    fn frame(&self) -> &[u32] {
+        &self.buf
+    }
+}
@@ -1,15 +1,29 @@
+mod composite;
+mod monochrome;
 mod ntsc;
+mod rgb;
 
+use crate::{Model, settings};
+pub use composite::{CompositeFilter, CompositeRenderer};
 use grok_6502::bus::Bus;
-use ntsc::Ntsc;
+use grok_6502::state::{StateReader, StateWriter};
+pub use monochrome::{MonochromeRenderer, Phosphor};
+pub use ntsc::NtscRenderer;
+pub use rgb::RgbRenderer;
 
 const BLOCK_ROWS: usize = 24;
 const BLOCK_COLS: usize = 40;
 const BLOCK_WIDTH: usize = 7;
 const BLOCK_HEIGHT: usize = 8;
 const FLASH_FRAMES: u32 = 15;
+const MIXED_TEXT_ROWS: usize = 4;
 
 pub(crate) const CHAR_ROM_SIZE: usize = 0x800;
+pub(crate) const IIE_CHAR_ROM_SIZE: usize = 2 * CHAR_ROM_SIZE;
+// Sather (IIe) 8-30: Only the first 128 characters of the IIe video ROM can be marked as flashing
+const IIE_FLASH_ROM_END: usize = 0x400;
+pub(crate) const TEXT_ROWS: usize = BLOCK_ROWS;
+pub(crate) const TEXT_COLS: usize = BLOCK_COLS;
 pub(crate) const VSCAN_MAX: u16 = 262;
 pub(crate) const HSCAN_MAX: u16 = 65;
 
@@ -24,28 +38,92 @@ mod soft_switch {
     pub const HIRES_MODE: u16 = 0xC057;
 }
 
-type FrameBuf = [[FrameByte; BLOCK_COLS]; BLOCK_ROWS * BLOCK_HEIGHT];
+/// How a character on the text screen is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharStyle {
+    Normal,
+    Inverse,
+    Flash,
+    /// A MouseText glyph on the enhanced IIe, which has no matching character.
+    MouseText,
+}
+
+/// Every visible scanline of a frame, top to bottom.
+pub type FrameBuf = [Scanline; BLOCK_ROWS * BLOCK_HEIGHT];
+
+/// What the video hardware latched during one visible scanline.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Scanline {
+    /// The 40 bytes shifted out across the line, left to right.
+    pub bytes: [FrameByte; BLOCK_COLS],
+    /// Whether the color burst was sent before the line, which is turned off in text mode
+    /// (but not for the text at the bottom of mixed mode).
+    pub color_burst: bool,
+}
 
-// I know enough about how the Apple II actually generates video to know this is not the best approach,
-// and some NTSC specifics have leaked into this, but not enough to know something better just yet
-//
-// Have some ideas, and would be cool to come back and completely decouple frame generation
-// from renderer, and have more accurate NTSC coloring, but probably won't be anytime soon
+/// One byte of video, which is shifted out as 7 dots (bit 0 first).
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum FrameByte {
+pub enum FrameByte {
+    /// The 4-bit color of the lores block, for this half of it.
     Lores(u8),
+    /// A hires byte, where bit 7 delays its dots by half a dot.
     Hires(u8),
+    /// A line of a character's dots, already inverted if need be (bit 7 is always clear).
     Text(u8),
 }
 
 impl FrameByte {
-    fn val(self) -> u8 {
+    /// The byte itself, whatever kind it is.
+    pub fn val(self) -> u8 {
         match self {
             FrameByte::Lores(v) | FrameByte::Hires(v) | FrameByte::Text(v) => v,
         }
     }
 }
 
+/// Turns the bytes latched over a frame into pixels.
+///
+/// Video is generated the same way whichever renderer is used,
+/// so a renderer only decides what the monitor makes of it.
+pub trait Renderer {
+    /// The width of rendered frames, which can be wider than [`settings::DISP_WIDTH`]
+    /// for renderers with finer horizontal resolution. They are always [`settings::DISP_HEIGHT`] tall.
+    fn width(&self) -> u32 {
+        settings::DISP_WIDTH
+    }
+
+    /// Render a frame as a flat buffer of `0x00RRGGBB` pixels.
+    fn render(&mut self, frame: &FrameBuf) -> &[u32];
+
+    /// The last frame rendered.
+    fn frame(&self) -> &[u32];
+}
+
+/// The kinds of monitor there are built-in renderers for (see [`crate::Apple2::set_monitor`]).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Monitor {
+    /// A color TV, approximated by [`NtscRenderer`].
+    #[default]
+    Color,
+    /// A color TV, decoded from the composite signal by [`CompositeRenderer`].
+    Composite,
+    /// An RGB monitor, using [`RgbRenderer`].
+    Rgb,
+    /// A monochrome monitor, using [`MonochromeRenderer`].
+    Monochrome(Phosphor),
+}
+
+impl Monitor {
+    fn renderer(self) -> Box<dyn Renderer> {
+        match self {
+            Monitor::Color => Box::new(NtscRenderer::new()),
+            Monitor::Composite => Box::new(CompositeRenderer::new()),
+            Monitor::Rgb => Box::new(RgbRenderer::new()),
+            Monitor::Monochrome(phosphor) => Box::new(MonochromeRenderer::new(phosphor)),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct Frame {
     buf: FrameBuf,
@@ -54,14 +132,22 @@ struct Frame {
 
 impl Frame {
     fn new() -> Self {
+        let line = Scanline {
+            bytes: [FrameByte::Text(0); BLOCK_COLS],
+            color_burst: false,
+        };
         Frame {
-            buf: [[FrameByte::Text(0); BLOCK_COLS]; BLOCK_ROWS * BLOCK_HEIGHT],
+            buf: [line; BLOCK_ROWS * BLOCK_HEIGHT],
             idx: 0,
         }
     }
 
-    fn latch_next(&mut self, byte: FrameByte) {
-        self.buf[self.row()][self.col()] = byte;
+    fn latch_next(&mut self, byte: FrameByte, color_burst: bool) {
+        let (row, col) = (self.row(), self.col());
+        if col == 0 {
+            self.buf[row].color_burst = color_burst;
+        }
+        self.buf[row].bytes[col] = byte;
 
         self.idx += 1;
         if self.idx >= BLOCK_COLS * BLOCK_ROWS * BLOCK_HEIGHT {
@@ -83,9 +169,10 @@ impl Frame {
 }
 
 pub struct Video {
+    model: Model,
     phase1: bool,
-    char_rom: [u8; CHAR_ROM_SIZE],
-    renderer: Ntsc,
+    char_rom: Vec<u8>,
+    renderer: Box<dyn Renderer>,
     frame: Frame,
     frame_count: u32,
     flash: bool,
@@ -93,21 +180,49 @@ pub struct Video {
     hires_mode: bool,
     mixed_mode: bool,
     use_pg2: bool,
+    store80: bool,
+    altcharset: bool,
+    vbl: bool,
 }
 
 impl Video {
-    pub(crate) fn new(char_rom: [u8; CHAR_ROM_SIZE]) -> Self {
-        // We do this to be consistent with how hires pixels are represented.
-        // Basically, hires bytes represent dots in reverse order,
-        // So we reverse the character rom bytes to match this.
-        //
-        // The idea is we can then just draw text in the same way we draw hires
-        let char_rom = char_rom.map(|byte| byte.reverse_bits() >> 1);
+    /// The character ROM is an image of the video ROM fitted to the model.
+    ///
+    /// The II/II+ ROM holds 64 upright glyphs, and inverse and flashing text is produced by the video hardware.
+    /// The IIe ROM (342-0133 or the enhanced 342-0265) holds 256 glyphs for the primary character set
+    /// followed by 256 for the alternate set, with the dots active low and in the opposite order.
+    /// Inverse characters (and MouseText) are stored as they appear, and clearing bit 7 of a glyph
+    /// in the first 1K makes it flash.
+    pub(crate) fn new(char_rom: &[u8], model: Model) -> Self {
+        let char_rom = if model.is_iie() {
+            // Already in the same order as hires dots, so this just brings the dots back to active high,
+            // keeping bit 7 to mark flashing glyphs (which can't be mistaken for a hires phase shift
+            // since it's cleared again before latching)
+            char_rom
+                .iter()
+                .enumerate()
+                .map(|(i, &byte)| {
+                    let flash = i < IIE_FLASH_ROM_END && byte & 0x80 == 0;
+                    ((flash as u8) << 7) | (!byte & 0x7F)
+                })
+                .collect()
+        } else {
+            // We do this to be consistent with how hires pixels are represented.
+            // Basically, hires bytes represent dots in reverse order,
+            // So we reverse the character rom bytes to match this.
+            //
+            // The idea is we can then just draw text in the same way we draw hires
+            char_rom
+                .iter()
+                .map(|byte| byte.reverse_bits() >> 1)
+                .collect()
+        };
 
         Video {
+            model,
             phase1: true,
             char_rom,
-            renderer: Ntsc::new(),
+            renderer: Monitor::default().renderer(),
             frame: Frame::new(),
             frame_count: 0,
             flash: false,
@@ -115,6 +230,9 @@ impl Video {
             hires_mode: false,
             mixed_mode: false,
             use_pg2: false,
+            store80: false,
+            altcharset: false,
+            vbl: false,
         }
     }
 
@@ -129,18 +247,16 @@ impl Video {
         // which is useful for determining if we are drawing a character or hires byte
         let (v2, v4) = ((vscan >> 5) & 1, (vscan >> 7) & 1);
         let hires_time = (v4 == 0) || (v2 == 0);
+        self.vbl = self.in_vbl(vscan);
 
         if self.phase1 {
             // Sather 3-12
             let addr = self.vram_base_addr(vscan, hscan);
 
-            let addr = if self.mixed_mode && self.hires_mode {
-                if hires_time {
-                    self.vram_hires_addr(addr, vscan)
-                } else {
-                    self.vram_lores_addr(addr, hscan)
-                }
-            } else if self.hires_mode {
+            // Text (including the bottom of mixed mode) is always fetched from the text page,
+            // no matter how HIRES is set
+            let text_time = self.txt_mode || (self.mixed_mode && !hires_time);
+            let addr = if self.hires_mode && !text_time {
                 self.vram_hires_addr(addr, vscan)
             } else {
                 self.vram_lores_addr(addr, hscan)
@@ -152,12 +268,14 @@ impl Video {
             // http://www.deater.net/weave/vmwprod/megademo/vapor_lock.html
             bus.start_read(addr);
         } else if !self.in_hbl(hscan) && !self.in_vbl(vscan) {
+            // The switches are checked every byte, so a mode change shows up from the very next one
+            // (even partway along a scanline)
             let data = bus.data();
 
             if self.txt_mode || (!hires_time && self.mixed_mode) {
                 self.latch_char_byte(data);
             } else if self.hires_mode {
-                self.frame.latch_next(FrameByte::Hires(data));
+                self.latch_byte(FrameByte::Hires(data));
             } else {
                 self.latch_lores_byte(data);
             }
@@ -168,10 +286,7 @@ impl Video {
 
     pub(crate) fn decode(&mut self, bus: &dyn Bus) {
         match bus.addr() {
-            soft_switch::TXT_MODE => {
-                self.txt_mode = true;
-                self.hires_mode = false;
-            }
+            soft_switch::TXT_MODE => self.txt_mode = true,
             soft_switch::GFX_MODE => self.txt_mode = false,
             soft_switch::SINGLE_MODE => self.mixed_mode = false,
             soft_switch::MIXED_MODE => self.mixed_mode = true,
@@ -183,11 +298,104 @@ impl Video {
         }
     }
 
+    // The display soft switches aren't connected to RESET, and come up in whatever state
+    // on power on, so just pick text mode page 1 to match what the firmware sets up anyway
+    pub(crate) fn power_on(&mut self) {
+        self.txt_mode = true;
+        self.hires_mode = false;
+        self.mixed_mode = false;
+        self.use_pg2 = false;
+        self.store80 = false;
+        self.altcharset = false;
+    }
+
+    /// On the IIe, 80STORE turns PAGE2 into a bank switch for display memory
+    /// (see [`crate::mmu`]), so page 1 is always displayed while it is set.
+    pub(crate) fn set_store80(&mut self, store80: bool) {
+        self.store80 = store80;
+    }
+
+    /// On the IIe, ALTCHARSET selects the alternate character set, which trades
+    /// flashing characters for inverse lowercase (and MouseText on the enhanced IIe).
+    pub(crate) fn set_altcharset(&mut self, altcharset: bool) {
+        self.altcharset = altcharset;
+    }
+
+    pub(crate) fn txt_mode(&self) -> bool {
+        self.txt_mode
+    }
+
+    pub(crate) fn mixed_mode(&self) -> bool {
+        self.mixed_mode
+    }
+
+    pub(crate) fn page2(&self) -> bool {
+        self.use_pg2
+    }
+
+    pub(crate) fn hires_mode(&self) -> bool {
+        self.hires_mode
+    }
+
+    pub(crate) fn vbl(&self) -> bool {
+        self.vbl
+    }
+
+    // The frame buffer itself is left out since it is entirely redrawn every frame
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.phase1);
+        state.u32(self.frame.idx as u32);
+        state.u32(self.frame_count);
+        state.bool(self.flash);
+        state.bool(self.txt_mode);
+        state.bool(self.hires_mode);
+        state.bool(self.mixed_mode);
+        state.bool(self.use_pg2);
+        state.bool(self.store80);
+        state.bool(self.altcharset);
+        state.bool(self.vbl);
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.phase1 = state.bool()?;
+        self.frame.idx = state.u32()? as usize;
+        if self.frame.idx >= BLOCK_COLS * BLOCK_ROWS * BLOCK_HEIGHT {
+            return Err("Save state has an invalid frame position");
+        }
+        self.frame_count = state.u32()?;
+        self.flash = state.bool()?;
+        self.txt_mode = state.bool()?;
+        self.hires_mode = state.bool()?;
+        self.mixed_mode = state.bool()?;
+        self.use_pg2 = state.bool()?;
+        self.store80 = state.bool()?;
+        self.altcharset = state.bool()?;
+        self.vbl = state.bool()?;
+        Ok(())
+    }
+
+    pub(crate) fn set_renderer(&mut self, renderer: impl Renderer + 'static) {
+        self.renderer = Box::new(renderer);
+    }
+
+    pub(crate) fn set_monitor(&mut self, monitor: Monitor) {
+        self.renderer = monitor.renderer();
+    }
+
+    pub(crate) fn frame_width(&self) -> u32 {
+        self.renderer.width()
+    }
+
     pub(crate) fn render(&mut self) -> &[u32] {
         self.handle_flash();
         self.renderer.render(&self.frame.buf)
     }
 
+    // The last frame rendered, without rendering a new one
+    pub(crate) fn frame(&self) -> &[u32] {
+        self.renderer.frame()
+    }
+
     fn in_hbl(&self, hscan: u16) -> bool {
         let (h3, h4, h5) = ((hscan >> 3) & 1, (hscan >> 4) & 1, (hscan >> 5) & 1);
 
@@ -202,19 +410,23 @@ impl Video {
         v4 != 0 && v3 != 0
     }
 
+    fn display_pg2(&self) -> bool {
+        self.use_pg2 && !self.store80
+    }
+
     // This builds the upper 3 bits of the video RAM byte we are about to address in hires mode
     fn vram_hires_addr(&self, addr: u16, vscan: u16) -> u16 {
         let (va, vb, vc) = (vscan & 1, (vscan >> 1) & 1, (vscan >> 2) & 1);
 
         // Sather 5-7
-        let page = if self.use_pg2 { 0b10 } else { 0b01 };
+        let page = if self.display_pg2() { 0b10 } else { 0b01 };
         (page << 13) | (vc << 12) | (vb << 11) | (va << 10) | addr
     }
 
     // This builds the upper 3 bits of the video RAM byte we are about to address in lores/text mode
     fn vram_lores_addr(&self, addr: u16, hscan: u16) -> u16 {
         // Sather 5-7
-        let page = if self.use_pg2 { 0b10 } else { 0b01 };
+        let page = if self.display_pg2() { 0b10 } else { 0b01 };
         ((self.in_hbl(hscan) as u16) << 12) | (page << 10) | addr
     }
 
@@ -247,32 +459,94 @@ impl Video {
         (v2 << 9) | (v1 << 8) | (v0 << 7) | (sum << 3) | (h2 << 2) | (h1 << 1) | h0
     }
 
+    // Returns the glyph to draw for a character, and whether it should be drawn inverted
+    pub(crate) fn char_style(&self, byte: u8) -> CharStyle {
+        match self.model {
+            // 7th bit tells us if in normal mode
+            // 6th bit tells us if in flash or inverse mode (if bit 7 is not set)
+            //
+            // The IIe primary set works the same way
+            Model::Apple2 | Model::Apple2Plus => Self::primary_char_style(byte),
+            _ if !self.altcharset => Self::primary_char_style(byte),
+
+            // Alternate set: No flashing, so everything below $80 is inverse (except MouseText)
+            Model::Apple2eEnhanced if (0x40..0x60).contains(&byte) => CharStyle::MouseText,
+            _ if byte < 0x80 => CharStyle::Inverse,
+            _ => CharStyle::Normal,
+        }
+    }
+
+    fn primary_char_style(byte: u8) -> CharStyle {
+        match (byte & (1 << 7) != 0, byte & (1 << 6) != 0) {
+            (true, _) => CharStyle::Normal,
+            (false, true) => CharStyle::Flash,
+            (false, false) => CharStyle::Inverse,
+        }
+    }
+
+    /// Returns the character displayed for a byte of text page memory, along with its style.
+    ///
+    /// MouseText has no equivalent, so is replaced with U+FFFD.
+    pub(crate) fn screen_char(&self, byte: u8) -> (char, CharStyle) {
+        let style = self.char_style(byte);
+
+        // Only the IIe has lowercase, and the II/II+ ignores the upper two bits entirely
+        let code = match style {
+            CharStyle::MouseText => return (char::REPLACEMENT_CHARACTER, style),
+            CharStyle::Flash => byte & 0x3F,
+            _ if self.model.is_iie() => byte & 0x7F,
+            _ => byte & 0x3F,
+        };
+
+        // Codes below $20 are the uppercase letters (and a few symbols) starting at '@'
+        let ascii = if code < 0x20 { code + 0x40 } else { code };
+        (ascii as char, style)
+    }
+
+    /// Returns the address of a row of the text page being displayed,
+    /// or `None` if graphics are being displayed there instead.
+    pub(crate) fn text_row_addr(&self, row: usize) -> Option<u16> {
+        let visible = self.txt_mode || (self.mixed_mode && row >= BLOCK_ROWS - MIXED_TEXT_ROWS);
+        if !visible {
+            return None;
+        }
+
+        // Sather 5-7: Rows are interleaved in groups of 8
+        let page: u16 = if self.display_pg2() { 0x0800 } else { 0x0400 };
+        Some(page + (row % 8) as u16 * 0x80 + (row / 8) as u16 * 0x28)
+    }
+
+    fn char_glyph(&self, byte: u8) -> (usize, bool) {
+        let inverse = match self.char_style(byte) {
+            CharStyle::Normal | CharStyle::MouseText => false,
+            CharStyle::Inverse => true,
+            CharStyle::Flash => self.flash,
+        };
+
+        match self.model {
+            // The upper two bits don't affect the glyph
+            Model::Apple2 | Model::Apple2Plus => ((byte & 0x3F) as usize, inverse),
+            // The IIe ROM already holds inverse glyphs, and marks the ones that flash itself
+            _ if !self.altcharset => (byte as usize, false),
+            _ => (0x100 + byte as usize, false),
+        }
+    }
+
     fn latch_char_byte(&mut self, byte: u8) {
-        // Mask off the upper two bits as they don't affect address
-        // Then multiply by 8 (since each character is represented by 8 bytes)
-        let char_byte_addr = ((byte & 0x3F) as usize * BLOCK_HEIGHT) + self.frame.block_row();
+        // Multiply by 8 since each character is represented by 8 bytes
+        let (glyph, inverse) = self.char_glyph(byte);
+        let char_byte_addr = (glyph * BLOCK_HEIGHT) + self.frame.block_row();
 
-        // 7th bit tells us if in normal mode
-        // 6th bit tells us if in flash or inverse mode (if bit 7 is not set)
-        let char_byte = if (byte & (1 << 7) == 0) && (byte & (1 << 6) == 0 || self.flash) {
+        let rom_byte = self.char_rom[char_byte_addr];
+        let flashing = rom_byte & 0x80 != 0;
+        let char_byte = if inverse || (flashing && self.flash) {
             // We only invert the 7 LSBs because the MSB is unused and don't want it mistaken as a phase shift
-            self.char_rom[char_byte_addr] ^ 0x7F
+            (rom_byte ^ 0x7F) & 0x7F
         } else {
-            self.char_rom[char_byte_addr]
+            rom_byte & 0x7F
         };
 
-        // Still a bit unsure about this one. Apparently Apple II will turn off color burst
-        // for text in mixed mode, but also most TVs couldn't actually switch in time,
-        // so we still see color artifacts on text (even in mixed lores mode).
-        //
-        // So, if we aren't explictly in text mode we treat text as hires so we still get color artifacts later.
-        // This seems to be the most "typical" behavior
-        let byte = if self.txt_mode {
-            FrameByte::Text(char_byte)
-        } else {
-            FrameByte::Hires(char_byte)
-        };
-        self.frame.latch_next(byte);
+        self.latch_byte(FrameByte::Text(char_byte));
     }
 
     fn latch_lores_byte(&mut self, byte: u8) {
@@ -283,7 +557,13 @@ impl Video {
         } else {
             byte >> 4
         };
-        self.frame.latch_next(FrameByte::Lores(byte));
+        self.latch_byte(FrameByte::Lores(byte));
+    }
+
+    // The color burst is only turned off by text mode, so text in mixed mode still gets color
+    // artifacts (which is what most TVs showed anyway, since they couldn't switch in time)
+    fn latch_byte(&mut self, byte: FrameByte) {
+        self.frame.latch_next(byte, !self.txt_mode);
     }
 
     fn handle_flash(&mut self) {
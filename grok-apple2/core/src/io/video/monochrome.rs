@@ -0,0 +1,78 @@
+use super::composite::{LINE_SAMPLES, LINES, generate_dots};
+use super::{FrameBuf, Renderer};
+use crate::settings;
+
+/// The color of a monochrome monitor's phosphor.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Phosphor {
+    /// P1, as on the Apple Monitor /// and most green screens.
+    #[default]
+    Green,
+    /// P3, as on amber monitors.
+    Amber,
+    /// P4, as on black and white TVs and monitors.
+    White,
+}
+
+impl Phosphor {
+    pub(crate) fn color(self) -> u32 {
+        match self {
+            Phosphor::Green => 0x33FF66,
+            Phosphor::Amber => 0xFFB000,
+            Phosphor::White => 0xFFFFFF,
+        }
+    }
+}
+
+/// Shows the raw dots at 560 pixels across (one per 14M clock), as on a monochrome monitor.
+///
+/// Without any color to smear them, hires and text come out much crisper than on a color TV,
+/// while color comes out as its dot pattern.
+pub struct MonochromeRenderer {
+    phosphor: Phosphor,
+    dots: [bool; LINE_SAMPLES],
+    buf: Vec<u32>,
+}
+
+impl MonochromeRenderer {
+    /// A renderer showing dots in the color of the given phosphor.
+    pub fn new(phosphor: Phosphor) -> Self {
+        MonochromeRenderer {
+            phosphor,
+            dots: [false; LINE_SAMPLES],
+            buf: vec![0; LINE_SAMPLES * LINES],
+        }
+    }
+
+    /// The phosphor dots are currently shown in.
+    pub fn phosphor(&self) -> Phosphor {
+        self.phosphor
+    }
+
+    /// Change the phosphor, which takes effect from the next frame rendered.
+    pub fn set_phosphor(&mut self, phosphor: Phosphor) {
+        self.phosphor = phosphor;
+    }
+}
+
+impl Renderer for MonochromeRenderer {
+    fn width(&self) -> u32 {
+        2 * settings::DISP_WIDTH
+    }
+
+    fn render(&mut self, frame: &FrameBuf) -> &[u32] {
+        let color = self.phosphor.color();
+        for (line, pixels) in frame.iter().zip(self.buf.chunks_exact_mut(LINE_SAMPLES)) {
+            generate_dots(&line.bytes, &mut self.dots);
+            for (pixel, &dot) in pixels.iter_mut().zip(&self.dots) {
+                *pixel = if dot { color } else { 0 };
+            }
+        }
+
+        &self.buf
+    }
+
+    fn frame(&self) -> &[u32] {
+        &self.buf
+    }
+}
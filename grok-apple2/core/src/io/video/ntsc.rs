@@ -1,6 +1,6 @@
-use super::{BLOCK_COLS, BLOCK_HEIGHT, BLOCK_ROWS, BLOCK_WIDTH, FrameBuf, FrameByte};
+use super::{BLOCK_COLS, BLOCK_HEIGHT, BLOCK_ROWS, BLOCK_WIDTH, FrameBuf, FrameByte, Renderer};
 
-mod color {
+pub(super) mod color {
     // All
     pub const BLACK: u32 = 0x000000;
     pub const WHITE: u32 = 0xFFFFFF;
@@ -47,27 +47,40 @@ mod color {
     pub const HIRES_GREEN: u32 = 0x68E043;
 }
 
-pub(super) struct Ntsc {
+/// Approximates what a color TV makes of the video signal, including hires color fringing.
+///
+/// This is the default renderer.
+pub struct NtscRenderer {
     buf: [u32; BLOCK_COLS * BLOCK_WIDTH * BLOCK_ROWS * BLOCK_HEIGHT],
 }
 
-impl Ntsc {
-    pub(super) fn new() -> Self {
-        Ntsc {
+impl Default for NtscRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NtscRenderer {
+    pub fn new() -> Self {
+        NtscRenderer {
             buf: [0; BLOCK_COLS * BLOCK_WIDTH * BLOCK_ROWS * BLOCK_HEIGHT],
         }
     }
+}
 
+impl Renderer for NtscRenderer {
     // Note: This is kind of hacky shortcut for picking color, not accurate NTSC emulation
     // Would be cool to revisit sometime though and really nail the NTSC artifacts
-    //
-    // Returns the finished frame as a flat buffer of 0x00RRGGBB pixels
-    // Caller then determines how to ultimately display them
-    pub(super) fn render(&mut self, frame: &FrameBuf) -> &[u32] {
-        for (row, row_cells) in frame.iter().enumerate() {
+    fn render(&mut self, frame: &FrameBuf) -> &[u32] {
+        for (row, line) in frame.iter().enumerate() {
+            let row_cells = &line.bytes;
             for col in 0..BLOCK_COLS {
                 let colors = match row_cells[col] {
-                    FrameByte::Hires(byte) => {
+                    FrameByte::Lores(byte) => Self::map_color_lores(byte),
+                    // Text only gets color artifacts while the color burst is on,
+                    // like the text at the bottom of mixed mode
+                    FrameByte::Text(byte) if !line.color_burst => Self::map_color_text(byte),
+                    FrameByte::Hires(byte) | FrameByte::Text(byte) => {
                         // Neighboring bytes are needed for cross-byte fringing (0 at edges)
                         let left = if col > 0 { row_cells[col - 1].val() } else { 0 };
                         let right = if col < (BLOCK_COLS - 1) {
@@ -77,8 +90,6 @@ impl Ntsc {
                         };
                         Self::map_color_hires(left, byte, right, col)
                     }
-                    FrameByte::Lores(byte) => Self::map_color_lores(byte),
-                    FrameByte::Text(byte) => Self::map_color_text(byte),
                 };
 
                 let base = row * (BLOCK_COLS * BLOCK_WIDTH) + col * BLOCK_WIDTH;
@@ -91,6 +102,12 @@ impl Ntsc {
         &self.buf
     }
 
+    fn frame(&self) -> &[u32] {
+        &self.buf
+    }
+}
+
+impl NtscRenderer {
     fn map_color_hires(left: u8, cur: u8, right: u8, col: usize) -> [u32; BLOCK_WIDTH] {
         // The pixels for this block in order
         let mut pixel_map = [color::BLACK; BLOCK_WIDTH];
@@ -165,6 +182,7 @@ impl Ntsc {
         [color::LORES_MAP[(byte & 0x0F) as usize]; BLOCK_WIDTH]
     }
 
+    // Without a color burst, the TV shows every dot in white (ignoring bit 7)
     fn map_color_text(byte: u8) -> [u32; BLOCK_WIDTH] {
         core::array::from_fn(|i| {
             if byte & (1 << i) != 0 {
@@ -0,0 +1,83 @@
+use super::ntsc::color;
+use super::{BLOCK_COLS, BLOCK_HEIGHT, BLOCK_ROWS, BLOCK_WIDTH, FrameBuf, FrameByte, Renderer};
+
+/// Shows the colors a program meant to draw, as if on an RGB monitor.
+///
+/// Hires dots come out as solid blocks of their color (or white if next to another dot)
+/// without any fringing, and text is always white.
+pub struct RgbRenderer {
+    buf: [u32; BLOCK_COLS * BLOCK_WIDTH * BLOCK_ROWS * BLOCK_HEIGHT],
+}
+
+impl Default for RgbRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RgbRenderer {
+    pub fn new() -> Self {
+        RgbRenderer {
+            buf: [0; BLOCK_COLS * BLOCK_WIDTH * BLOCK_ROWS * BLOCK_HEIGHT],
+        }
+    }
+
+    // Treats the whole line as one run of dots, so neighbors in other bytes are picked up
+    fn render_hires(line: &[FrameByte; BLOCK_COLS], col: usize) -> [u32; BLOCK_WIDTH] {
+        let dot = |x: isize| {
+            if !(0..(BLOCK_COLS * BLOCK_WIDTH) as isize).contains(&x) {
+                return false;
+            }
+            let (col, i) = (x as usize / BLOCK_WIDTH, x as usize % BLOCK_WIDTH);
+            matches!(line[col], FrameByte::Hires(byte) if byte & (1 << i) != 0)
+        };
+
+        let byte = line[col].val();
+        core::array::from_fn(|i| {
+            let x = (col * BLOCK_WIDTH + i) as isize;
+            if !dot(x) {
+                color::BLACK
+            } else if dot(x - 1) || dot(x + 1) {
+                color::WHITE
+            } else {
+                match (byte >> 7 != 0, x % 2 == 0) {
+                    (true, true) => color::HIRES_BLUE,
+                    (true, false) => color::HIRES_ORANGE,
+                    (false, true) => color::HIRES_VIOLET,
+                    (false, false) => color::HIRES_GREEN,
+                }
+            }
+        })
+    }
+}
+
+impl Renderer for RgbRenderer {
+    fn render(&mut self, frame: &FrameBuf) -> &[u32] {
+        for (row, line) in frame.iter().enumerate() {
+            for col in 0..BLOCK_COLS {
+                let colors = match line.bytes[col] {
+                    FrameByte::Hires(_) => Self::render_hires(&line.bytes, col),
+                    FrameByte::Lores(byte) => {
+                        [color::LORES_MAP[(byte & 0x0F) as usize]; BLOCK_WIDTH]
+                    }
+                    FrameByte::Text(byte) => core::array::from_fn(|i| {
+                        if byte & (1 << i) != 0 {
+                            color::WHITE
+                        } else {
+                            color::BLACK
+                        }
+                    }),
+                };
+
+                let base = row * (BLOCK_COLS * BLOCK_WIDTH) + col * BLOCK_WIDTH;
+                self.buf[base..base + BLOCK_WIDTH].copy_from_slice(&colors);
+            }
+        }
+
+        &self.buf
+    }
+
+    fn frame(&self) -> &[u32] {
+        &self.buf
+    }
+}
@@ -1,8 +1,21 @@
+pub mod headless;
 pub mod io;
 mod memory;
+mod mmu;
 pub mod peripheral;
+pub mod tape;
+#[cfg(test)]
+mod tests;
 
 pub use io::Audio;
+pub use io::game::AnnunciatorEvent;
+pub use io::keyboard::Arrow;
+pub use io::mixer::AudioSource;
+pub use io::video::{
+    CharStyle, CompositeFilter, CompositeRenderer, FrameBuf, FrameByte, Monitor,
+    MonochromeRenderer, NtscRenderer, Phosphor, Renderer, RgbRenderer, Scanline,
+};
+pub use memory::RamInit;
 
 mod mem_map {
     pub const RAM: u16 = 0x0000;
@@ -11,6 +24,7 @@ mod mem_map {
     pub const BUILTIN_IO: u16 = 0xC000;
     pub const KEYBOARD_EN: u16 = 0xC000;
     pub const KEYBOARD_CLR: u16 = 0xC010;
+    pub const MMU_STATUS: u16 = 0xC011;
     pub const CASSETTE_TOGGLE: u16 = 0xC020;
     pub const SPEAKER: u16 = 0xC030;
     pub const UTIL_STROBE: u16 = 0xC040;
@@ -27,8 +41,10 @@ mod mem_map {
     // Address space for peripheral card IO
     pub const PERIPHERAL_IO: u16 = 0xC080;
     pub const DEVICE_SELECT: u16 = 0xC080;
+    pub const SLOT1_DEVICE_SELECT: u16 = 0xC090;
     pub const IO_SELECT: u16 = 0xC100;
     pub const IO_STROBE: u16 = 0xC800;
+    pub const IO_STROBE_RESET: u16 = 0xCFFF;
 
     pub const ROM: u16 = 0xD000;
 }
@@ -41,60 +57,290 @@ pub mod settings {
     pub const SAMPLE_RATE: u32 = 44100;
 }
 
-use grok_6502::Cpu;
-use grok_6502::bus::{Bus, SimpleBus};
+use grok_6502::bus::Bus;
+use grok_6502::bus::checked::{CheckedBus, Fault};
+use grok_6502::dma::{Dma, DmaState};
+use grok_6502::state::{StateReader, StateWriter};
+use grok_6502::vcd::VcdRecorder;
+use grok_6502::{Cpu, Variant};
 use io::Io;
+use io::game::{NUM_ANNUNCIATORS, NUM_BUTTONS, NUM_PADDLES};
 use io::keyboard::Keyboard;
+use io::mixer::Mixer;
 use io::speaker::Speaker;
-use io::video::{self, CHAR_ROM_SIZE, Video};
+use io::video::{self, CHAR_ROM_SIZE, IIE_CHAR_ROM_SIZE, TEXT_COLS, TEXT_ROWS, Video};
 use memory::{ROM_SIZE, Ram, Rom};
-use peripheral::{Peripheral, Peripherals};
+use mmu::{CX_ROM_SIZE, IIE_ROM_SIZE, Mmu};
+use peripheral::{Peripheral, Peripherals, Reset, VideoFrame};
+use std::io::Write;
+use tape::Tape;
+
+// Identifies a save state, followed by the version of its layout
+// (which should be bumped whenever anything saved changes)
+const STATE_MAGIC: &[u8; 4] = b"GA2S";
+const STATE_VERSION: u16 = 7;
+
+// The Monitor's tape read routine, which is the same on every model
+const MONITOR_READ: u16 = 0xFEFD;
+const RTS_OPCODE: u8 = 0x60;
+
+// The Monitor's zero page pointers to the start and end of memory to read into
+const A1: u16 = 0x3C;
+const A2: u16 = 0x3E;
+
+/// The model of Apple II being emulated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    /// The original Apple II, with Integer BASIC and the old Monitor ROM.
+    ///
+    /// The old Monitor doesn't autostart, so it drops straight into the monitor on reset
+    /// instead of booting a disk. That is all down to the ROM though, the hardware
+    /// is emulated exactly the same as the II+.
+    Apple2,
+    /// The Apple II+, with Applesoft BASIC and the Autostart ROM.
+    #[default]
+    Apple2Plus,
+    /// The Apple IIe, with 64K of auxiliary memory, a built-in language card,
+    /// lowercase and the alternate character set.
+    Apple2e,
+    /// The enhanced Apple IIe, which swaps the 6502 for a 65C02 and adds MouseText characters.
+    Apple2eEnhanced,
+}
+
+impl Model {
+    /// Returns the expected size of the firmware ROM image.
+    ///
+    /// This covers $D000-$FFFF, except on the IIe where it covers $C000-$FFFF.
+    pub fn rom_size(self) -> usize {
+        if self.is_iie() {
+            IIE_ROM_SIZE
+        } else {
+            ROM_SIZE
+        }
+    }
+
+    /// Returns the expected size of the character ROM image.
+    pub fn char_rom_size(self) -> usize {
+        if self.is_iie() {
+            IIE_CHAR_ROM_SIZE
+        } else {
+            CHAR_ROM_SIZE
+        }
+    }
+
+    /// Returns whether this is a IIe, which has a language card built in
+    /// (so nothing should be inserted in slot 0).
+    pub fn is_iie(self) -> bool {
+        matches!(self, Model::Apple2e | Model::Apple2eEnhanced)
+    }
+
+    fn cpu_variant(self) -> Variant {
+        match self {
+            Model::Apple2eEnhanced => Variant::Cmos,
+            _ => Variant::Nmos,
+        }
+    }
+}
+
+/// Builds an [`Apple2`] of a given [`Model`], checking the ROM images are the right size for it.
+pub struct Apple2Builder<'r> {
+    model: Model,
+    fw_rom: Option<&'r [u8]>,
+    char_rom: Option<&'r [u8]>,
+}
+
+impl<'r> Apple2Builder<'r> {
+    /// Start building a machine of the given model.
+    ///
+    /// There are no ROMs built in, so both [`Apple2Builder::fw_rom`] and
+    /// [`Apple2Builder::char_rom`] must be given before [`Apple2Builder::build`].
+    pub fn new(model: Model) -> Self {
+        Apple2Builder {
+            model,
+            fw_rom: None,
+            char_rom: None,
+        }
+    }
+
+    /// Set the firmware ROM image (see [`Model::rom_size`]).
+    pub fn fw_rom(mut self, fw_rom: &'r [u8]) -> Self {
+        self.fw_rom = Some(fw_rom);
+        self
+    }
+
+    /// Set the character ROM image (see [`Model::char_rom_size`]).
+    pub fn char_rom(mut self, char_rom: &'r [u8]) -> Self {
+        self.char_rom = Some(char_rom);
+        self
+    }
+
+    /// Build the machine, with sound going to `audio`.
+    ///
+    /// Peripherals can be inserted afterwards, then it must be powered on with [`Apple2::init`].
+    ///
+    /// # Errors
+    ///
+    /// - `"Firmware ROM not provided"` or `"Character ROM not provided"` if a ROM wasn't given.
+    /// - `"Firmware ROM is the wrong size for this model"` if it isn't [`Model::rom_size`] bytes.
+    /// - `"Character ROM is the wrong size for this model"` if it isn't
+    ///   [`Model::char_rom_size`] bytes.
+    pub fn build<'a, A: Audio>(self, audio: A) -> Result<Apple2<'a, A>, &'static str> {
+        let fw_rom = self.fw_rom.ok_or("Firmware ROM not provided")?;
+        let char_rom = self.char_rom.ok_or("Character ROM not provided")?;
+
+        if fw_rom.len() != self.model.rom_size() {
+            return Err("Firmware ROM is the wrong size for this model");
+        }
+        if char_rom.len() != self.model.char_rom_size() {
+            return Err("Character ROM is the wrong size for this model");
+        }
+
+        Ok(Apple2::with_model(self.model, fw_rom, char_rom, audio))
+    }
+}
 
 pub struct Apple2<'a, A: Audio> {
-    bus: SimpleBus,
+    model: Model,
+    bus: CheckedBus,
     cpu: Cpu,
+    dma: Dma,
     rom: Rom,
     ram: Ram,
+    mmu: Option<Mmu>,
     io: Io<A>,
     peripherals: Peripherals<'a>,
+    vcd: Option<VcdRecorder<Box<dyn Write>>>,
+    // Recording happens from inside the emulation loop, so errors are held onto until it is stopped
+    vcd_error: Option<std::io::Error>,
+    ram_init: RamInit,
+    reset_needs_ctrl: bool,
+    // CPU cycles run since power on
+    cycles: u64,
 }
 
 impl<'a, A: Audio> Apple2<'a, A> {
+    /// Create an Apple II+ (use [`Apple2Builder`] for other models).
     pub fn new(fw_rom: [u8; ROM_SIZE], char_rom: [u8; CHAR_ROM_SIZE], audio: A) -> Self {
-        let bus = SimpleBus::new();
-        let cpu = Cpu::new();
+        Self::with_model(Model::Apple2Plus, &fw_rom, &char_rom, audio)
+    }
+
+    // ROM sizes have already been checked against the model by this point
+    //
+    // On the IIe, since the language card is built in, nothing should be inserted in slot 0.
+    // Note: Only the memory map is emulated so far, so 80 column text is not displayed.
+    fn with_model(model: Model, fw_rom: &[u8], char_rom: &[u8], audio: A) -> Self {
+        let (fw_rom, mmu) = if model.is_iie() {
+            let (cx_rom, fw_rom) = fw_rom.split_at(CX_ROM_SIZE);
+            (fw_rom, Some(Mmu::new(cx_rom.try_into().unwrap())))
+        } else {
+            (fw_rom, None)
+        };
+
+        let mut bus = CheckedBus::default();
+        bus.add_read_only(mem_map::ROM..=u16::MAX);
+        let cpu = Cpu::with_variant(model.cpu_variant());
 
         let ram = Ram::new();
-        let rom = Rom::new(fw_rom);
+        let rom = Rom::new(fw_rom.try_into().unwrap());
 
-        let video = Video::new(char_rom);
-        let speaker = Speaker::new(audio);
-        let keyboard = Keyboard::new();
+        let video = Video::new(char_rom, model);
+        let speaker = Speaker::new();
+        let mixer = Mixer::new(audio);
+        let keyboard = Keyboard::new(model.is_iie());
         let io = Io {
             keyboard,
             video,
             speaker,
+            mixer,
             game: Default::default(),
+            cassette: Default::default(),
         };
 
         let peripherals = Peripherals::default();
 
         Apple2 {
+            model,
             bus,
             cpu,
+            dma: Dma::new(),
             rom,
             ram,
+            mmu,
             io,
             peripherals,
+            vcd: None,
+            vcd_error: None,
+            ram_init: RamInit::default(),
+            reset_needs_ctrl: model.is_iie(),
+            cycles: 0,
         }
     }
 
+    /// Pull the RESET line, as if the RESET key was pressed (see [`Apple2::reset_key`]).
+    ///
+    /// The CPU, the IIe MMU and every peripheral are reset, but RAM is left alone
+    /// (so the firmware can tell this apart from a power on).
     pub fn reset(&mut self) {
-        self.cpu.reset(&mut self.bus);
+        self.reset_line(Reset::Warm);
+    }
+
+    /// Press the RESET key, with or without CTRL held.
+    ///
+    /// Later II+ keyboards have a switch to require CTRL-RESET so it can't be hit by accident
+    /// (see [`Apple2::set_reset_needs_ctrl`]), and the IIe always requires it.
+    pub fn reset_key(&mut self, ctrl: bool) {
+        if ctrl || !self.reset_needs_ctrl {
+            self.reset();
+        }
+    }
+
+    /// Set the II+ keyboard switch choosing whether RESET needs CTRL held too.
+    ///
+    /// Defaults to off, like the original keyboard. This has no effect on the IIe,
+    /// which always needs CTRL-RESET.
+    pub fn set_reset_needs_ctrl(&mut self, needs_ctrl: bool) {
+        self.reset_needs_ctrl = needs_ctrl || self.model.is_iie();
     }
 
+    /// Turn the machine off and on again (a cold boot).
+    ///
+    /// RAM (including any language card and auxiliary RAM) is filled according to
+    /// [`Apple2::set_ram_init`], and the soft switches go back to their power on defaults.
+    pub fn power_cycle(&mut self) {
+        self.ram.power_on(self.ram_init);
+        if let Some(mmu) = &mut self.mmu {
+            mmu.power_on(self.ram_init);
+        }
+        self.io.video.power_on();
+        self.io.keyboard.power_on();
+        self.io.game.power_on();
+        self.io.cassette.power_on();
+        self.io.speaker.power_on();
+        self.io.mixer.power_on();
+        self.dma = Dma::new();
+        self.cycles = 0;
+
+        self.reset_line(Reset::Cold(self.ram_init));
+    }
+
+    /// Choose what RAM holds after [`Apple2::power_cycle`] (all zeros by default).
+    pub fn set_ram_init(&mut self, init: RamInit) {
+        self.ram_init = init;
+    }
+
+    /// Power on for the first time, once all peripherals have been inserted.
     pub fn init(&mut self) {
-        self.reset();
+        self.power_cycle();
+    }
+
+    fn reset_line(&mut self, reset: Reset) {
+        if let Some(mmu) = &mut self.mmu {
+            mmu.reset();
+            self.io.video.set_store80(false);
+            self.io.video.set_altcharset(false);
+        }
+        self.peripherals.reset(reset);
+        self.cpu.reset(&mut self.bus);
     }
 
     pub fn run_frame(&mut self) -> &[u32] {
@@ -109,43 +355,341 @@ impl<'a, A: Audio> Apple2<'a, A> {
                 // driving the address bus and reading the data bus.
                 //
                 // So: Tick the video twice, then tick cpu twice and handle all other updates there.
-                // It is safe to call `decode` here because video will only put RAM addresses on the bus.
+                // Video only ever puts main RAM addresses on the bus (no matter how the IIe MMU is set up).
+                self.bus.set_device("video");
                 self.io.video.tick(vscan, hscan, &mut self.bus);
-                self.decode();
+                self.ram.decode(&mut self.bus);
                 self.io.video.tick(vscan, hscan, &mut self.bus);
+                let floating = self.bus.data();
 
                 // Tick the CPU one clock phase so it can announce address on the bus
                 self.cpu.tick(&mut self.bus);
 
+                // Unless a peripheral has taken the bus over, in which case it announces instead
+                let dma = self.update_dma();
+                self.record_bus();
+
                 // Then give peripherals a chance to react first in case they need to inhibit ROM
                 self.peripherals.tick(&mut self.bus);
+                if self.peripherals.pins.inh() {
+                    self.bus.claim();
+                }
 
-                // Update the speaker state
+                // Update the speaker, paddle timers and cassette, and type the next queued key if ready
                 self.io.speaker.tick();
+                self.io.keyboard.tick();
+                self.io.game.tick();
+                self.io.cassette.tick();
+
+                // Mix everything making sound this cycle
+                self.io.mixer.tick([
+                    self.io.speaker.output(),
+                    self.io.cassette.output(),
+                    self.peripherals.audio_output(),
+                ]);
 
                 // Update the bus state
                 self.bus.tick();
 
+                // Nothing drives the data bus when reading an address nothing answers to
+                // (like most soft switches and empty slots), so it still holds the byte the video
+                // scanner just fetched. Software can use this to find where the beam is (vapor lock).
+                //
+                // This goes around the bus checks so it still shows up as floating.
+                if self.bus.op() == grok_6502::bus::Op::Read {
+                    self.bus.inner_mut().set_data(floating);
+                }
+
                 // Then decode the address and dispatch to appropriate component
                 self.decode();
+                if self.bus.sync()
+                    && self.bus.addr() == MONITOR_READ
+                    && self.io.cassette.fast_load()
+                {
+                    self.fast_load_tape();
+                }
+                self.record_bus();
+
+                if dma {
+                    self.peripherals.dma_end(&mut self.bus);
+                }
 
                 // Then finally tick the CPU one more clock phase to react to the data bus
                 self.cpu.tick(&mut self.bus);
+                self.cycles += 1;
             }
         }
 
         // We've collected samples during the frame, so feed them to the audio output
-        self.io.speaker.feed_samples();
+        self.io.mixer.feed_samples();
 
         self.io.video.render()
     }
 
+    /// Returns how many CPU cycles have run since power on.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Returns the most recently rendered frame (see [`Apple2::run_frame`]).
+    pub fn frame(&self) -> &[u32] {
+        self.io.video.frame()
+    }
+
+    /// Change how frames are rendered, which takes effect from the next frame.
+    ///
+    /// Frames are rendered with [`NtscRenderer`] unless changed.
+    pub fn set_renderer(&mut self, renderer: impl Renderer + 'static) {
+        self.io.video.set_renderer(renderer);
+    }
+
+    /// Switch to the built-in renderer for a kind of monitor, which takes effect from the next frame.
+    pub fn set_monitor(&mut self, monitor: Monitor) {
+        self.io.video.set_monitor(monitor);
+    }
+
+    /// The width of rendered frames, which depends on the renderer (see [`Renderer::width`]).
+    pub fn frame_width(&self) -> u32 {
+        self.io.video.frame_width()
+    }
+
+    /// The most recently rendered frame along with its size, such as for [`headless::write_png`].
+    pub fn video_frame(&self) -> VideoFrame<'_> {
+        VideoFrame {
+            pixels: self.frame(),
+            width: self.frame_width(),
+            height: settings::DISP_HEIGHT,
+        }
+    }
+
+    /// Run the given number of frames, returning the last one.
+    pub fn run_frames(&mut self, frames: usize) -> &[u32] {
+        for _ in 0..frames {
+            self.run_frame();
+        }
+        self.frame()
+    }
+
+    /// Run frames until `cond` returns true (checked after every frame), giving up after `max_frames`.
+    ///
+    /// Returns how many frames were run, or `None` if it gave up.
+    pub fn run_until(
+        &mut self,
+        max_frames: usize,
+        mut cond: impl FnMut(&Self) -> bool,
+    ) -> Option<usize> {
+        for frame in 1..=max_frames {
+            self.run_frame();
+            if cond(self) {
+                return Some(frame);
+            }
+        }
+        None
+    }
+
+    /// Returns the lines of text currently on screen, regardless of how each character
+    /// is displayed (see [`Apple2::screen_styles`]). Rows showing graphics are empty.
+    ///
+    /// Note: 80 column text is not supported.
+    pub fn screen_text(&self) -> Vec<String> {
+        (0..TEXT_ROWS)
+            .map(|row| self.screen_row(row).map(|(char, _)| char).collect())
+            .collect()
+    }
+
+    /// Returns how each character of [`Apple2::screen_text`] is displayed.
+    pub fn screen_styles(&self) -> Vec<Vec<CharStyle>> {
+        (0..TEXT_ROWS)
+            .map(|row| self.screen_row(row).map(|(_, style)| style).collect())
+            .collect()
+    }
+
+    // The characters on a row of the text screen, or nothing if it shows graphics
+    fn screen_row(&self, row: usize) -> impl Iterator<Item = (char, CharStyle)> + '_ {
+        let addr = self.io.video.text_row_addr(row);
+
+        // Video only ever displays main RAM
+        addr.into_iter().flat_map(move |addr| {
+            (0..TEXT_COLS).map(move |col| self.io.video.screen_char(self.ram[addr as usize + col]))
+        })
+    }
+
+    /// Run frames until `pattern` appears in a line of [`Apple2::screen_text`], giving up after `timeout_frames`.
+    ///
+    /// Returns how many frames were run, or `None` if it timed out.
+    pub fn wait_for_text(&mut self, pattern: &str, timeout_frames: usize) -> Option<usize> {
+        self.run_until(timeout_frames, |apple2| apple2.screen_contains(pattern))
+    }
+
+    /// Whether `pattern` appears in a line of [`Apple2::screen_text`].
+    pub fn screen_contains(&self, pattern: &str) -> bool {
+        self.screen_text().iter().any(|line| line.contains(pattern))
+    }
+
     pub fn input(&mut self, char: u8, shift: bool, ctrl: bool) {
         self.io.keyboard.input(char, shift, ctrl);
     }
 
+    /// Press the left or right arrow key, which every model has.
+    ///
+    /// See [`Apple2::input_arrow_key`] for the IIe's up and down arrows too.
     pub fn input_arrow(&mut self, right: bool) {
-        self.io.keyboard.input_arrow(right);
+        self.input_arrow_key(if right { Arrow::Right } else { Arrow::Left });
+    }
+
+    /// Press an arrow key. Up and down are ignored unless the model has them (the IIe).
+    pub fn input_arrow_key(&mut self, arrow: Arrow) {
+        self.io.keyboard.input_arrow(arrow);
+    }
+
+    /// Let go of all keys, which the IIe can detect.
+    pub fn key_released(&mut self) {
+        self.io.keyboard.key_released();
+    }
+
+    /// Type out text, such as a pasted BASIC listing or a command like `RUN HELLO\n`.
+    ///
+    /// Keys are fed in one at a time, only after the program has read the last one
+    /// (by clearing the strobe at $C010), so nothing gets overwritten before it's seen.
+    /// Newlines become RETURN, and characters the keyboard can't type are skipped.
+    ///
+    /// The queue is host input so isn't part of save states.
+    pub fn type_text(&mut self, text: &str) {
+        self.io.keyboard.type_text(text);
+    }
+
+    /// Wait this many CPU cycles after the strobe is cleared before typing the next queued key.
+    ///
+    /// Defaults to 0 (as fast as the program reads them), but some programs clear the strobe
+    /// without reading the key first, so slowing down typing can stop keys being dropped.
+    pub fn set_type_delay(&mut self, cycles: u32) {
+        self.io.keyboard.set_type_delay(cycles);
+    }
+
+    /// Whether there are still keys queued up by [`Apple2::type_text`].
+    pub fn is_typing(&self) -> bool {
+        self.io.keyboard.is_typing()
+    }
+
+    /// Throw away any keys still queued up by [`Apple2::type_text`].
+    pub fn cancel_typing(&mut self) {
+        self.io.keyboard.cancel_typing();
+    }
+
+    /// Set the position of a paddle (0-3) from 0 to 255.
+    ///
+    /// A joystick is read as two paddles, with X on paddle 0 and Y on paddle 1.
+    pub fn set_paddle(&mut self, paddle: usize, value: u8) {
+        assert!(paddle < NUM_PADDLES, "Paddle must be in range 0-3");
+        self.io.game.set_paddle(paddle, value);
+    }
+
+    /// Press or release a pushbutton (0-2).
+    ///
+    /// On the IIe, buttons 0 and 1 are also the Open Apple and Solid Apple keys.
+    pub fn set_button(&mut self, button: usize, pressed: bool) {
+        assert!(button < NUM_BUTTONS, "Button must be in range 0-2");
+        self.io.game.set_button(button, pressed);
+    }
+
+    /// Install the shift key mod, which lets programs read the shift key through pushbutton 2
+    /// (see [`Apple2::set_shift_key`]).
+    pub fn set_shift_key_mod(&mut self, enabled: bool) {
+        self.io.game.set_shift_key_mod(enabled);
+    }
+
+    /// Hold down or let go of the shift key, which is only visible with the shift key mod.
+    pub fn set_shift_key(&mut self, pressed: bool) {
+        self.io.game.set_shift(pressed);
+    }
+
+    /// The current state of the four annunciator outputs on the game I/O connector.
+    ///
+    /// On the IIe, AN3 doubles as the double hires enable (which is active while AN3 is off).
+    pub fn annunciators(&self) -> [bool; NUM_ANNUNCIATORS] {
+        self.io.game.annunciators()
+    }
+
+    /// Install a handler that is called whenever an annunciator output changes,
+    /// for emulating devices attached to the game I/O connector.
+    ///
+    /// Events are timestamped in CPU cycles so they can be lined up with audio
+    /// (such as for a single bit speech box).
+    pub fn set_annunciator_handler(&mut self, handler: impl FnMut(AnnunciatorEvent) + 'static) {
+        self.io.game.set_annunciator_handler(handler);
+    }
+
+    /// Remove the annunciator handler.
+    pub fn clear_annunciator_handler(&mut self) {
+        self.io.game.clear_annunciator_handler();
+    }
+
+    /// Set the volume of a sound source, where 1.0 is full volume.
+    ///
+    /// Everything starts at full volume, except the cassette which starts silent.
+    pub fn set_volume(&mut self, source: AudioSource, volume: f32) {
+        self.io.mixer.set_volume(source, volume);
+    }
+
+    /// The audio output passed in when the machine was created.
+    pub fn audio(&self) -> &A {
+        self.io.mixer.audio()
+    }
+
+    /// Mutable access to the audio output, such as to flush a [`headless::WavAudio`].
+    pub fn audio_mut(&mut self) -> &mut A {
+        self.io.mixer.audio_mut()
+    }
+
+    /// How many samples are waiting to be played by the audio output, if it knows (see [`Audio::buffered`]).
+    pub fn audio_buffered(&self) -> Option<usize> {
+        self.io.mixer.buffered()
+    }
+
+    /// Start playing a tape into the cassette input from the beginning,
+    /// as if pressing play once `LOAD` (or the Monitor's `R` command) is waiting for it.
+    pub fn play_tape(&mut self, tape: Tape) {
+        self.io.cassette.play(tape);
+    }
+
+    /// Stop playing the current tape.
+    pub fn stop_tape(&mut self) {
+        self.io.cassette.stop();
+    }
+
+    /// Whether a tape is still playing (it stops by itself at the end).
+    pub fn is_tape_playing(&self) -> bool {
+        self.io.cassette.is_playing()
+    }
+
+    /// Load tapes instantly by shortcutting the Monitor's read routine (used by `LOAD` and `R`).
+    ///
+    /// When the routine is called while a tape is playing, the next record is copied straight
+    /// into memory and the routine returns at once. If the record doesn't decode cleanly,
+    /// it's read normally instead. On the IIe, memory is banked just as the routine would see it.
+    pub fn set_tape_fast_load(&mut self, enabled: bool) {
+        self.io.cassette.set_fast_load(enabled);
+    }
+
+    /// Start recording the cassette output, as if pressing record before `SAVE`
+    /// (or the Monitor's `W` command).
+    pub fn start_tape_recording(&mut self) {
+        self.io.cassette.start_recording();
+    }
+
+    /// Stop recording, returning what was recorded (or `None` if not recording).
+    pub fn stop_tape_recording(&mut self) -> Option<Tape> {
+        self.io.cassette.stop_recording()
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Render the video output of the peripheral in a slot,
+    /// or `None` if the slot is empty or the peripheral has no video output of its own.
+    pub fn peripheral_video(&mut self, slot: usize) -> Option<VideoFrame<'_>> {
+        self.peripherals.video_output(slot)
     }
 
     pub fn insert_peripheral(&mut self, peripheral: &'a mut dyn Peripheral, slotno: usize) {
@@ -156,12 +700,261 @@ impl<'a, A: Audio> Apple2<'a, A> {
         self.peripherals.slots[slotno] = Some(peripheral);
     }
 
+    /// Save the state of the whole machine, so emulation can be resumed later with [`Apple2::load_state`].
+    ///
+    /// This should be called between frames. ROMs are not included, and neither is anything
+    /// belonging to the host (such as the audio output or the host side of a serial port).
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        state.bytes(STATE_MAGIC);
+        state.u16(STATE_VERSION);
+        state.u8(self.model as u8);
+        state.u64(self.cycles);
+
+        self.cpu.save_state(&mut state);
+        self.dma.save_state(&mut state);
+        self.bus.inner().save_state(&mut state);
+        self.ram.save_state(&mut state);
+        if let Some(mmu) = &self.mmu {
+            mmu.save_state(&mut state);
+        }
+        self.io.video.save_state(&mut state);
+        self.io.keyboard.save_state(&mut state);
+        self.io.speaker.save_state(&mut state);
+        self.io.mixer.save_state(&mut state);
+        self.io.game.save_state(&mut state);
+        self.io.cassette.save_state(&mut state);
+        self.peripherals.save_state(&mut state);
+
+        state.into_inner()
+    }
+
+    /// Restore a state previously saved by [`Apple2::save_state`].
+    ///
+    /// The machine must be the same model, with the same slots occupied by the same kinds of cards.
+    /// If the state can't be loaded, the machine is left just as it was.
+    pub fn load_state(&mut self, state: &[u8]) -> Result<(), &'static str> {
+        // Components are restored one at a time, so go back to where we were if one fails
+        let backup = self.save_state();
+        self.restore_state(state).inspect_err(|_| {
+            self.restore_state(&backup)
+                .expect("Machine should load its own state");
+        })
+    }
+
+    fn restore_state(&mut self, state: &[u8]) -> Result<(), &'static str> {
+        let mut state = StateReader::new(state);
+        if state.bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err("Not a save state");
+        }
+        if state.u16()? != STATE_VERSION {
+            return Err("Save state version is not supported");
+        }
+        if state.u8()? != self.model as u8 {
+            return Err("Save state is for a different model");
+        }
+        self.cycles = state.u64()?;
+
+        self.cpu.load_state(&mut state)?;
+        self.dma.load_state(&mut state)?;
+        self.bus.inner_mut().load_state(&mut state)?;
+        self.ram.load_state(&mut state)?;
+        if let Some(mmu) = &mut self.mmu {
+            mmu.load_state(&mut state)?;
+        }
+        self.io.video.load_state(&mut state)?;
+        self.io.keyboard.load_state(&mut state)?;
+        self.io.speaker.load_state(&mut state)?;
+        self.io.mixer.load_state(&mut state)?;
+        self.io.game.load_state(&mut state)?;
+        self.io.cassette.load_state(&mut state)?;
+        self.peripherals.load_state(&mut state)?;
+
+        state.finish()
+    }
+
+    // Returns whether a peripheral is bus master this cycle
+    fn update_dma(&mut self) -> bool {
+        // Peripherals assert DMA during their tick, so this reacts to last cycle's request
+        if self.peripherals.pins.dma() {
+            self.dma.request(&mut self.bus);
+        } else if self.dma.state() != DmaState::Idle {
+            self.dma.release(&mut self.bus);
+        }
+
+        let granted = self.dma.tick(&self.cpu, &self.bus);
+        if granted {
+            self.peripherals.dma_start(&mut self.bus);
+        }
+        granted
+    }
+
+    /// Install a handler that is called whenever suspicious bus activity is detected,
+    /// such as two devices driving the data bus at once or a read nothing responded to.
+    ///
+    /// Checking the bus has a small cost, so it is only performed while a handler is installed.
+    pub fn set_bus_fault_handler(&mut self, handler: impl FnMut(Fault) + 'static) {
+        self.bus.set_handler(handler);
+    }
+
+    /// Remove the bus fault handler, disabling bus checking.
+    pub fn clear_bus_fault_handler(&mut self) {
+        self.bus.clear_handler();
+    }
+
+    /// Start recording the CPU bus as a Value Change Dump to the given writer.
+    ///
+    /// Each cycle is sampled twice: once after the CPU drives the address lines
+    /// and once after the addressed device has responded.
+    pub fn start_bus_recording(&mut self, out: Box<dyn Write>) -> std::io::Result<()> {
+        self.vcd_error = None;
+        self.vcd = Some(VcdRecorder::new(out, settings::CPU_CLK_SPEED)?);
+        Ok(())
+    }
+
+    /// Stop recording the CPU bus, flushing any remaining output.
+    ///
+    /// If writing failed while recording, recording stopped there and the error is returned here.
+    pub fn stop_bus_recording(&mut self) -> std::io::Result<()> {
+        if let Some(e) = self.vcd_error.take() {
+            return Err(e);
+        }
+        match self.vcd.take() {
+            Some(mut vcd) => vcd.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn record_bus(&mut self) {
+        // Give up on recording if the writer fails rather than interrupting emulation
+        if let Some(vcd) = &mut self.vcd
+            && let Err(e) = vcd.sample(&self.bus)
+        {
+            self.vcd = None;
+            self.vcd_error = Some(e);
+        }
+    }
+
+    // Called as the CPU fetches the first opcode of the Monitor's read routine,
+    // which reads from the address in A1 up to (and including) the address in A2
+    fn fast_load_tape(&mut self) {
+        // Make sure it's the Monitor ROM about to run, and not a copy of it
+        // (or something else entirely) in language card RAM
+        let lc_ram = self.mmu.as_ref().is_some_and(|mmu| mmu.lc_ram_read());
+        if self.peripherals.pins.inh() || lc_ram {
+            return;
+        }
+
+        let a1 = u16::from_le_bytes([self.peek_ram(A1), self.peek_ram(A1 + 1)]);
+        let a2 = u16::from_le_bytes([self.peek_ram(A2), self.peek_ram(A2 + 1)]);
+        if a2 < a1 || a2 >= mem_map::BUILTIN_IO {
+            return;
+        }
+
+        let len = (a2 - a1) as usize + 1;
+        let Some(data) = self.io.cassette.take_record(len) else {
+            return;
+        };
+        for (addr, byte) in (a1..=a2).zip(data) {
+            self.poke_ram(addr, byte);
+        }
+
+        // Leave A1 just past the end like the routine does, then swap in an RTS to return at once
+        let [lo, hi] = (a2 + 1).to_le_bytes();
+        self.poke_ram(A1, lo);
+        self.poke_ram(A1 + 1, hi);
+        self.bus.set_data(RTS_OPCODE);
+    }
+
+    // Access $0000-$BFFF the way the CPU would see it right now, going through the IIe MMU
+    fn peek_ram(&self, addr: u16) -> u8 {
+        match &self.mmu {
+            Some(mmu) => mmu.peek_ram(addr, &self.ram, &self.io.video),
+            None => self.ram[addr as usize],
+        }
+    }
+
+    fn poke_ram(&mut self, addr: u16, data: u8) {
+        match &mut self.mmu {
+            Some(mmu) => mmu.poke_ram(addr, data, &mut self.ram, &self.io.video),
+            None => self.ram[addr as usize] = data,
+        }
+    }
+
     fn decode(&mut self) {
+        if self.mmu.is_some() {
+            self.decode_iie();
+            return;
+        }
+
         match self.bus.addr() {
-            mem_map::RAM..mem_map::BUILTIN_IO => self.ram.decode(&mut self.bus),
-            mem_map::BUILTIN_IO..mem_map::PERIPHERAL_IO => self.io.decode(&mut self.bus),
+            mem_map::RAM..mem_map::BUILTIN_IO => {
+                self.bus.set_device("ram");
+                self.ram.decode(&mut self.bus);
+            }
+            mem_map::BUILTIN_IO..mem_map::PERIPHERAL_IO => {
+                self.bus.set_device("io");
+                self.io.decode(&mut self.bus, self.cycles);
+            }
             mem_map::PERIPHERAL_IO..mem_map::ROM => self.peripherals.decode(&mut self.bus),
-            mem_map::ROM.. => self.rom.decode(&mut self.bus, &mut self.peripherals.pins),
+            mem_map::ROM.. => {
+                self.bus.set_device("rom");
+                self.rom.decode(&mut self.bus, &mut self.peripherals.pins);
+            }
+        }
+    }
+
+    fn decode_iie(&mut self) {
+        let Some(mmu) = &mut self.mmu else {
+            unreachable!()
+        };
+        let read = self.bus.op() == grok_6502::bus::Op::Read;
+
+        match self.bus.addr() {
+            mem_map::RAM..mem_map::BUILTIN_IO => {
+                self.bus.set_device("ram");
+                mmu.decode_ram(&mut self.bus, &mut self.ram, &self.io.video);
+            }
+            mem_map::KEYBOARD_EN..mem_map::KEYBOARD_CLR if !read => {
+                self.bus.set_device("mmu");
+                mmu.decode_switch(&self.bus);
+                self.io.video.set_store80(mmu.store80());
+                self.io.video.set_altcharset(mmu.altcharset());
+            }
+            mem_map::MMU_STATUS..mem_map::CASSETTE_TOGGLE if read => {
+                self.bus.set_device("mmu");
+                let key_latch = self.io.keyboard.latch();
+                mmu.decode_status(&mut self.bus, &self.io.video, key_latch);
+            }
+            mem_map::BUILTIN_IO..mem_map::PERIPHERAL_IO => {
+                self.bus.set_device("io");
+                self.io.decode(&mut self.bus, self.cycles);
+            }
+            mem_map::DEVICE_SELECT..mem_map::SLOT1_DEVICE_SELECT => {
+                self.bus.set_device("mmu");
+                mmu.decode_lc_switch(&self.bus);
+            }
+            mem_map::SLOT1_DEVICE_SELECT..mem_map::IO_SELECT => {
+                self.peripherals.decode(&mut self.bus)
+            }
+            mem_map::IO_SELECT..mem_map::ROM => {
+                self.bus.set_device("rom");
+                if !mmu.decode_cx_rom(&mut self.bus) {
+                    self.peripherals.decode(&mut self.bus);
+                } else if self.bus.addr() == mem_map::IO_STROBE_RESET {
+                    self.peripherals.release_strobe();
+                }
+            }
+            mem_map::ROM.. => {
+                self.bus.set_device("rom");
+
+                // Language card RAM takes priority over ROM, unless a peripheral inhibited both
+                if !self.peripherals.pins.inh() && mmu.decode_lc(&mut self.bus) {
+                    self.bus.claim();
+                } else {
+                    self.rom.decode(&mut self.bus, &mut self.peripherals.pins);
+                }
+            }
         }
     }
 }
@@ -1,10 +1,45 @@
 use core::ops::{Index, IndexMut};
 use core::slice::SliceIndex;
 use grok_6502::bus::{self, Bus};
+use grok_6502::state::{StateReader, StateWriter};
 
 const RAM_SIZE: usize = 48 * 1024;
 pub(crate) const ROM_SIZE: usize = 12 * 1024;
 
+/// What RAM holds when the machine is powered on.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RamInit {
+    /// All zeros, which keeps runs reproducible.
+    #[default]
+    Zero,
+    /// Pseudo-random bytes generated from the given seed.
+    ///
+    /// Real DRAM comes up holding garbage, so this is handy for catching software
+    /// that relies on memory it never initialised.
+    Random(u64),
+}
+
+impl RamInit {
+    /// Fill the given RAM according to this policy.
+    pub fn fill(self, ram: &mut [u8]) {
+        match self {
+            RamInit::Zero => ram.fill(0),
+            RamInit::Random(seed) => {
+                // Reference: https://prng.di.unimi.it/splitmix64.c
+                let mut state = seed;
+                for chunk in ram.chunks_mut(8) {
+                    state = state.wrapping_add(0x9E3779B97F4A7C15);
+                    let mut z = state;
+                    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+                    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+                    z ^= z >> 31;
+                    chunk.copy_from_slice(&z.to_le_bytes()[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
 pub(crate) struct Ram {
     data: [u8; RAM_SIZE],
 }
@@ -16,12 +51,24 @@ impl Ram {
         }
     }
 
+    pub(crate) fn power_on(&mut self, init: RamInit) {
+        init.fill(&mut self.data);
+    }
+
     pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
         match bus.op() {
             bus::Op::Read => bus.set_data(self[bus.addr() as usize]),
             bus::Op::Write => self[bus.addr() as usize] = bus.data(),
         }
     }
+
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.data);
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        state.fill(&mut self.data)
+    }
 }
 
 impl<I: SliceIndex<[u8]>> Index<I> for Ram {
@@ -0,0 +1,316 @@
+//! Apple IIe memory management unit (MMU) and the soft switches of its I/O unit (IOU).
+//!
+//! On top of the II+ memory map, the IIe adds:
+//! - 64K of auxiliary RAM (the 80 column card slot always has one fitted here) that can be banked
+//!   in over main RAM for reads and writes separately
+//! - A built-in language card, with its own 16K of RAM in both main and auxiliary memory
+//! - Internal ROM at $C100-$CFFF that can be banked in over the peripheral ROM space
+//!
+//! Reference: Sather, Understanding the Apple IIe, Chapter 5
+
+use crate::io::video::Video;
+use crate::memory::{Ram, RamInit};
+use crate::peripheral::language::{BankRam, BankSwitches};
+use grok_6502::bus::{self, Bus};
+use grok_6502::state::{StateReader, StateWriter};
+
+pub(crate) const CX_ROM_SIZE: usize = 0x1000;
+pub(crate) const IIE_ROM_SIZE: usize = CX_ROM_SIZE + crate::memory::ROM_SIZE;
+
+mod soft_switch {
+    // Write only
+    pub const STORE80_OFF: u16 = 0xC000;
+    pub const STORE80_ON: u16 = 0xC001;
+    pub const RAMRD_OFF: u16 = 0xC002;
+    pub const RAMRD_ON: u16 = 0xC003;
+    pub const RAMWRT_OFF: u16 = 0xC004;
+    pub const RAMWRT_ON: u16 = 0xC005;
+    pub const INTCXROM_OFF: u16 = 0xC006;
+    pub const INTCXROM_ON: u16 = 0xC007;
+    pub const ALTZP_OFF: u16 = 0xC008;
+    pub const ALTZP_ON: u16 = 0xC009;
+    pub const SLOTC3ROM_OFF: u16 = 0xC00A;
+    pub const SLOTC3ROM_ON: u16 = 0xC00B;
+    pub const COL80_OFF: u16 = 0xC00C;
+    pub const COL80_ON: u16 = 0xC00D;
+    pub const ALTCHARSET_OFF: u16 = 0xC00E;
+    pub const ALTCHARSET_ON: u16 = 0xC00F;
+
+    // Read only (status in bit 7)
+    pub const RDLCBNK2: u16 = 0xC011;
+    pub const RDLCRAM: u16 = 0xC012;
+    pub const RDRAMRD: u16 = 0xC013;
+    pub const RDRAMWRT: u16 = 0xC014;
+    pub const RDCXROM: u16 = 0xC015;
+    pub const RDALTZP: u16 = 0xC016;
+    pub const RDC3ROM: u16 = 0xC017;
+    pub const RD80STORE: u16 = 0xC018;
+    pub const RDVBLBAR: u16 = 0xC019;
+    pub const RDTEXT: u16 = 0xC01A;
+    pub const RDMIXED: u16 = 0xC01B;
+    pub const RDPAGE2: u16 = 0xC01C;
+    pub const RDHIRES: u16 = 0xC01D;
+    pub const RDALTCHAR: u16 = 0xC01E;
+    pub const RD80COL: u16 = 0xC01F;
+}
+
+const ZERO_PAGE_STACK_END: u16 = 0x0200;
+const TEXT_PAGE1_START: u16 = 0x0400;
+const TEXT_PAGE1_END: u16 = 0x0800;
+const HIRES_PAGE1_START: u16 = 0x2000;
+const HIRES_PAGE1_END: u16 = 0x4000;
+
+const SLOT3_ROM_START: u16 = 0xC300;
+const SLOT3_ROM_END: u16 = 0xC400;
+const EXT_ROM_START: u16 = 0xC800;
+
+pub(crate) struct Mmu {
+    pub(crate) aux_ram: Ram,
+    main_lc_ram: BankRam,
+    aux_lc_ram: BankRam,
+    lc: BankSwitches,
+    cx_rom: [u8; CX_ROM_SIZE],
+    store80: bool,
+    ramrd: bool,
+    ramwrt: bool,
+    intcxrom: bool,
+    altzp: bool,
+    slotc3rom: bool,
+    col80: bool,
+    altcharset: bool,
+    intc8rom: bool,
+}
+
+impl Mmu {
+    pub(crate) fn new(cx_rom: [u8; CX_ROM_SIZE]) -> Self {
+        Mmu {
+            aux_ram: Ram::new(),
+            main_lc_ram: BankRam::new(),
+            aux_lc_ram: BankRam::new(),
+            lc: BankSwitches::new(),
+            cx_rom,
+            store80: false,
+            ramrd: false,
+            ramwrt: false,
+            intcxrom: false,
+            altzp: false,
+            slotc3rom: false,
+            col80: false,
+            altcharset: false,
+            intc8rom: false,
+        }
+    }
+
+    pub(crate) fn power_on(&mut self, init: RamInit) {
+        self.aux_ram.power_on(init);
+        self.main_lc_ram.power_on(init);
+        self.aux_lc_ram.power_on(init);
+    }
+
+    // RESET clears all the MMU switches, and resets the built-in language card
+    pub(crate) fn reset(&mut self) {
+        self.lc.reset();
+
+        self.store80 = false;
+        self.ramrd = false;
+        self.ramwrt = false;
+        self.intcxrom = false;
+        self.altzp = false;
+        self.slotc3rom = false;
+        self.col80 = false;
+        self.altcharset = false;
+        self.intc8rom = false;
+    }
+
+    pub(crate) fn store80(&self) -> bool {
+        self.store80
+    }
+
+    pub(crate) fn altcharset(&self) -> bool {
+        self.altcharset
+    }
+
+    pub(crate) fn lc_ram_read(&self) -> bool {
+        self.lc.ram_read
+    }
+
+    // Whether an access to $0000-$BFFF goes to auxiliary RAM
+    fn aux_selected(&self, addr: u16, write: bool, video: &Video) -> bool {
+        match addr {
+            ..ZERO_PAGE_STACK_END => self.altzp,
+            TEXT_PAGE1_START..TEXT_PAGE1_END if self.store80 => video.page2(),
+            HIRES_PAGE1_START..HIRES_PAGE1_END if self.store80 && video.hires_mode() => {
+                video.page2()
+            }
+            _ if write => self.ramwrt,
+            _ => self.ramrd,
+        }
+    }
+
+    /// Main or auxiliary RAM in $0000-$BFFF.
+    pub(crate) fn decode_ram(&mut self, bus: &mut dyn Bus, main_ram: &mut Ram, video: &Video) {
+        if self.aux_selected(bus.addr(), bus.op() == bus::Op::Write, video) {
+            self.aux_ram.decode(bus);
+        } else {
+            main_ram.decode(bus);
+        }
+    }
+
+    /// Read $0000-$BFFF from wherever the CPU would, without going through the bus.
+    pub(crate) fn peek_ram(&self, addr: u16, main_ram: &Ram, video: &Video) -> u8 {
+        if self.aux_selected(addr, false, video) {
+            self.aux_ram[addr as usize]
+        } else {
+            main_ram[addr as usize]
+        }
+    }
+
+    /// Write $0000-$BFFF wherever the CPU would, without going through the bus.
+    pub(crate) fn poke_ram(&mut self, addr: u16, data: u8, main_ram: &mut Ram, video: &Video) {
+        if self.aux_selected(addr, true, video) {
+            self.aux_ram[addr as usize] = data;
+        } else {
+            main_ram[addr as usize] = data;
+        }
+    }
+
+    /// The write only switches in $C000-$C00F.
+    pub(crate) fn decode_switch(&mut self, bus: &dyn Bus) {
+        match bus.addr() {
+            soft_switch::STORE80_OFF => self.store80 = false,
+            soft_switch::STORE80_ON => self.store80 = true,
+            soft_switch::RAMRD_OFF => self.ramrd = false,
+            soft_switch::RAMRD_ON => self.ramrd = true,
+            soft_switch::RAMWRT_OFF => self.ramwrt = false,
+            soft_switch::RAMWRT_ON => self.ramwrt = true,
+            soft_switch::INTCXROM_OFF => self.intcxrom = false,
+            soft_switch::INTCXROM_ON => self.intcxrom = true,
+            soft_switch::ALTZP_OFF => self.altzp = false,
+            soft_switch::ALTZP_ON => self.altzp = true,
+            soft_switch::SLOTC3ROM_OFF => self.slotc3rom = false,
+            soft_switch::SLOTC3ROM_ON => self.slotc3rom = true,
+            soft_switch::COL80_OFF => self.col80 = false,
+            soft_switch::COL80_ON => self.col80 = true,
+            soft_switch::ALTCHARSET_OFF => self.altcharset = false,
+            soft_switch::ALTCHARSET_ON => self.altcharset = true,
+            _ => (),
+        }
+    }
+
+    /// The status reads in $C011-$C01F.
+    ///
+    /// Only bit 7 holds the status, the rest comes from the keyboard latch.
+    pub(crate) fn decode_status(&self, bus: &mut dyn Bus, video: &Video, key_latch: u8) {
+        let status = match bus.addr() {
+            soft_switch::RDLCBNK2 => self.lc.bank2_active,
+            soft_switch::RDLCRAM => self.lc.ram_read,
+            soft_switch::RDRAMRD => self.ramrd,
+            soft_switch::RDRAMWRT => self.ramwrt,
+            soft_switch::RDCXROM => self.intcxrom,
+            soft_switch::RDALTZP => self.altzp,
+            soft_switch::RDC3ROM => self.slotc3rom,
+            soft_switch::RD80STORE => self.store80,
+            soft_switch::RDVBLBAR => !video.vbl(),
+            soft_switch::RDTEXT => video.txt_mode(),
+            soft_switch::RDMIXED => video.mixed_mode(),
+            soft_switch::RDPAGE2 => video.page2(),
+            soft_switch::RDHIRES => video.hires_mode(),
+            soft_switch::RDALTCHAR => self.altcharset,
+            soft_switch::RD80COL => self.col80,
+            _ => unreachable!(),
+        };
+
+        bus.set_data(((status as u8) << 7) | (key_latch & 0x7F));
+    }
+
+    /// The built-in language card switches in $C080-$C08F.
+    pub(crate) fn decode_lc_switch(&mut self, bus: &dyn Bus) {
+        self.lc.decode(bus);
+    }
+
+    /// Internal ROM in $C100-$CFFF, returning whether it responded (instead of a peripheral).
+    pub(crate) fn decode_cx_rom(&mut self, bus: &mut dyn Bus) -> bool {
+        let addr = bus.addr();
+
+        // Accessing the internal slot 3 ROM also banks in the rest of the internal ROM
+        // at $C800-$CFFF, until $CFFF is accessed
+        let slot3 = (SLOT3_ROM_START..SLOT3_ROM_END).contains(&addr);
+        if slot3 && !self.slotc3rom {
+            self.intc8rom = true;
+        }
+
+        let internal =
+            self.intcxrom || (slot3 && !self.slotc3rom) || (addr >= EXT_ROM_START && self.intc8rom);
+
+        if addr == crate::mem_map::IO_STROBE_RESET {
+            self.intc8rom = false;
+        }
+
+        if internal && bus.op() == bus::Op::Read {
+            bus.set_data(self.cx_rom[(addr - crate::mem_map::BUILTIN_IO) as usize]);
+        }
+        internal
+    }
+
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        self.aux_ram.save_state(state);
+        self.main_lc_ram.save_state(state);
+        self.aux_lc_ram.save_state(state);
+        self.lc.save_state(state);
+        for switch in [
+            self.store80,
+            self.ramrd,
+            self.ramwrt,
+            self.intcxrom,
+            self.altzp,
+            self.slotc3rom,
+            self.col80,
+            self.altcharset,
+            self.intc8rom,
+        ] {
+            state.bool(switch);
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.aux_ram.load_state(state)?;
+        self.main_lc_ram.load_state(state)?;
+        self.aux_lc_ram.load_state(state)?;
+        self.lc.load_state(state)?;
+        for switch in [
+            &mut self.store80,
+            &mut self.ramrd,
+            &mut self.ramwrt,
+            &mut self.intcxrom,
+            &mut self.altzp,
+            &mut self.slotc3rom,
+            &mut self.col80,
+            &mut self.altcharset,
+            &mut self.intc8rom,
+        ] {
+            *switch = state.bool()?;
+        }
+        Ok(())
+    }
+
+    /// Language card RAM in $D000-$FFFF, returning whether it responded (instead of ROM).
+    pub(crate) fn decode_lc(&mut self, bus: &mut dyn Bus) -> bool {
+        let ram = if self.altzp {
+            &mut self.aux_lc_ram
+        } else {
+            &mut self.main_lc_ram
+        };
+
+        match bus.op() {
+            bus::Op::Read if self.lc.ram_read => {
+                ram.read(bus, self.lc.bank2_active);
+                true
+            }
+            bus::Op::Write if self.lc.ram_write => {
+                ram.write(bus, self.lc.bank2_active);
+                true
+            }
+            _ => false,
+        }
+    }
+}
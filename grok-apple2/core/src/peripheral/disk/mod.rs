@@ -2,12 +2,12 @@
 TODO:
 -Handle 2nd disc
 -Handle writes
--Handle proper reset behavior
 */
 
 mod dsk2woz;
 mod woz;
 
+use super::{Reset, StateReader, StateWriter};
 use grok_6502::bus::Bus;
 use woz::WozImage;
 
@@ -305,4 +305,86 @@ impl crate::peripheral::Peripheral for ControllerCard {
     fn io_strobe(&mut self, _bus: &mut dyn Bus, _pins: &mut super::Pins) {
         // Intentionally do nothing
     }
+
+    // RESET clears the latch holding the soft switches, so the phases and drives turn off
+    // straight away (without the usual motor off delay) and drive 1 is selected in read mode.
+    // The head stays wherever it was.
+    fn reset(&mut self, _reset: Reset) {
+        self.phases = [false; MAX_PHASE + 1];
+        self.drives_on = false;
+        self.motor_off_delay = 0;
+        self.current_drive = 1;
+        self.write_mode = false;
+        self.write_sense = false;
+    }
+
+    // The disk image is saved too, since the state is meaningless without the disk it was taken with
+    fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.data_reg);
+        state.u8(self.half_track);
+        state.u8(self.current_phase as u8);
+        for phase in self.phases {
+            state.bool(phase);
+        }
+        state.u32(self.bit_pntr as u32);
+        state.bool(self.reading_byte);
+        state.bool(self.drives_on);
+        state.u8(self.current_drive);
+        state.bool(self.write_mode);
+        state.bool(self.write_sense);
+        state.u32(self.motor_off_delay as u32);
+
+        state.bool(self.disk_image.is_some());
+        if let Some(disk_image) = &self.disk_image {
+            disk_image.save_state(state);
+        }
+    }
+
+    // Everything is read and checked before any of it is used, so a bad state leaves the card as it was
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        let data_reg = state.u8()?;
+        let half_track = state.u8()?;
+        let current_phase = state.u8()? as usize;
+        let mut phases = [false; MAX_PHASE + 1];
+        for phase in &mut phases {
+            *phase = state.bool()?;
+        }
+        let bit_pntr = state.u32()? as usize;
+        let reading_byte = state.bool()?;
+        let drives_on = state.bool()?;
+        let current_drive = state.u8()?;
+        let write_mode = state.bool()?;
+        let write_sense = state.bool()?;
+        let motor_off_delay = state.u32()? as usize;
+
+        let disk_image = if state.bool()? {
+            Some(WozImage::load_state(state)?)
+        } else {
+            None
+        };
+
+        if half_track > MAX_TRACK * 2 || current_phase > MAX_PHASE {
+            return Err("Save state has an invalid disk head position");
+        }
+        if let Some(disk_image) = &disk_image {
+            let track = disk_image.tracks.get((half_track / 2) as usize);
+            if track.is_none_or(|track| bit_pntr >= track.bit_count as usize) {
+                return Err("Save state has an invalid disk head position");
+            }
+        }
+
+        self.data_reg = data_reg;
+        self.half_track = half_track;
+        self.current_phase = current_phase;
+        self.phases = phases;
+        self.bit_pntr = bit_pntr;
+        self.reading_byte = reading_byte;
+        self.drives_on = drives_on;
+        self.current_drive = current_drive;
+        self.write_mode = write_mode;
+        self.write_sense = write_sense;
+        self.motor_off_delay = motor_off_delay;
+        self.disk_image = disk_image;
+        Ok(())
+    }
 }
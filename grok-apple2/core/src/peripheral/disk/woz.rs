@@ -4,6 +4,7 @@ Reference: https://applesaucefdc.com/woz/reference2
 */
 
 use super::dsk2woz;
+use crate::peripheral::{StateReader, StateWriter};
 
 const WOZ_IMG_SIZE: usize = 250000;
 
@@ -119,6 +120,37 @@ impl WozImage {
         Self::new_inner(&woz_buf)
     }
 
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.write_protected);
+        state.u8(self.tracks.len() as u8);
+        for track in &self.tracks {
+            state.u32(track.bit_count);
+            state.blob(&track.data);
+        }
+    }
+
+    pub(crate) fn load_state(state: &mut StateReader) -> Result<Self, &'static str> {
+        let write_protected = state.bool()?;
+        let mut tracks = Vec::new();
+        for _ in 0..state.u8()? {
+            let bit_count = state.u32()?;
+            let data = state.blob()?.to_vec();
+            if bit_count == 0 || bit_count as usize > data.len() * 8 {
+                return Err("Save state has an invalid disk track");
+            }
+            tracks.push(WozTrack { bit_count, data });
+        }
+
+        if tracks.len() != MAX_TRACKS {
+            return Err("Save state has an invalid disk image");
+        }
+
+        Ok(WozImage {
+            write_protected,
+            tracks,
+        })
+    }
+
     fn new_inner(data: &[u8]) -> Result<Self, &'static str> {
         WozImage::verify(data)?;
 
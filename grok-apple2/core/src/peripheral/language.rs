@@ -1,5 +1,7 @@
 //! Apple II Language Card
 
+use super::{Reset, StateReader, StateWriter};
+use crate::RamInit;
 use grok_6502::bus::{self, Bus};
 
 const WRITE_EN_COUNT_MAX: u8 = 1;
@@ -32,65 +34,35 @@ mod soft_switch {
     pub const BANK1_RAM_READ_WRITE_ALT: u8 = BANK1_RAM_READ_WRITE + 4;
 }
 
-/// Language card emulator.
-pub struct LanguageCard {
+// The RAM making up a language card, laid over the $D000-$FFFF ROM range
+pub(crate) struct BankRam {
     bank1_ram: [u8; BANK_RAM_SIZE],
     bank2_ram: [u8; BANK_RAM_SIZE],
     ext_ram: [u8; EXT_RAM_SIZE],
-    ram_read: bool,
-    ram_write: bool,
-    bank2_active: bool,
-    write_en_count: u8,
-}
-
-impl Default for LanguageCard {
-    fn default() -> Self {
-        Self::new()
-    }
 }
 
-impl LanguageCard {
-    pub fn new() -> Self {
-        LanguageCard {
+impl BankRam {
+    pub(crate) fn new() -> Self {
+        BankRam {
             bank1_ram: [0; BANK_RAM_SIZE],
             bank2_ram: [0; BANK_RAM_SIZE],
             ext_ram: [0; EXT_RAM_SIZE],
-            ram_read: false,
-            ram_write: false,
-            bank2_active: true,
-            write_en_count: WRITE_EN_COUNT_MAX,
         }
     }
 
-    fn read_enable(&mut self, bank2: bool, ram_read: bool) {
-        self.bank2_active = bank2;
-        self.ram_read = ram_read;
-        self.ram_write = false;
-        self.write_en_count = WRITE_EN_COUNT_MAX;
-    }
-
-    fn write_enable(&mut self, bank2: bool, ram_read: bool) {
-        self.bank2_active = bank2;
-        self.ram_read = ram_read;
-
-        // It takes two consecutive accesses to a write enable switch to actually enable RAM write
-        if !self.ram_write {
-            if self.write_en_count == 0 {
-                self.ram_write = true;
-                self.write_en_count = WRITE_EN_COUNT_MAX;
-            } else {
-                self.write_en_count -= 1;
-            }
-        }
+    pub(crate) fn power_on(&mut self, init: RamInit) {
+        init.fill(&mut self.bank1_ram);
+        init.fill(&mut self.bank2_ram);
+        init.fill(&mut self.ext_ram);
     }
 
-    fn read(&mut self, bus: &mut dyn Bus) {
+    pub(crate) fn read(&self, bus: &mut dyn Bus, bank2_active: bool) {
         let addr = bus.addr() as usize;
 
         match addr {
             BANK_RAM_START..BANK_RAM_END => {
                 let bank_addr = addr - BANK_RAM_START;
-                if self.bank2_active {
+                if bank2_active {
                     bus.set_data(self.bank2_ram[bank_addr])
                 } else {
                     bus.set_data(self.bank1_ram[bank_addr])
@@ -101,14 +73,14 @@ impl LanguageCard {
         }
     }
 
-    fn write(&mut self, bus: &mut dyn Bus) {
+    pub(crate) fn write(&mut self, bus: &mut dyn Bus, bank2_active: bool) {
         let addr = bus.addr() as usize;
         let data = bus.data();
 
         match addr {
             BANK_RAM_START..BANK_RAM_END => {
                 let bank_addr = addr - BANK_RAM_START;
-                if self.bank2_active {
+                if bank2_active {
                     self.bank2_ram[bank_addr] = data;
                 } else {
                     self.bank1_ram[bank_addr] = data;
@@ -118,28 +90,67 @@ impl LanguageCard {
             _ => (),
         }
     }
+
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.bank1_ram);
+        state.bytes(&self.bank2_ram);
+        state.bytes(&self.ext_ram);
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        state.fill(&mut self.bank1_ram)?;
+        state.fill(&mut self.bank2_ram)?;
+        state.fill(&mut self.ext_ram)
+    }
 }
 
-impl super::Peripheral for LanguageCard {
-    fn tick(&mut self, bus: &mut dyn Bus, pins: &mut super::Pins) {
-        match bus.op() {
-            bus::Op::Read if self.ram_read => {
-                pins.set_inh(true);
-                self.read(bus);
-            }
-            bus::Op::Write if self.ram_write => {
-                pins.set_inh(true);
-                self.write(bus);
-            }
-            _ => pins.set_inh(false),
+// The soft switches controlling a language card (also built into the IIe MMU)
+pub(crate) struct BankSwitches {
+    pub(crate) ram_read: bool,
+    pub(crate) ram_write: bool,
+    pub(crate) bank2_active: bool,
+    write_en_count: u8,
+}
+
+impl BankSwitches {
+    pub(crate) fn new() -> Self {
+        BankSwitches {
+            ram_read: false,
+            ram_write: false,
+            bank2_active: true,
+            write_en_count: WRITE_EN_COUNT_MAX,
         }
     }
 
-    fn io_select(&mut self, _bus: &mut dyn Bus, _pins: &mut super::Pins) {
-        // Do nothing, language card has no ROM
+    // RESET leaves the card reading ROM and writing RAM in bank 2
+    pub(crate) fn reset(&mut self) {
+        *self = BankSwitches::new();
+        self.ram_write = true;
     }
 
-    fn device_select(&mut self, bus: &mut dyn Bus, _pins: &mut super::Pins) {
+    fn read_enable(&mut self, bank2: bool, ram_read: bool) {
+        self.bank2_active = bank2;
+        self.ram_read = ram_read;
+        self.ram_write = false;
+        self.write_en_count = WRITE_EN_COUNT_MAX;
+    }
+
+    fn write_enable(&mut self, bank2: bool, ram_read: bool) {
+        self.bank2_active = bank2;
+        self.ram_read = ram_read;
+
+        // It takes two consecutive accesses to a write enable switch to actually enable RAM write
+        if !self.ram_write {
+            if self.write_en_count == 0 {
+                self.ram_write = true;
+                self.write_en_count = WRITE_EN_COUNT_MAX;
+            } else {
+                self.write_en_count -= 1;
+            }
+        }
+    }
+
+    pub(crate) fn decode(&mut self, bus: &dyn Bus) {
         // If we receive a write, reset the write enable count because technically
         // it requires two consecutive READs to become enabled
         if bus.op() == bus::Op::Write {
@@ -175,7 +186,84 @@ impl super::Peripheral for LanguageCard {
         }
     }
 
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.ram_read);
+        state.bool(self.ram_write);
+        state.bool(self.bank2_active);
+        state.u8(self.write_en_count);
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.ram_read = state.bool()?;
+        self.ram_write = state.bool()?;
+        self.bank2_active = state.bool()?;
+        self.write_en_count = state.u8()?.min(WRITE_EN_COUNT_MAX);
+        Ok(())
+    }
+}
+
+/// Language card emulator.
+pub struct LanguageCard {
+    ram: BankRam,
+    switches: BankSwitches,
+}
+
+impl Default for LanguageCard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LanguageCard {
+    pub fn new() -> Self {
+        LanguageCard {
+            ram: BankRam::new(),
+            switches: BankSwitches::new(),
+        }
+    }
+}
+
+impl super::Peripheral for LanguageCard {
+    fn tick(&mut self, bus: &mut dyn Bus, pins: &mut super::Pins) {
+        match bus.op() {
+            bus::Op::Read if self.switches.ram_read => {
+                pins.set_inh(true);
+                self.ram.read(bus, self.switches.bank2_active);
+            }
+            bus::Op::Write if self.switches.ram_write => {
+                pins.set_inh(true);
+                self.ram.write(bus, self.switches.bank2_active);
+            }
+            _ => pins.set_inh(false),
+        }
+    }
+
+    fn io_select(&mut self, _bus: &mut dyn Bus, _pins: &mut super::Pins) {
+        // Do nothing, language card has no ROM
+    }
+
+    fn device_select(&mut self, bus: &mut dyn Bus, _pins: &mut super::Pins) {
+        self.switches.decode(bus);
+    }
+
     fn io_strobe(&mut self, _bus: &mut dyn Bus, _pins: &mut super::Pins) {
         // Intentionally do nothing
     }
+
+    fn reset(&mut self, reset: Reset) {
+        if let Reset::Cold(init) = reset {
+            self.ram.power_on(init);
+        }
+        self.switches.reset();
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        self.ram.save_state(state);
+        self.switches.save_state(state);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.ram.load_state(state)?;
+        self.switches.load_state(state)
+    }
 }
@@ -1,15 +1,23 @@
 pub mod disk;
 pub mod language;
 pub mod serial;
+pub mod videx;
 
-use crate::mem_map;
+use crate::{RamInit, mem_map};
 use grok_6502::bus::Bus;
+pub use grok_6502::state::{StateReader, StateWriter};
 
 const NUM_SLOTS: usize = 8;
 
+// How each slot identifies itself on the bus
+const SLOT_NAMES: [&str; NUM_SLOTS] = [
+    "slot0", "slot1", "slot2", "slot3", "slot4", "slot5", "slot6", "slot7",
+];
+
 /// Shared pins between all peripherals and the motherboard.
 pub struct Pins {
     inh: bool,
+    dma: bool,
 }
 
 impl Pins {
@@ -27,16 +35,56 @@ impl Pins {
         self.inh = inh;
     }
 
+    /// Returns whether the DMA pin is currently active.
+    pub fn dma(&self) -> bool {
+        self.dma
+    }
+
+    /// Sets the active state of the DMA pin.
+    ///
+    /// A peripheral should hold this active during every `tick` for as long as it wants
+    /// to be bus master. Once the CPU has stopped, the peripheral gets to drive the bus
+    /// through [`Peripheral::dma_start`] and [`Peripheral::dma_end`] instead of the CPU.
+    pub fn set_dma(&mut self, dma: bool) {
+        self.dma = dma;
+    }
+
     pub(crate) fn new() -> Self {
-        Self { inh: false }
+        Self {
+            inh: false,
+            dma: false,
+        }
     }
 }
 
+/// What caused the RESET line to be pulled (see [`Peripheral::reset`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reset {
+    /// The RESET key was pressed, so RAM is left alone.
+    Warm,
+    /// The machine was powered on, so any RAM should be filled according to the given policy.
+    Cold(RamInit),
+}
+
+/// A frame from a peripheral's own video output (see [`Peripheral::video_output`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoFrame<'f> {
+    /// The frame as a flat buffer of `0x00RRGGBB` pixels.
+    pub pixels: &'f [u32],
+    pub width: u32,
+    pub height: u32,
+}
+
 pub(crate) struct Peripherals<'a> {
     pub(crate) pins: Pins,
     // Chose to own references instead of boxing it up to keep this no_std compatible
     pub(crate) slots: [Option<&'a mut dyn Peripheral>; NUM_SLOTS],
-    active_slot: usize,
+    // The slot answering $C800-$CFFF, until $CFFF is accessed
+    active_slot: Option<usize>,
+    dma_slot: Option<usize>,
+    // The slot driving the bus this cycle, since another can win the bus for the next one
+    // in between the start and end of the cycle
+    bus_master: Option<usize>,
 }
 
 impl Default for Peripherals<'_> {
@@ -44,15 +92,50 @@ impl Default for Peripherals<'_> {
         Peripherals {
             pins: Pins::new(),
             slots: [const { None }; NUM_SLOTS],
-            active_slot: 0,
+            active_slot: None,
+            dma_slot: None,
+            bus_master: None,
         }
     }
 }
 
 impl Peripherals<'_> {
     pub(crate) fn tick(&mut self, bus: &mut dyn Bus) {
-        for peripheral in self.slots.iter_mut().flatten() {
-            peripheral.tick(bus, &mut self.pins);
+        let mut requests = [false; NUM_SLOTS];
+
+        for (slot, peripheral) in self.slots.iter_mut().enumerate() {
+            if let Some(peripheral) = peripheral {
+                self.pins.dma = false;
+                bus.set_device(SLOT_NAMES[slot]);
+                peripheral.tick(bus, &mut self.pins);
+                requests[slot] = self.pins.dma;
+            }
+        }
+
+        // Sather 7-21: DMA priority is daisy chained down from slot 7,
+        // but a card that already owns the bus keeps it until it lets go
+        self.dma_slot = match self.dma_slot {
+            Some(slot) if requests[slot] => Some(slot),
+            _ => requests.iter().rposition(|&r| r),
+        };
+        self.pins.dma = self.dma_slot.is_some();
+    }
+
+    pub(crate) fn dma_start(&mut self, bus: &mut dyn Bus) {
+        self.bus_master = self.dma_slot;
+        if let Some(slot) = self.bus_master
+            && let Some(peripheral) = &mut self.slots[slot]
+        {
+            bus.set_device(SLOT_NAMES[slot]);
+            peripheral.dma_start(bus, &mut self.pins);
+        }
+    }
+
+    pub(crate) fn dma_end(&mut self, bus: &mut dyn Bus) {
+        if let Some(slot) = self.bus_master.take()
+            && let Some(peripheral) = &mut self.slots[slot]
+        {
+            peripheral.dma_end(bus, &mut self.pins);
         }
     }
 
@@ -61,7 +144,8 @@ impl Peripherals<'_> {
 
         if let Some(peripheral) = &mut self.slots[slot as usize] {
             // Sather 6-4: Device select can also enable extended ROM
-            self.active_slot = slot as usize;
+            self.active_slot = Some(slot as usize);
+            bus.set_device(SLOT_NAMES[slot as usize]);
             peripheral.device_select(bus, &mut self.pins);
         }
     }
@@ -72,20 +156,107 @@ impl Peripherals<'_> {
 
         if let Some(peripheral) = &mut self.slots[slot as usize] {
             // Sather 6-4
-            self.active_slot = slot as usize;
+            self.active_slot = Some(slot as usize);
+            bus.set_device(SLOT_NAMES[slot as usize]);
             peripheral.io_select(bus, &mut self.pins);
         }
     }
 
+    /// Every card lets go of $C800-$CFFF when $CFFF is accessed (Sather 6-4),
+    /// even if something else answered it.
+    pub(crate) fn release_strobe(&mut self) {
+        self.active_slot = None;
+    }
+
+    pub(crate) fn audio_output(&self) -> f32 {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|peripheral| peripheral.audio_output())
+            .sum()
+    }
+
+    pub(crate) fn video_output(&mut self, slot: usize) -> Option<VideoFrame<'_>> {
+        self.slots.get_mut(slot)?.as_mut()?.video_output()
+    }
+
+    pub(crate) fn reset(&mut self, reset: Reset) {
+        for peripheral in self.slots.iter_mut().flatten() {
+            peripheral.reset(reset);
+        }
+        self.pins = Pins::new();
+        self.dma_slot = None;
+    }
+
+    // Each card's state is written as its own blob so a misbehaving card can't throw off the rest
+    pub(crate) fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.pins.inh);
+        state.bool(self.pins.dma);
+        state.u8(self.active_slot.map_or(u8::MAX, |slot| slot as u8));
+        state.u8(self.dma_slot.map_or(u8::MAX, |slot| slot as u8));
+
+        for peripheral in &self.slots {
+            state.bool(peripheral.is_some());
+            if let Some(peripheral) = peripheral {
+                let mut card = StateWriter::new();
+                peripheral.save_state(&mut card);
+                state.blob(&card.into_inner());
+            }
+        }
+    }
+
+    pub(crate) fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        self.pins.inh = state.bool()?;
+        self.pins.dma = state.bool()?;
+        let mut slot = || {
+            state.u8().map(|slot| match slot {
+                u8::MAX => None,
+                slot => Some(slot as usize),
+            })
+        };
+        self.active_slot = slot()?;
+        self.dma_slot = slot()?;
+        if [self.active_slot, self.dma_slot]
+            .iter()
+            .flatten()
+            .any(|&slot| slot >= NUM_SLOTS)
+        {
+            return Err("Save state has an invalid slot number");
+        }
+
+        for peripheral in &mut self.slots {
+            if state.bool()? != peripheral.is_some() {
+                return Err("Save state has different peripheral slots occupied");
+            }
+
+            if let Some(peripheral) = peripheral {
+                let mut card = StateReader::new(state.blob()?);
+                peripheral.load_state(&mut card)?;
+                card.finish()?;
+            }
+        }
+        Ok(())
+    }
+
     pub(crate) fn decode(&mut self, bus: &mut dyn Bus) {
         match bus.addr() {
             mem_map::DEVICE_SELECT..mem_map::IO_SELECT => self.device_select(bus),
             mem_map::IO_SELECT..mem_map::IO_STROBE => self.io_select(bus),
-            mem_map::IO_STROBE.. if let Some(peripheral) = &mut self.slots[self.active_slot] => {
-                // Note: Not checking for the magic value of $CFFF because we are not leaving it
-                // up to each peripheral to decide if it will respond to IO strobe or not
-                // (though on real hardware a card could misbehave and cause conflicts)
-                peripheral.io_strobe(bus, &mut self.pins);
+            mem_map::IO_STROBE.. => {
+                // Nothing answers if the last slot addressed is empty, leaving the bus floating
+                if let Some(slot) = self.active_slot
+                    && let Some(peripheral) = &mut self.slots[slot]
+                {
+                    // Note: Handling the magic value of $CFFF here rather than leaving it
+                    // up to each peripheral to decide if it will respond to IO strobe or not
+                    // (though on real hardware a card could misbehave and cause conflicts)
+                    bus.set_device(SLOT_NAMES[slot]);
+                    peripheral.io_strobe(bus, &mut self.pins);
+                }
+
+                if bus.addr() == mem_map::IO_STROBE_RESET {
+                    self.release_strobe();
+                }
             }
             _ => unreachable!(),
         }
@@ -114,4 +285,49 @@ pub trait Peripheral {
     ///
     /// This allows the peripheral to make use of the extended ROM range.
     fn io_strobe(&mut self, bus: &mut dyn Bus, pins: &mut Pins);
+
+    /// Called at the start of every cycle this peripheral is bus master (see [`Pins::set_dma`]).
+    ///
+    /// The peripheral should start a read or write on the bus in place of the CPU.
+    fn dma_start(&mut self, _bus: &mut dyn Bus, _pins: &mut Pins) {}
+
+    /// Called at the end of every cycle this peripheral is bus master,
+    /// once the addressed device has responded.
+    ///
+    /// For reads, the data bus now holds the value that was read.
+    fn dma_end(&mut self, _bus: &mut dyn Bus, _pins: &mut Pins) {}
+
+    /// The sound the peripheral is making this cycle, from -1.0 to 1.0.
+    ///
+    /// Called every CPU cycle (after [`Peripheral::tick`]) and mixed in with the speaker,
+    /// so sound cards don't need to do any resampling themselves.
+    fn audio_output(&self) -> f32 {
+        0.0
+    }
+
+    /// Render the peripheral's own video output, for cards that drive a separate monitor
+    /// (like 80 column cards) rather than the Apple's video.
+    ///
+    /// Called whenever the host wants a frame, which is usually once per [`crate::Apple2::run_frame`].
+    fn video_output(&mut self) -> Option<VideoFrame<'_>> {
+        None
+    }
+
+    /// Called when the RESET line is pulled, either by the RESET key or on power on.
+    ///
+    /// The peripheral should put itself back in the state its hardware would be left in.
+    fn reset(&mut self, _reset: Reset) {}
+
+    /// Save whatever state is needed to resume emulation later (see [`crate::Apple2::save_state`]).
+    ///
+    /// Things that never change (like ROM) or that belong to the host don't need to be saved.
+    /// Peripherals without any state can rely on the default, which saves nothing.
+    fn save_state(&self, _state: &mut StateWriter) {}
+
+    /// Restore state previously written by [`Peripheral::save_state`].
+    ///
+    /// All of the state must be read, otherwise loading fails.
+    fn load_state(&mut self, _state: &mut StateReader) -> Result<(), &'static str> {
+        Ok(())
+    }
 }
@@ -1,6 +1,6 @@
 //! Apple II Super Serial Card.
 
-use crate::peripheral::Peripheral;
+use crate::peripheral::{Peripheral, Reset, StateReader, StateWriter};
 use bitfield_struct::bitfield;
 use grok_6502::bus::{Bus, Op};
 
@@ -532,11 +532,7 @@ impl<S: SerialPort> SuperSerial<S> {
 }
 
 impl<S: SerialPort> Peripheral for SuperSerial<S> {
-    fn tick(&mut self, bus: &mut dyn Bus, _pins: &mut super::Pins) {
-        if bus.res() {
-            self.acia.hard_reset();
-        }
-
+    fn tick(&mut self, _bus: &mut dyn Bus, _pins: &mut super::Pins) {
         self.acia.tick();
     }
 
@@ -577,6 +573,33 @@ impl<S: SerialPort> Peripheral for SuperSerial<S> {
         let addr = (bus.addr() & 0x7FF) as usize;
         bus.set_data(self.rom[addr]);
     }
+
+    // The ACIA's RES pin is wired straight to the Apple's RESET line
+    fn reset(&mut self, _reset: Reset) {
+        self.acia.hard_reset();
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.acia.status.into());
+        state.u8(self.acia.command.into());
+        state.u8(self.acia.control.into());
+        state.u8(self.acia.rdreg);
+        state.u32(self.acia.frame_ticks);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        let status = state.u8()?.into();
+        let command = state.u8()?.into();
+        let control = state.u8()?.into();
+
+        // Forcing these also brings the host serial port back in line with the saved settings
+        self.acia.set_command(command, true);
+        self.acia.set_control(control, true);
+        self.acia.status = status;
+        self.acia.rdreg = state.u8()?;
+        self.acia.frame_ticks = state.u32()?;
+        Ok(())
+    }
 }
 
 /// Represents the actual serial port on the host machine.
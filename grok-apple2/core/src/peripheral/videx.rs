@@ -0,0 +1,252 @@
+//! Videx Videoterm 80 column card.
+//!
+//! The card has its own 6845 CRTC, 2K of display RAM and character generator,
+//! and its own video output, so 80 column text goes to a second monitor
+//! (or through a soft video switch controlled by annunciator 0) instead of the Apple's video.
+
+use crate::io::video::Phosphor;
+use crate::peripheral::{Peripheral, Pins, Reset, StateReader, StateWriter, VideoFrame};
+use crate::settings;
+use grok_6502::bus::{Bus, Op};
+
+/// Videoterm firmware ROM size.
+pub const ROM_SIZE: usize = 0x400;
+
+/// Videoterm character generator ROM size (128 characters of 16 lines each).
+pub const CHAR_ROM_SIZE: usize = 0x800;
+
+const RAM_SIZE: usize = 0x800;
+const RAM_BANK_SIZE: usize = 0x200;
+const CHAR_LINES: usize = 16;
+const CHAR_WIDTH: usize = 8;
+
+// The window at $CC00-$CDFF showing one bank of display RAM, after the ROM at $C800-$CBFF
+const RAM_WINDOW: u16 = 0x400;
+const RAM_WINDOW_END: u16 = RAM_WINDOW + RAM_BANK_SIZE as u16;
+
+// Cursor blink periods, in fields
+const CPU_CYCLES_PER_FIELD: u32 = 17030;
+const FAST_BLINK_FIELDS: u32 = 16;
+const SLOW_BLINK_FIELDS: u32 = 32;
+
+// Limits on what the CRTC is programmed to display, to keep a bad program from making huge frames
+const MAX_COLS: usize = 128;
+const MAX_ROWS: usize = 64;
+
+const BLACK: u32 = 0x000000;
+
+mod reg {
+    pub const HORIZ_DISPLAYED: usize = 1;
+    pub const VERT_DISPLAYED: usize = 6;
+    pub const MAX_SCAN_LINE: usize = 9;
+    pub const CURSOR_START: usize = 10;
+    pub const CURSOR_END: usize = 11;
+    pub const START_ADDR_HI: usize = 12;
+    pub const START_ADDR_LO: usize = 13;
+    pub const CURSOR_HI: usize = 14;
+    pub const CURSOR_LO: usize = 15;
+    pub const COUNT: usize = 18;
+
+    // Which bits of each register exist
+    pub const MASKS: [u8; COUNT] = [
+        0xFF, 0xFF, 0xFF, 0xFF, 0x7F, 0x1F, 0x7F, 0x7F, 0x03, 0x1F, 0x7F, 0x1F, 0x3F, 0xFF, 0x3F,
+        0xFF, 0x3F, 0xFF,
+    ];
+}
+
+/// The card's MC6845 CRTC, which only needs its registers emulated since the image is drawn all at once.
+struct Crtc {
+    addr: u8,
+    regs: [u8; reg::COUNT],
+}
+
+impl Crtc {
+    fn read(&self) -> u8 {
+        // Only the cursor and light pen registers can be read back
+        match self.addr as usize {
+            reg::CURSOR_HI..reg::COUNT => self.regs[self.addr as usize],
+            _ => 0,
+        }
+    }
+
+    fn write(&mut self, data: u8) {
+        if let Some(mask) = reg::MASKS.get(self.addr as usize) {
+            self.regs[self.addr as usize] = data & mask;
+        }
+    }
+
+    fn addr(&self, hi: usize, lo: usize) -> usize {
+        ((self.regs[hi] as usize) << 8) | self.regs[lo] as usize
+    }
+}
+
+/// Videx Videoterm 80 column card, usually placed in slot 3.
+pub struct Videoterm {
+    rom: [u8; ROM_SIZE],
+    char_rom: [u8; CHAR_ROM_SIZE],
+    ram: [u8; RAM_SIZE],
+    bank: usize,
+    crtc: Crtc,
+    cycles: u32,
+    buf: Vec<u32>,
+}
+
+impl Videoterm {
+    pub fn new(rom: [u8; ROM_SIZE], char_rom: [u8; CHAR_ROM_SIZE]) -> Self {
+        Videoterm {
+            rom,
+            char_rom,
+            ram: [0; RAM_SIZE],
+            bank: 0,
+            crtc: Crtc {
+                addr: 0,
+                regs: [0; reg::COUNT],
+            },
+            cycles: 0,
+            buf: Vec::new(),
+        }
+    }
+
+    fn cursor_visible(&self) -> bool {
+        let fields = self.cycles / CPU_CYCLES_PER_FIELD;
+        match (self.crtc.regs[reg::CURSOR_START] >> 5) & 0b11 {
+            0b00 => true,
+            0b01 => false,
+            0b10 => fields % FAST_BLINK_FIELDS < FAST_BLINK_FIELDS / 2,
+            _ => fields % SLOW_BLINK_FIELDS < SLOW_BLINK_FIELDS / 2,
+        }
+    }
+}
+
+impl Peripheral for Videoterm {
+    fn tick(&mut self, _bus: &mut dyn Bus, _pins: &mut Pins) {
+        self.cycles = self.cycles.wrapping_add(1);
+    }
+
+    // Every access picks the RAM bank from address bits 2-3,
+    // then even addresses select a CRTC register and odd addresses access it
+    fn device_select(&mut self, bus: &mut dyn Bus, _pins: &mut Pins) {
+        let addr = bus.addr() & 0xF;
+        self.bank = ((addr >> 2) & 0b11) as usize;
+
+        match (addr & 1, bus.op()) {
+            (0, Op::Write) => self.crtc.addr = bus.data() & 0x1F,
+            (1, Op::Write) => self.crtc.write(bus.data()),
+            (1, Op::Read) => bus.set_data(self.crtc.read()),
+            _ => (),
+        }
+    }
+
+    // The slot ROM space shows the last page of the firmware
+    fn io_select(&mut self, bus: &mut dyn Bus, _pins: &mut Pins) {
+        let addr = 0x300 | (bus.addr() & 0xFF) as usize;
+        bus.set_data(self.rom[addr]);
+    }
+
+    fn io_strobe(&mut self, bus: &mut dyn Bus, _pins: &mut Pins) {
+        let addr = bus.addr() & 0x7FF;
+        match addr {
+            ..RAM_WINDOW => bus.set_data(self.rom[addr as usize]),
+            RAM_WINDOW..RAM_WINDOW_END => {
+                let addr = self.bank * RAM_BANK_SIZE + (addr - RAM_WINDOW) as usize;
+                match bus.op() {
+                    Op::Read => bus.set_data(self.ram[addr]),
+                    Op::Write => self.ram[addr] = bus.data(),
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // The CRTC isn't wired to RESET, so it's only cleared on power on
+    fn reset(&mut self, reset: Reset) {
+        if let Reset::Cold(init) = reset {
+            init.fill(&mut self.ram);
+            self.bank = 0;
+            self.crtc.addr = 0;
+            self.crtc.regs = [0; reg::COUNT];
+            self.cycles = 0;
+        }
+    }
+
+    // Characters are 8 dots across (most significant bit first) and up to 16 lines tall,
+    // where setting bit 7 of a character shows it in inverse
+    fn video_output(&mut self) -> Option<VideoFrame<'_>> {
+        let regs = &self.crtc.regs;
+        let cols = (regs[reg::HORIZ_DISPLAYED] as usize).min(MAX_COLS);
+        let rows = (regs[reg::VERT_DISPLAYED] as usize).min(MAX_ROWS);
+        let lines = (regs[reg::MAX_SCAN_LINE] as usize + 1).min(CHAR_LINES);
+        let start = self.crtc.addr(reg::START_ADDR_HI, reg::START_ADDR_LO);
+        let cursor = self.crtc.addr(reg::CURSOR_HI, reg::CURSOR_LO);
+        let cursor_lines = regs[reg::CURSOR_START] as usize & 0x1F..=regs[reg::CURSOR_END] as usize;
+        let cursor_visible = self.cursor_visible();
+        let color = Phosphor::Green.color();
+
+        let width = cols * CHAR_WIDTH;
+        self.buf.clear();
+        self.buf.reserve(width * rows * lines);
+        for row in 0..rows {
+            for line in 0..lines {
+                for col in 0..cols {
+                    let addr = start + row * cols + col;
+                    let char = self.ram[addr % RAM_SIZE];
+                    let mut dots = self.char_rom[(char & 0x7F) as usize * CHAR_LINES + line];
+                    if char & 0x80 != 0 {
+                        dots = !dots;
+                    }
+                    if cursor_visible
+                        && addr % RAM_SIZE == cursor % RAM_SIZE
+                        && cursor_lines.contains(&line)
+                    {
+                        dots = !dots;
+                    }
+
+                    self.buf.extend((0..CHAR_WIDTH).map(|bit| {
+                        if dots & (0x80 >> bit) != 0 {
+                            color
+                        } else {
+                            BLACK
+                        }
+                    }));
+                }
+            }
+        }
+
+        // Until the firmware sets up the CRTC there's nothing to show,
+        // so show a blank screen the size the Apple's would be
+        if self.buf.is_empty() {
+            self.buf = vec![BLACK; (settings::DISP_WIDTH * settings::DISP_HEIGHT) as usize];
+            return Some(VideoFrame {
+                pixels: &self.buf,
+                width: settings::DISP_WIDTH,
+                height: settings::DISP_HEIGHT,
+            });
+        }
+
+        Some(VideoFrame {
+            pixels: &self.buf,
+            width: width as u32,
+            height: (rows * lines) as u32,
+        })
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.bytes(&self.ram);
+        state.u8(self.bank as u8);
+        state.u8(self.crtc.addr);
+        state.bytes(&self.crtc.regs);
+        state.u32(self.cycles);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), &'static str> {
+        state.fill(&mut self.ram)?;
+        self.bank = state.u8()? as usize;
+        if self.bank >= RAM_SIZE / RAM_BANK_SIZE {
+            return Err("Save state has an invalid Videoterm RAM bank");
+        }
+        self.crtc.addr = state.u8()?;
+        state.fill(&mut self.crtc.regs)?;
+        self.cycles = state.u32()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+//! Cassette tapes, for loading and saving programs through the cassette port.
+//!
+//! The Apple II only ever sees the zero crossings of the tape signal (on input it's fed through
+//! a comparator, and output is just a toggle), so a [`Tape`] is stored as the time between each
+//! edge of a square wave. Tapes can be read from and written to WAV files and CT2 images,
+//! or generated straight from binary data in the format written by the Monitor's `W` command.
+
+use crate::settings;
+use std::io::{self, Write};
+
+// The signal must swing this far past zero before it counts as an edge,
+// so noise in quiet parts of a recording doesn't look like data
+const WAV_HYSTERESIS: i32 = i16::MAX as i32 / 64;
+
+// A header tone is 770 Hz, a sync bit is one half cycle of 2500 Hz then one of 2000 Hz,
+// a 0 bit is one cycle of 2000 Hz and a 1 bit is one cycle of 1000 Hz.
+const HEADER_HALF_US: u32 = 650;
+const SYNC_FIRST_HALF_US: u32 = 200;
+const SYNC_SECOND_HALF_US: u32 = 250;
+const ZERO_HALF_US: u32 = 250;
+const ONE_HALF_US: u32 = 500;
+
+// The Monitor's read routine skips the first few seconds of the header before looking for the sync bit
+const HEADER_US: u32 = 4_000_000;
+
+const CHECKSUM_SEED: u8 = 0xFF;
+
+// When decoding, a half cycle at least this long is part of a header tone,
+// and it takes this many of them in a row to count as a header
+const HEADER_MIN_HALF_US: u32 = 575;
+const MIN_HEADER_HALVES: u32 = 64;
+
+// When decoding, a full cycle is a 0 bit if shorter than this, a 1 bit if shorter than the max,
+// and otherwise marks the end of the record
+const ONE_MIN_CYCLE_US: u32 = 750;
+const ONE_MAX_CYCLE_US: u32 = 1250;
+
+// Identifies a CT2 tape image, and the lengths of its long header tone (about what the
+// Monitor's `W` command writes) and short header tone (just long enough for the Monitor to read)
+const CT2_SIGNATURE: &[u8; 4] = b"CUTE";
+const CT2_LONG_HEADER_US: u32 = 10_000_000;
+const CT2_SHORT_HEADER_US: u32 = HEADER_US;
+
+fn us_to_cycles(us: u32) -> u32 {
+    (us as u64 * settings::CPU_CLK_SPEED as u64 / 1_000_000) as u32
+}
+
+// Data is stored in WAV files in little-endian format
+fn get_bytes_4(buf: &[u8], start: usize) -> u32 {
+    u32::from_le_bytes(buf[start..start + 4].try_into().unwrap())
+}
+
+fn get_bytes_2(buf: &[u8], start: usize) -> u16 {
+    u16::from_le_bytes(buf[start..start + 2].try_into().unwrap())
+}
+
+/// A cassette tape, stored as the number of CPU cycles between each edge of its signal.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Tape {
+    pub(crate) edges: Vec<u32>,
+    // Where the data belongs in memory and how long it is, when known (see `Tape::read_command`)
+    load: Option<(u16, u16)>,
+}
+
+impl Tape {
+    /// Create a tape from the time (in CPU cycles) between each edge of the signal.
+    pub fn from_edges(edges: Vec<u32>) -> Self {
+        Tape { edges, load: None }
+    }
+
+    /// The time (in CPU cycles) between each edge of the signal.
+    pub fn edges(&self) -> &[u32] {
+        &self.edges
+    }
+
+    /// How long the tape takes to play, in CPU cycles.
+    pub fn len_cycles(&self) -> u64 {
+        self.edges.iter().map(|&edge| edge as u64).sum()
+    }
+
+    /// Read a tape from an 8 or 16-bit PCM WAV file (only the first channel is used).
+    pub fn from_wav(buf: &[u8]) -> Result<Self, &'static str> {
+        if buf.len() < 12 || &buf[0..4] != b"RIFF" || &buf[8..12] != b"WAVE" {
+            return Err("File is not a WAV file.");
+        }
+
+        let mut format = None;
+        let mut data = None;
+        let mut pntr = 12;
+        while pntr + 8 <= buf.len() {
+            let id = &buf[pntr..pntr + 4];
+            let size = get_bytes_4(buf, pntr + 4) as usize;
+            let chunk = &buf[pntr + 8..(pntr + 8).saturating_add(size).min(buf.len())];
+            match id {
+                b"fmt " if chunk.len() >= 16 => format = Some(chunk),
+                b"data" => data = Some(chunk),
+                _ => (),
+            }
+
+            // Chunks are padded to an even size
+            pntr = pntr.saturating_add(8 + size + (size & 1));
+        }
+
+        let (Some(format), Some(data)) = (format, data) else {
+            return Err("WAV file is missing its format or data.");
+        };
+
+        let audio_format = get_bytes_2(format, 0);
+        let channels = get_bytes_2(format, 2) as usize;
+        let sample_rate = get_bytes_4(format, 4) as u64;
+        let bits = get_bytes_2(format, 14);
+        if audio_format != 1 || channels == 0 || sample_rate == 0 || !matches!(bits, 8 | 16) {
+            return Err("Only 8 or 16-bit PCM WAV files are supported.");
+        }
+
+        // Scale everything to signed 16-bit
+        let frame_size = channels * bits as usize / 8;
+        let samples = data.chunks_exact(frame_size).map(|frame| match bits {
+            8 => (frame[0] as i32 - 0x80) << 8,
+            _ => i16::from_le_bytes([frame[0], frame[1]]) as i32,
+        });
+
+        let mut edges = Vec::new();
+        let mut level = false;
+        let mut last_edge = 0;
+        for (i, sample) in samples.enumerate() {
+            let edge = if level {
+                sample < -WAV_HYSTERESIS
+            } else {
+                sample > WAV_HYSTERESIS
+            };
+
+            if edge {
+                let cycle = i as u64 * settings::CPU_CLK_SPEED as u64 / sample_rate;
+                edges.push((cycle - last_edge) as u32);
+                last_edge = cycle;
+                level = !level;
+            }
+        }
+
+        Ok(Tape::from_edges(edges))
+    }
+
+    /// Write the tape out as a 16-bit mono PCM WAV file.
+    pub fn write_wav(&self, out: &mut impl Write) -> io::Result<()> {
+        const AMPLITUDE: i16 = i16::MAX / 2;
+        // Plus one so the last edge makes it in
+        let num_samples =
+            self.len_cycles() * settings::SAMPLE_RATE as u64 / settings::CPU_CLK_SPEED as u64 + 1;
+        let data_size = (num_samples * 2) as u32;
+
+        out.write_all(b"RIFF")?;
+        out.write_all(&(36 + data_size).to_le_bytes())?;
+        out.write_all(b"WAVE")?;
+
+        out.write_all(b"fmt ")?;
+        out.write_all(&16u32.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&1u16.to_le_bytes())?;
+        out.write_all(&settings::SAMPLE_RATE.to_le_bytes())?;
+        out.write_all(&(settings::SAMPLE_RATE * 2).to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&16u16.to_le_bytes())?;
+
+        out.write_all(b"data")?;
+        out.write_all(&data_size.to_le_bytes())?;
+
+        // Start low, and flip the level at every edge
+        let mut edges = self.edges.iter();
+        let mut next_edge = edges.next().map(|&edge| edge as u64);
+        let mut level = false;
+        for sample in 0..num_samples {
+            let cycle = sample * settings::CPU_CLK_SPEED as u64 / settings::SAMPLE_RATE as u64;
+            while let Some(edge) = next_edge
+                && cycle >= edge
+            {
+                level = !level;
+                next_edge = edges.next().map(|&next| edge + next as u64);
+            }
+
+            let sample = if level { AMPLITUDE } else { -AMPLITUDE };
+            out.write_all(&sample.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    /// Generate a tape holding `data` as a single record, just like the Monitor's `W` command writes:
+    /// a header tone, a sync bit, then the data (most significant bit first) followed by a checksum.
+    ///
+    /// The tape itself doesn't say where the data goes, since the Monitor is told that when reading it,
+    /// so `load_addr` is kept alongside for [`Tape::read_command`].
+    pub fn from_binary(data: &[u8], load_addr: u16) -> Self {
+        assert!(
+            load_addr as usize + data.len() <= 0x10000,
+            "Data must fit in memory after the load address"
+        );
+
+        let mut tape = Tape::default();
+        tape.push_header(HEADER_US);
+        tape.push_record(data, None);
+        if !data.is_empty() {
+            tape.load = Some((load_addr, (data.len() - 1) as u16));
+        }
+        tape
+    }
+
+    /// The Monitor command that reads the tape back into memory where it belongs,
+    /// such as `800.8FFR` (followed by a newline, ready for [`crate::Apple2::type_text`]).
+    ///
+    /// This is only known for tapes made by [`Tape::from_binary`].
+    pub fn read_command(&self) -> Option<String> {
+        let (start, len) = self.load?;
+        let end = start + len;
+        Some(format!("{start:X}.{end:X}R\n"))
+    }
+
+    /// Read a tape from a CT2 image.
+    ///
+    /// Rather than audio, a CT2 image holds the records on the tape, so it loads without
+    /// any signal processing. It starts with the signature `CUTE`, followed by any number of
+    /// chunks made up of a 4 byte ID, a 16-bit little-endian length, then that many bytes:
+    ///
+    /// - `LONG`: a long header tone, as written before a record.
+    /// - `SHRT`: a short header tone.
+    /// - `DATA`: a sync bit and the bytes of a record, where the last byte is the checksum
+    ///   (as it was on tape, so bad checksums are kept).
+    ///
+    /// Chunks with any other ID are skipped.
+    pub fn from_ct2(buf: &[u8]) -> Result<Self, &'static str> {
+        let Some(mut chunks) = buf.strip_prefix(CT2_SIGNATURE) else {
+            return Err("File is not a CT2 tape image.");
+        };
+
+        let mut tape = Tape::default();
+        while !chunks.is_empty() {
+            let Some((id, rest)) = chunks.split_first_chunk::<4>() else {
+                return Err("CT2 tape image is corrupt.");
+            };
+            let Some((len, rest)) = rest.split_first_chunk::<2>() else {
+                return Err("CT2 tape image is corrupt.");
+            };
+            let Some((data, rest)) = rest.split_at_checked(u16::from_le_bytes(*len) as usize)
+            else {
+                return Err("CT2 tape image is corrupt.");
+            };
+
+            match id {
+                b"LONG" => tape.push_header(CT2_LONG_HEADER_US),
+                b"SHRT" => tape.push_header(CT2_SHORT_HEADER_US),
+                b"DATA" => {
+                    let Some((checksum, data)) = data.split_last() else {
+                        return Err("CT2 tape image is corrupt.");
+                    };
+                    tape.push_record(data, Some(*checksum));
+                }
+                _ => (),
+            }
+            chunks = rest;
+        }
+        Ok(tape)
+    }
+
+    /// Write the tape out as a CT2 image (see [`Tape::from_ct2`]), decoding the records on it.
+    ///
+    /// Anything that can't be decoded (such as noise between records) is left out.
+    pub fn to_ct2(&self) -> Vec<u8> {
+        let mut ct2 = CT2_SIGNATURE.to_vec();
+        let mut push_chunk = |id: &[u8; 4], data: &[u8]| {
+            ct2.extend_from_slice(id);
+            ct2.extend_from_slice(&(data.len() as u16).to_le_bytes());
+            ct2.extend_from_slice(data);
+        };
+
+        let mut idx = 0;
+        while let Some(record) = self.decode_record(idx) {
+            // Only the two lengths of header can be stored, so pick the closest
+            let header_us = record.header_halves.saturating_mul(HEADER_HALF_US);
+            if header_us * 2 > CT2_LONG_HEADER_US + CT2_SHORT_HEADER_US {
+                push_chunk(b"LONG", &[]);
+            } else {
+                push_chunk(b"SHRT", &[]);
+            }
+
+            // Records too long for a chunk are cut short (they'd be over 30 minutes long anyway)
+            let data = &record.data[..record.data.len().min(u16::MAX as usize)];
+            push_chunk(b"DATA", data);
+
+            idx = record.end;
+        }
+        ct2
+    }
+
+    fn push_header(&mut self, us: u32) {
+        let header_halves = (us + HEADER_HALF_US / 2) / HEADER_HALF_US;
+        self.edges.extend(std::iter::repeat_n(
+            us_to_cycles(HEADER_HALF_US),
+            header_halves as usize,
+        ));
+    }
+
+    // Writes the checksum for the data unless one is given
+    fn push_record(&mut self, data: &[u8], checksum: Option<u8>) {
+        self.edges.push(us_to_cycles(SYNC_FIRST_HALF_US));
+        self.edges.push(us_to_cycles(SYNC_SECOND_HALF_US));
+
+        let checksum =
+            checksum.unwrap_or_else(|| data.iter().fold(CHECKSUM_SEED, |sum, byte| sum ^ byte));
+        for &byte in data.iter().chain([&checksum]) {
+            for bit in (0..8).rev() {
+                let half = match (byte >> bit) & 1 {
+                    0 => us_to_cycles(ZERO_HALF_US),
+                    _ => us_to_cycles(ONE_HALF_US),
+                };
+                self.edges.extend([half, half]);
+            }
+        }
+
+        // Finish with a short tone so the last bit ends cleanly
+        self.edges.extend([us_to_cycles(HEADER_HALF_US); 2]);
+    }
+
+    /// Decode the next record on the tape, starting from the given edge.
+    ///
+    /// This looks for a header tone followed by a sync bit, then reads bits (telling them apart
+    /// by the length of each full cycle, like the Monitor does) until something too long for a bit.
+    pub(crate) fn decode_record(&self, from: usize) -> Option<Record> {
+        let header_min_half = us_to_cycles(HEADER_MIN_HALF_US);
+        let one_min_cycle = us_to_cycles(ONE_MIN_CYCLE_US);
+        let one_max_cycle = us_to_cycles(ONE_MAX_CYCLE_US);
+
+        // Find a header tone followed by the (much shorter) first half of the sync bit
+        let mut idx = from;
+        let mut header_halves = 0;
+        loop {
+            let half = *self.edges.get(idx)?;
+            idx += 1;
+            if half >= header_min_half {
+                header_halves += 1;
+            } else if header_halves >= MIN_HEADER_HALVES {
+                break;
+            } else {
+                header_halves = 0;
+            }
+        }
+
+        // Skip the second half of the sync bit
+        idx += 1;
+
+        let mut data = Vec::new();
+        let mut byte = 0;
+        let mut bits = 0;
+        while let Some(&[first, second]) = self.edges.get(idx..idx + 2) {
+            let bit = match first.saturating_add(second) {
+                cycle if cycle < one_min_cycle => 0,
+                cycle if cycle < one_max_cycle => 1,
+                _ => break,
+            };
+            idx += 2;
+
+            byte = (byte << 1) | bit;
+            bits += 1;
+            if bits == 8 {
+                data.push(byte);
+                bits = 0;
+            }
+        }
+
+        Some(Record {
+            header_halves,
+            data,
+            end: idx,
+        })
+    }
+}
+
+/// A record decoded from a tape.
+pub(crate) struct Record {
+    pub(crate) header_halves: u32,
+    /// The data, with the checksum as the last byte.
+    pub(crate) data: Vec<u8>,
+    /// The edge just after the record.
+    pub(crate) end: usize,
+}
+
+impl Record {
+    /// Whether the first `len` bytes match the checksum following them.
+    pub(crate) fn checksum_ok(&self, len: usize) -> bool {
+        self.data.len() > len
+            && self.data[..=len]
+                .iter()
+                .fold(CHECKSUM_SEED, |sum, byte| sum ^ byte)
+                == 0
+    }
+}
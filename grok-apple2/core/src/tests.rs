@@ -0,0 +1,1470 @@
+use crate::*;
+use headless::NullAudio;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Where the test program is assembled, which is also the reset vector
+const ORG: u16 = 0xD000;
+
+const NOP: u8 = 0xEA;
+const LDA_IMM: u8 = 0xA9;
+const LDA_ABS: u8 = 0xAD;
+const STA_ABS: u8 = 0x8D;
+const JMP_ABS: u8 = 0x4C;
+const JSR_ABS: u8 = 0x20;
+
+// Firmware for `model` with `program` assembled at $D000
+fn test_rom(model: Model, program: &[u8]) -> Vec<u8> {
+    let mut fw_rom = vec![0; model.rom_size()];
+    patch_rom(&mut fw_rom, ORG, program);
+    patch_rom(&mut fw_rom, 0xFFFC, &ORG.to_le_bytes());
+    fw_rom
+}
+
+// Firmware always ends at $FFFF, however much it covers
+fn patch_rom(fw_rom: &mut [u8], addr: u16, bytes: &[u8]) {
+    let start = fw_rom.len() + addr as usize - 0x10000;
+    fw_rom[start..start + bytes.len()].copy_from_slice(bytes);
+}
+
+// Boots the firmware with empty RAM and a blank character ROM
+fn boot_rom(model: Model, fw_rom: &[u8]) -> Apple2<'static, NullAudio> {
+    boot_roms(model, fw_rom, &vec![0; model.char_rom_size()])
+}
+
+fn boot_roms(model: Model, fw_rom: &[u8], char_rom: &[u8]) -> Apple2<'static, NullAudio> {
+    let mut apple2 = Apple2Builder::new(model)
+        .fw_rom(fw_rom)
+        .char_rom(char_rom)
+        .build(NullAudio)
+        .unwrap();
+    apple2.init();
+    apple2
+}
+
+// Boots a II+ into `program` assembled at $D000
+fn boot(program: &[u8]) -> Apple2<'static, NullAudio> {
+    boot_rom(Model::Apple2Plus, &test_rom(Model::Apple2Plus, program))
+}
+
+// The address of the next byte of `program`, for jumping back to
+fn here(program: &[u8]) -> u16 {
+    ORG + program.len() as u16
+}
+
+// Hangs on to the bytes latched over the last frame instead of drawing anything
+struct CaptureRenderer {
+    lines: Rc<RefCell<Vec<Scanline>>>,
+    buf: Vec<u32>,
+}
+
+impl Renderer for CaptureRenderer {
+    fn render(&mut self, frame: &FrameBuf) -> &[u32] {
+        *self.lines.borrow_mut() = frame.to_vec();
+        &self.buf
+    }
+
+    fn frame(&self) -> &[u32] {
+        &self.buf
+    }
+}
+
+fn capture_frames(apple2: &mut Apple2<NullAudio>, frames: usize) -> Vec<Scanline> {
+    let lines = Rc::new(RefCell::new(Vec::new()));
+    apple2.set_renderer(CaptureRenderer {
+        lines: lines.clone(),
+        buf: vec![0; (settings::DISP_WIDTH * settings::DISP_HEIGHT) as usize],
+    });
+    apple2.run_frames(frames);
+    lines.take()
+}
+
+#[test]
+fn mid_scanline_mode_switch() {
+    // Full screen hires page 1
+    let mut program = vec![
+        LDA_ABS, 0x57, 0xC0, // HIRES
+        LDA_ABS, 0x52, 0xC0, // MIXED off
+        LDA_ABS, 0x54, 0xC0, // PAGE2 off
+    ];
+
+    // A loop taking exactly one scanline (65 cycles), showing graphics for 32 cycles then text for 33.
+    // Both are longer than the 25 cycles of horizontal blanking, so both always show up on every line.
+    //
+    // Switching to text must not turn HIRES off, or graphics would come back as lores.
+    let lp = here(&program);
+    program.extend([LDA_ABS, 0x50, 0xC0]); // TEXT off
+    program.extend([NOP; 14]);
+    program.extend([LDA_ABS, 0x51, 0xC0]); // TEXT on
+    program.extend([NOP; 13]);
+    program.extend([JMP_ABS, lp as u8, (lp >> 8) as u8]);
+
+    let mut apple2 = boot(&program);
+    let lines = capture_frames(&mut apple2, 3);
+
+    let kinds = |line: &Scanline| {
+        line.bytes
+            .map(|byte| match byte {
+                FrameByte::Hires(_) => 'H',
+                FrameByte::Text(_) => 'T',
+                FrameByte::Lores(_) => 'L',
+            })
+            .iter()
+            .collect::<String>()
+    };
+
+    let first = kinds(&lines[0]);
+    assert!(first.contains('H') && first.contains('T'), "{first}");
+    assert!(!first.contains('L'), "{first}");
+
+    // Each run of graphics or text is in one piece (wrapping around the blanking)
+    let switches = first.as_bytes().windows(2).filter(|w| w[0] != w[1]).count();
+    assert!(switches <= 2, "{first}");
+
+    // The loop stays in step with the beam, so the switch lands in the same place on every line
+    for (row, line) in lines.iter().enumerate() {
+        assert_eq!(kinds(line), first, "scanline {row}");
+    }
+}
+
+#[test]
+fn monochrome_dots() {
+    // Full screen hires page 1, with a dot in the first byte and a delayed dot in the second
+    let mut program = vec![
+        LDA_ABS, 0x57, 0xC0, // HIRES
+        LDA_ABS, 0x52, 0xC0, // MIXED off
+        LDA_ABS, 0x54, 0xC0, // PAGE2 off
+        LDA_ABS, 0x50, 0xC0, // TEXT off
+    ];
+    poke(&mut program, 0x2000, &[0x01, 0x81]);
+    end_program(&mut program);
+
+    let mut apple2 = boot(&program);
+    let lines = capture_frames(&mut apple2, 2);
+    assert_eq!(
+        lines[0].bytes[..3],
+        [
+            FrameByte::Hires(0x01),
+            FrameByte::Hires(0x81),
+            FrameByte::Hires(0)
+        ]
+    );
+
+    // One pixel per 14M clock, with two for each dot, in the phosphor's color
+    let mut frame = [lines[1]; TEXT_ROWS * 8];
+    frame[0] = lines[0];
+    let mut renderer = MonochromeRenderer::new(Phosphor::Amber);
+    assert_eq!(renderer.width(), 560);
+    let pixels = renderer.render(&frame);
+    assert_eq!(pixels.len(), 560 * settings::DISP_HEIGHT as usize);
+    let lit: Vec<usize> = (0..560).filter(|&x| pixels[x] != 0).collect();
+    assert_eq!(lit, [0, 1, 15, 16]);
+    assert!(lit.iter().all(|&x| pixels[x] == 0xFFB000));
+
+    renderer.set_phosphor(Phosphor::Green);
+    assert_eq!(renderer.phosphor(), Phosphor::Green);
+    let pixels = renderer.render(&frame);
+    assert!(lit.iter().all(|&x| pixels[x] == 0x33FF66));
+
+    // The same comes out of the machine itself once switched over
+    apple2.set_monitor(Monitor::Monochrome(Phosphor::White));
+    apple2.run_frames(1);
+    let frame = apple2.video_frame();
+    assert_eq!(frame.width, 560);
+    let lit: Vec<usize> = (0..560).filter(|&x| frame.pixels[x] != 0).collect();
+    assert_eq!(lit, [0, 1, 15, 16]);
+    assert_eq!(frame.pixels[0], 0xFFFFFF);
+}
+
+#[test]
+fn ntsc_color_burst() {
+    // A single dot in the middle of each line, which a color TV shows as a color
+    let line = |byte, color_burst| Scanline {
+        bytes: [byte; TEXT_COLS],
+        color_burst,
+    };
+    let mut frame = [line(FrameByte::Text(0), false); TEXT_ROWS * 8];
+    frame[0] = line(FrameByte::Text(0x08), false);
+    frame[1] = line(FrameByte::Text(0x08), true);
+    frame[2] = line(FrameByte::Hires(0x08), false);
+    frame[3] = line(FrameByte::Hires(0x08), true);
+
+    let mut renderer = NtscRenderer::new();
+    let pixels = renderer.render(&frame);
+    let width = settings::DISP_WIDTH as usize;
+    let row = |y: usize| &pixels[y * width..(y + 1) * width];
+
+    // Text is only colored with the color burst on (like at the bottom of mixed mode),
+    // but hires always is
+    assert!(row(0).iter().all(|&px| px == 0x000000 || px == 0xFFFFFF));
+    assert!(row(0).contains(&0xFFFFFF));
+    assert!(row(1).iter().any(|&px| px != 0x000000 && px != 0xFFFFFF));
+    assert_eq!(row(2), row(1));
+    assert_eq!(row(3), row(1));
+}
+
+// Renders a frame whose first line is `bytes`, returning that line
+fn render_line(
+    renderer: &mut impl Renderer,
+    bytes: [FrameByte; TEXT_COLS],
+    color_burst: bool,
+) -> Vec<u32> {
+    let blank = Scanline {
+        bytes: [FrameByte::Text(0); TEXT_COLS],
+        color_burst: false,
+    };
+    let mut frame = [blank; TEXT_ROWS * 8];
+    frame[0] = Scanline { bytes, color_burst };
+
+    let width = renderer.width() as usize;
+    renderer.render(&frame)[..width].to_vec()
+}
+
+fn is_gray(px: u32) -> bool {
+    let [_, r, g, b] = px.to_be_bytes();
+    r == g && g == b
+}
+
+#[test]
+fn composite_signal() {
+    let mut renderer = CompositeRenderer::new();
+
+    // Text lines have no color burst, so come out as just the dots (two 14M clocks each)
+    let mut bytes = [FrameByte::Text(0); TEXT_COLS];
+    bytes[1] = FrameByte::Text(0x05);
+    let line = render_line(&mut renderer, bytes, false);
+    assert_eq!(line.len(), 560);
+    assert!(line.iter().all(|&px| px == 0x000000 || px == 0xFFFFFF));
+    let lit: Vec<usize> = (0..560).filter(|&x| line[x] != 0).collect();
+    assert_eq!(lit, [14, 15, 18, 19]);
+
+    // Setting bit 7 of a hires byte delays its dots by one 14M clock
+    let mut bytes = [FrameByte::Hires(0); TEXT_COLS];
+    bytes[1] = FrameByte::Hires(0x85);
+    let line = render_line(&mut renderer, bytes, false);
+    let lit: Vec<usize> = (0..560).filter(|&x| line[x] != 0).collect();
+    assert_eq!(lit, [15, 16, 19, 20]);
+
+    // With the color burst, solid white stays gray (away from the edges of the line),
+    // while a lone dot shows up as a color
+    let line = render_line(&mut renderer, [FrameByte::Text(0x7F); TEXT_COLS], true);
+    assert!(line[8..552].iter().all(|&px| is_gray(px)));
+    assert!(line[280] > 0xC0C0C0);
+    let mut bytes = [FrameByte::Hires(0); TEXT_COLS];
+    bytes[1] = FrameByte::Hires(0x01);
+    let line = render_line(&mut renderer, bytes, true);
+    assert!(line[12..18].iter().any(|&px| !is_gray(px)));
+
+    // Lores color 1 is magenta, and taking the saturation away leaves it gray
+    let line = render_line(&mut renderer, [FrameByte::Lores(1); TEXT_COLS], true);
+    let [_, r, g, b] = line[280].to_be_bytes();
+    assert!(r > g && b > g, "{:06X}", line[280]);
+
+    renderer.set_filter(CompositeFilter {
+        saturation: 0.0,
+        ..renderer.filter()
+    });
+    let line = render_line(&mut renderer, [FrameByte::Lores(1); TEXT_COLS], true);
+    assert!(line[8..552].iter().all(|&px| is_gray(px)));
+}
+
+#[test]
+fn videx_display() {
+    use peripheral::videx::{self, Videoterm};
+
+    // 80x24 characters of 9 lines each, with the cursor off
+    let mut program = vec![];
+    for (reg, val) in [(1, 80), (6, 24), (9, 8), (10, 0x20)] {
+        program.extend([LDA_IMM, reg, STA_ABS, 0xB0, 0xC0]);
+        program.extend([LDA_IMM, val, STA_ABS, 0xB1, 0xC0]);
+    }
+    program.extend([
+        LDA_ABS, 0x00, 0xC3, // Select the slot's $C800 space
+        LDA_IMM, 0x01, STA_ABS, 0x00, 0xCC, // Character 1 at the top left
+        LDA_IMM, 0x81, STA_ABS, 0x01, 0xCC, // Then in inverse
+    ]);
+    let end = here(&program);
+    program.extend([JMP_ABS, end as u8, (end >> 8) as u8]);
+
+    // Character 1 is a single line across the top
+    let mut char_rom = [0; videx::CHAR_ROM_SIZE];
+    char_rom[videx::CHAR_ROM_SIZE / 128] = 0xFF;
+    let card = Box::leak(Box::new(Videoterm::new([0; videx::ROM_SIZE], char_rom)));
+
+    let mut apple2 = boot(&program);
+    apple2.insert_peripheral(card, 3);
+    apple2.run_frames(2);
+
+    let frame = apple2.peripheral_video(3).unwrap();
+    assert_eq!((frame.width, frame.height), (640, 24 * 9));
+
+    let dot = |x: usize, y: usize| frame.pixels[y * frame.width as usize + x] != 0;
+    assert!((0..8).all(|x| dot(x, 0)) && !(0..8).any(|x| dot(x, 1)));
+    assert!(!(8..16).any(|x| dot(x, 0)) && (8..16).all(|x| dot(x, 1)));
+    assert!(!dot(16, 0) && !dot(0, 9));
+}
+
+fn test_data() -> Vec<u8> {
+    (0..=255).map(|i: u8| i.wrapping_mul(37)).collect()
+}
+
+#[test]
+fn tape_from_binary() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+    assert_eq!(tape.read_command().as_deref(), Some("800.8FFR\n"));
+
+    let record = tape.decode_record(0).unwrap();
+    assert_eq!(record.data[..data.len()], data);
+    assert_eq!(record.data.len(), data.len() + 1);
+    assert!(record.checksum_ok(data.len()));
+    assert!(!record.checksum_ok(data.len() - 1));
+    assert!(tape.decode_record(record.end).is_none());
+}
+
+#[test]
+fn tape_ct2_round_trip() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+
+    // A short header, since it's only as long as the Monitor needs
+    let ct2 = tape.to_ct2();
+    assert_eq!(ct2[..10], *b"CUTESHRT\0\0");
+    assert_eq!(ct2[10..14], *b"DATA");
+    assert_eq!(ct2[14..16], 257u16.to_le_bytes());
+    assert_eq!(ct2.len(), 16 + 257);
+
+    let loaded = tape::Tape::from_ct2(&ct2).unwrap();
+    assert_eq!(loaded.edges(), tape.edges());
+    assert_eq!(loaded.read_command(), None);
+
+    // Unknown chunks are skipped, and long headers kept
+    let mut long = b"CUTELONG\0\0TEXT\x02\0hi".to_vec();
+    long.extend_from_slice(&ct2[10..]);
+    let loaded = tape::Tape::from_ct2(&long).unwrap();
+    assert_eq!(loaded.decode_record(0).unwrap().data[..data.len()], data);
+    assert!(loaded.len_cycles() > tape.len_cycles());
+    assert_eq!(
+        loaded.to_ct2(),
+        long[..10]
+            .iter()
+            .chain(&ct2[10..])
+            .copied()
+            .collect::<Vec<_>>()
+    );
+
+    assert!(tape::Tape::from_ct2(&ct2[..ct2.len() - 1]).is_err());
+    assert!(tape::Tape::from_ct2(b"CUTEDATA\0\0").is_err());
+    assert!(tape::Tape::from_ct2(b"CUTESHR").is_err());
+    assert!(tape::Tape::from_ct2(b"GA2T\x01").is_err());
+}
+
+#[test]
+fn tape_from_binary_fills_memory() {
+    let tape = tape::Tape::from_binary(&vec![0; 0x10000], 0);
+    assert_eq!(tape.read_command().as_deref(), Some("0.FFFFR\n"));
+}
+
+#[test]
+fn tape_wav_round_trip() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+
+    let mut wav = Vec::new();
+    tape.write_wav(&mut wav).unwrap();
+    let loaded = tape::Tape::from_wav(&wav).unwrap();
+
+    // Edges move to the nearest sample, but the data still decodes the same
+    let record = loaded.decode_record(0).unwrap();
+    assert_eq!(record.data, tape.decode_record(0).unwrap().data);
+    assert!(record.checksum_ok(data.len()));
+}
+
+#[test]
+fn tape_edge_timing() {
+    let mut cassette = io::cassette::Cassette::default();
+    cassette.play(tape::Tape::from_edges(vec![3, 5, 1]));
+
+    // The cycle each edge lands on, counting from when the tape started
+    let mut edges = vec![];
+    let mut level = cassette.output();
+    for cycle in 1..=20 {
+        cassette.tick();
+        if cassette.output() != level {
+            level = cassette.output();
+            edges.push(cycle);
+        }
+    }
+    assert_eq!(edges, [3, 8, 9]);
+    assert!(!cassette.is_playing());
+}
+
+// Stores `bytes` to memory starting at `addr`
+fn poke(program: &mut Vec<u8>, addr: u16, bytes: &[u8]) {
+    for (addr, &byte) in (addr..).zip(bytes) {
+        program.extend([LDA_IMM, byte, STA_ABS, addr as u8, (addr >> 8) as u8]);
+    }
+}
+
+// Sets the Monitor's A1 and A2 for `800.8FFR`, calls its read routine, then waits
+fn read_tape_program(setup: &[u8]) -> Vec<u8> {
+    let mut program = setup.to_vec();
+    poke(&mut program, 0x3C, &[0x00, 0x08, 0xFF, 0x08]);
+    program.extend([JSR_ABS, 0xFD, 0xFE]);
+    let end = here(&program);
+    program.extend([JMP_ABS, end as u8, (end >> 8) as u8]);
+    program
+}
+
+// Stands in for the Monitor's read routine, never returning unless it's skipped
+fn with_read_routine(mut fw_rom: Vec<u8>) -> Vec<u8> {
+    patch_rom(&mut fw_rom, MONITOR_READ, &[JMP_ABS, 0xFD, 0xFE]);
+    fw_rom
+}
+
+#[test]
+fn tape_fast_load() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+    assert_eq!(tape.read_command().as_deref(), Some("800.8FFR\n"));
+
+    let program = read_tape_program(&[]);
+    let fw_rom = with_read_routine(test_rom(Model::Apple2Plus, &program));
+    let mut apple2 = boot_rom(Model::Apple2Plus, &fw_rom);
+    apple2.set_tape_fast_load(true);
+    apple2.play_tape(tape);
+    apple2.run_frames(2);
+
+    assert_eq!(apple2.ram[0x800..0x900], data);
+    assert_eq!(apple2.ram[0x3C..0x3E], [0x00, 0x09]);
+}
+
+#[test]
+fn tape_fast_load_iie_banking() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+
+    // ALTZP and RAMWRT on, so A1 and A2 are in auxiliary memory and so is the data
+    let program = read_tape_program(&[
+        STA_ABS, 0x09, 0xC0, // ALTZP on
+        STA_ABS, 0x05, 0xC0, // RAMWRT on
+    ]);
+    let fw_rom = with_read_routine(test_rom(Model::Apple2e, &program));
+    let mut apple2 = boot_rom(Model::Apple2e, &fw_rom);
+    apple2.set_tape_fast_load(true);
+    apple2.play_tape(tape);
+    apple2.run_frames(2);
+
+    let mmu = apple2.mmu.as_ref().unwrap();
+    assert_eq!(mmu.aux_ram[0x800..0x900], data);
+    assert_eq!(mmu.aux_ram[0x3C..0x3E], [0x00, 0x09]);
+    assert!(apple2.ram[0x800..0x900].iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn tape_fast_load_skips_lc_ram() {
+    let data = test_data();
+    let tape = tape::Tape::from_binary(&data, 0x800);
+
+    // Copy the routine into language card RAM (reading $C081 twice write enables it while reading ROM),
+    // then call it from main RAM after switching to reading language card RAM with $C080
+    let mut program = vec![LDA_ABS, 0x81, 0xC0, LDA_ABS, 0x81, 0xC0];
+    poke(&mut program, 0xFEFD, &[JMP_ABS, 0xFD, 0xFE]);
+    poke(&mut program, 0x3C, &[0x00, 0x08, 0xFF, 0x08]);
+    let call = [
+        LDA_ABS, 0x80, 0xC0, // Read language card RAM
+        JSR_ABS, 0xFD, 0xFE,
+    ];
+    poke(&mut program, 0x300, &call);
+    program.extend([JMP_ABS, 0x00, 0x03]);
+
+    let fw_rom = with_read_routine(test_rom(Model::Apple2e, &program));
+    let mut apple2 = boot_rom(Model::Apple2e, &fw_rom);
+    apple2.set_tape_fast_load(true);
+    apple2.play_tape(tape);
+    apple2.run_frames(2);
+
+    assert!(apple2.ram[0x800..0x900].iter().all(|&byte| byte == 0));
+    assert_eq!(apple2.ram[0x3C..0x3E], [0x00, 0x08]);
+}
+
+// Loads from `addr` and stores it at `dest`, for checking later
+fn read_to(program: &mut Vec<u8>, addr: u16, dest: u16) {
+    program.extend([LDA_ABS, addr as u8, (addr >> 8) as u8]);
+    program.extend([STA_ABS, dest as u8, (dest >> 8) as u8]);
+}
+
+// Accesses a soft switch (by writing, which is all the IIe MMU switches respond to)
+fn switch(program: &mut Vec<u8>, addr: u16) {
+    program.extend([STA_ABS, addr as u8, (addr >> 8) as u8]);
+}
+
+fn end_program(program: &mut Vec<u8>) {
+    let end = here(program);
+    program.extend([JMP_ABS, end as u8, (end >> 8) as u8]);
+}
+
+fn run_iie(program: &[u8]) -> Apple2<'static, NullAudio> {
+    let mut apple2 = boot_rom(Model::Apple2e, &test_rom(Model::Apple2e, program));
+    apple2.run_frames(1);
+    apple2
+}
+
+#[test]
+fn iie_ram_banking() {
+    let mut program = vec![];
+
+    // RAMWRT sends writes to auxiliary RAM, and RAMRD reads from it
+    switch(&mut program, 0xC005);
+    poke(&mut program, 0x0800, &[0x11]);
+    switch(&mut program, 0xC004);
+    poke(&mut program, 0x0800, &[0x22]);
+    switch(&mut program, 0xC003);
+    read_to(&mut program, 0x0800, 0x0300);
+    switch(&mut program, 0xC002);
+
+    // ALTZP covers the zero page and stack, whatever RAMRD and RAMWRT say
+    switch(&mut program, 0xC009);
+    poke(&mut program, 0x0010, &[0x33]);
+    poke(&mut program, 0x0110, &[0x33]);
+    switch(&mut program, 0xC008);
+    poke(&mut program, 0x0010, &[0x44]);
+    switch(&mut program, 0xC005);
+    poke(&mut program, 0x0011, &[0x44]);
+    switch(&mut program, 0xC004);
+
+    // With 80STORE, PAGE2 picks the bank for text page 1 (and hires page 1 with HIRES on)
+    // instead of RAMRD and RAMWRT
+    switch(&mut program, 0xC001);
+    switch(&mut program, 0xC055);
+    poke(&mut program, 0x0400, &[0x55]);
+    poke(&mut program, 0x2000, &[0x55]);
+    switch(&mut program, 0xC057);
+    poke(&mut program, 0x2001, &[0x55]);
+    switch(&mut program, 0xC054);
+    switch(&mut program, 0xC005);
+    poke(&mut program, 0x0401, &[0x66]);
+    poke(&mut program, 0x2002, &[0x66]);
+    poke(&mut program, 0x0900, &[0x66]);
+    switch(&mut program, 0xC004);
+    switch(&mut program, 0xC000);
+    end_program(&mut program);
+
+    let apple2 = run_iie(&program);
+    let main = &apple2.ram;
+    let aux = &apple2.mmu.as_ref().unwrap().aux_ram;
+
+    assert_eq!((main[0x0800], aux[0x0800]), (0x22, 0x11));
+    assert_eq!(main[0x0300], 0x11);
+    assert_eq!((main[0x0010], aux[0x0010]), (0x44, 0x33));
+    assert_eq!((main[0x0110], aux[0x0110]), (0x00, 0x33));
+    assert_eq!((main[0x0011], aux[0x0011]), (0x44, 0x00));
+    assert_eq!((main[0x0400], aux[0x0400]), (0x00, 0x55));
+    assert_eq!((main[0x2000], aux[0x2000]), (0x55, 0x00));
+    assert_eq!((main[0x2001], aux[0x2001]), (0x00, 0x55));
+    assert_eq!((main[0x0401], aux[0x0401]), (0x66, 0x00));
+    assert_eq!((main[0x2002], aux[0x2002]), (0x66, 0x00));
+    assert_eq!((main[0x0900], aux[0x0900]), (0x00, 0x66));
+}
+
+#[test]
+fn iie_status_reads() {
+    // Every switch that can be read back, with how to turn it on and off
+    const SWITCHES: [(u16, u16, u16); 14] = [
+        (0xC011, 0xC081, 0xC089), // RDLCBNK2
+        (0xC012, 0xC08B, 0xC081), // RDLCRAM
+        (0xC013, 0xC003, 0xC002), // RDRAMRD
+        (0xC014, 0xC005, 0xC004), // RDRAMWRT
+        (0xC015, 0xC007, 0xC006), // RDCXROM
+        (0xC016, 0xC009, 0xC008), // RDALTZP
+        (0xC017, 0xC00B, 0xC00A), // RDC3ROM
+        (0xC018, 0xC001, 0xC000), // RD80STORE
+        (0xC01A, 0xC051, 0xC050), // RDTEXT
+        (0xC01B, 0xC053, 0xC052), // RDMIXED
+        (0xC01C, 0xC055, 0xC054), // RDPAGE2
+        (0xC01D, 0xC057, 0xC056), // RDHIRES
+        (0xC01E, 0xC00F, 0xC00E), // RDALTCHAR
+        (0xC01F, 0xC00D, 0xC00C), // RD80COL
+    ];
+
+    // Each switch is checked on its own, holding the status in X until it's off again
+    // so the result is stored normally. Reading language card RAM would stop this program
+    // running from ROM, so that one runs from main RAM at $0800 instead.
+    const TAX: u8 = 0xAA;
+    const STX_ABS: u8 = 0x8E;
+    const RTS: u8 = 0x60;
+
+    let mut program = vec![];
+    for (i, &(status, on, off)) in SWITCHES.iter().enumerate() {
+        let dest = 0x0300 + 2 * i as u16;
+
+        // The MMU switches only respond to writes and the language card only to reads
+        let access = |addr: u16| match addr {
+            0xC000..0xC010 => [STA_ABS, addr as u8, (addr >> 8) as u8],
+            _ => [LDA_ABS, addr as u8, (addr >> 8) as u8],
+        };
+
+        let mut check = vec![];
+        check.extend(access(on));
+        check.extend([LDA_ABS, status as u8, (status >> 8) as u8, TAX]);
+        check.extend(access(off));
+        check.extend([STX_ABS, dest as u8, (dest >> 8) as u8]);
+        read_to(&mut check, status, dest + 1);
+
+        if status == 0xC012 {
+            check.push(RTS);
+            poke(&mut program, 0x0800, &check);
+            program.extend([JSR_ABS, 0x00, 0x08]);
+        } else {
+            program.extend(check);
+        }
+    }
+    end_program(&mut program);
+
+    let apple2 = run_iie(&program);
+    for (i, &(status, ..)) in SWITCHES.iter().enumerate() {
+        let on = apple2.ram[0x0300 + 2 * i];
+        let off = apple2.ram[0x0301 + 2 * i];
+        assert_eq!((on, off), (0x80, 0x00), "${status:04X}");
+    }
+}
+
+// Answers its slot ROM with its slot's page (like $C4) and $C800-$CFFF with that page plus 8
+struct RomCard(u8);
+
+impl Peripheral for RomCard {
+    fn tick(&mut self, _bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {}
+
+    fn io_select(&mut self, bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {
+        bus.set_data(self.0);
+    }
+
+    fn device_select(&mut self, _bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {}
+
+    fn io_strobe(&mut self, bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {
+        bus.set_data(self.0 + 8);
+    }
+}
+
+fn insert_rom_card(apple2: &mut Apple2<'static, NullAudio>, slot: usize) {
+    let card = Box::leak(Box::new(RomCard(0xC0 + slot as u8)));
+    apple2.insert_peripheral(card, slot);
+}
+
+#[test]
+fn iie_cx_rom_selection() {
+    let mut program = vec![];
+    let mut dest = 0x0300..;
+    let mut read = |program: &mut Vec<u8>, addr| read_to(program, addr, dest.next().unwrap());
+
+    // Slot ROM by default, except for slot 3 which also brings in internal $C800-$CFFF
+    read(&mut program, 0xC400);
+    read(&mut program, 0xC800);
+    read(&mut program, 0xC300);
+    read(&mut program, 0xC800);
+
+    // $CFFF hands $C800-$CFFF back, and cards let go of it too (even though they didn't answer it)
+    read(&mut program, 0xCFFF);
+    read(&mut program, 0xC800);
+    read(&mut program, 0xC400);
+    read(&mut program, 0xC800);
+
+    // INTCXROM takes over all of $C100-$CFFF
+    switch(&mut program, 0xC007);
+    read(&mut program, 0xC400);
+    read(&mut program, 0xC800);
+    switch(&mut program, 0xC006);
+
+    // SLOTC3ROM gives slot 3 back to the card
+    read(&mut program, 0xCFFF);
+    switch(&mut program, 0xC00B);
+    read(&mut program, 0xC300);
+    read(&mut program, 0xC800);
+    end_program(&mut program);
+
+    // Internal ROM holds $13 at $C300, $14 at $C400 and $18 at $C800
+    let mut fw_rom = test_rom(Model::Apple2e, &program);
+    for page in [0x13, 0x14, 0x18] {
+        patch_rom(&mut fw_rom, (page as u16 + 0xB0) << 8, &[page]);
+    }
+
+    let mut apple2 = boot_rom(Model::Apple2e, &fw_rom);
+    insert_rom_card(&mut apple2, 3);
+    insert_rom_card(&mut apple2, 4);
+    apple2.run_frames(1);
+
+    // Nothing answers $C800 straight after $CFFF, so it floats (with the blank text screen in RAM)
+    let expected = [
+        0xC4, 0xCC, 0x13, 0x18, 0x00, 0x00, 0xC4, 0xCC, 0x14, 0x18, 0xCC, 0xC3, 0xCB,
+    ];
+    assert_eq!(apple2.ram[0x0300..0x0300 + expected.len()], expected);
+}
+
+#[test]
+fn iie_video_rom_layout() {
+    // The top line of a glyph, as shown (left to right from bit 0) and as stored in the ROM
+    const DOTS: u8 = 0b0011100;
+    const NORMAL: u8 = 0x80 | (!DOTS & 0x7F);
+    const INVERSE: u8 = 0x80 | DOTS;
+    const FLASHING: u8 = !DOTS & 0x7F;
+
+    let mut char_rom = vec![0xFF; IIE_CHAR_ROM_SIZE];
+    for (code, byte) in [(0xC1, NORMAL), (0x01, INVERSE), (0x41, FLASHING)] {
+        char_rom[code * 8] = byte;
+        char_rom[CHAR_ROM_SIZE + code * 8] = byte;
+    }
+
+    let top_line = |lines: &[Scanline]| lines[0].bytes[..3].to_vec();
+    let text = |dots: [u8; 3]| dots.map(FrameByte::Text).to_vec();
+    let boot_with = |altcharset: bool| {
+        let mut program = vec![];
+        if altcharset {
+            switch(&mut program, 0xC00F);
+        }
+        poke(&mut program, 0x0400, &[0xC1, 0x01, 0x41]);
+        end_program(&mut program);
+        boot_roms(
+            Model::Apple2e,
+            &test_rom(Model::Apple2e, &program),
+            &char_rom,
+        )
+    };
+
+    // Normal and flashing characters are stored active low, while inverse is stored already inverted
+    let mut apple2 = boot_with(false);
+    let lines = capture_frames(&mut apple2, 1);
+    assert_eq!(top_line(&lines), text([DOTS, DOTS ^ 0x7F, DOTS]));
+
+    // Flashing characters then turn inverse for a while
+    let lines = capture_frames(&mut apple2, 15);
+    assert_eq!(top_line(&lines), text([DOTS, DOTS ^ 0x7F, DOTS ^ 0x7F]));
+
+    // Nothing in the alternate set flashes, since only the first 1K of the ROM can
+    let mut apple2 = boot_with(true);
+    let lines = capture_frames(&mut apple2, 16);
+    assert_eq!(top_line(&lines), text([DOTS, DOTS ^ 0x7F, DOTS]));
+}
+
+#[test]
+fn screen_text_styles() {
+    // Inverse, then normal, then flashing, at the top left of the screen
+    let text = b"NO BUFFERS AVAILABLE";
+    let bytes: Vec<u8> = (0..)
+        .zip(text)
+        .map(|(col, &char)| match col {
+            0..10 => char & 0x3F,
+            10 => char | 0x80,
+            _ => (char & 0x3F) | 0x40,
+        })
+        .collect();
+    let mut program = vec![];
+    poke(&mut program, 0x0400, &bytes);
+    end_program(&mut program);
+
+    let mut apple2 = boot(&program);
+    assert!(apple2.wait_for_text("NO BUFFERS AVAILABLE", 2).is_some());
+
+    let line = &apple2.screen_text()[0];
+    assert_eq!(line.len(), TEXT_COLS);
+    assert!(line.starts_with("NO BUFFERS AVAILABLE"), "{line}");
+
+    let styles = &apple2.screen_styles()[0];
+    assert_eq!(styles[..10], [CharStyle::Inverse; 10]);
+    assert_eq!(styles[10], CharStyle::Normal);
+    assert_eq!(styles[11..20], [CharStyle::Flash; 9]);
+}
+
+#[test]
+fn wav_audio_output() {
+    use headless::WavAudio;
+    use std::io::Cursor;
+
+    let mut wav = WavAudio::new(Cursor::new(Vec::new()), 44100).unwrap();
+    wav.feed_samples(&[0.0, 1.0, -1.0]);
+    wav.feed_samples(&[0.5, 2.0]);
+    wav.flush().unwrap();
+
+    let bytes = wav.get_ref().get_ref();
+    let u32_at = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(u32_at(4), 36 + 10);
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(16), 16);
+    assert_eq!(bytes[20..24], [1, 0, 1, 0]); // PCM, mono
+    assert_eq!(u32_at(24), 44100);
+    assert_eq!(u32_at(28), 44100 * 2);
+    assert_eq!(bytes[32..36], [2, 0, 16, 0]); // 2 byte frames of 16 bits
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(u32_at(40), 10);
+
+    // Out of range samples are clamped
+    let samples: Vec<i16> = bytes[44..]
+        .chunks(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    assert_eq!(samples, [0, i16::MAX, -i16::MAX, 16384, i16::MAX]);
+}
+
+#[test]
+fn wav_audio_error_is_sticky() {
+    use headless::WavAudio;
+    use std::io::Cursor;
+
+    // Only room for the header and a single sample
+    let mut buf = [0; 46];
+    let mut wav = WavAudio::new(Cursor::new(&mut buf[..]), 44100).unwrap();
+    wav.feed_samples(&[0.0, 0.0]);
+    wav.feed_samples(&[0.0]);
+
+    assert!(wav.flush().is_err());
+    assert!(wav.flush().is_err());
+}
+
+#[test]
+fn checksums() {
+    use headless::{adler32, crc32};
+
+    assert_eq!(crc32(b"123456789".iter()), 0xCBF43926);
+    assert_eq!(crc32([].iter()), 0);
+    assert_eq!(adler32(b"Wikipedia"), 0x11E60398);
+    assert_eq!(adler32(&[]), 1);
+}
+
+// A frame of the given size with a red first pixel and a blue last pixel
+fn test_frame(width: u32, height: u32) -> Vec<u32> {
+    let mut frame = vec![0x00FFFFFF; (width * height) as usize];
+    frame[0] = 0x00FF0000;
+    *frame.last_mut().unwrap() = 0x000000FF;
+    frame
+}
+
+fn video_frame(pixels: &[u32], width: u32, height: u32) -> peripheral::VideoFrame<'_> {
+    peripheral::VideoFrame {
+        pixels,
+        width,
+        height,
+    }
+}
+
+#[test]
+fn ppm_frame() {
+    let mut ppm = Vec::new();
+    let frame = test_frame(2, 3);
+    headless::write_ppm(video_frame(&frame, 2, 3), &mut ppm).unwrap();
+
+    let header = b"P6\n2 3\n255\n";
+    assert!(ppm.starts_with(header));
+    let pixels = &ppm[header.len()..];
+    assert_eq!(pixels.len(), 2 * 3 * 3);
+    assert_eq!(pixels[..6], [0xFF, 0, 0, 0xFF, 0xFF, 0xFF]);
+    assert_eq!(pixels[pixels.len() - 3..], [0, 0, 0xFF]);
+}
+
+#[test]
+fn png_frame() {
+    use headless::{adler32, crc32};
+
+    // Including the 560 dot wide monitors, whose image data needs several stored blocks,
+    // and the Videx's taller frames
+    let sizes = [
+        (2, 3),
+        (settings::DISP_WIDTH, settings::DISP_HEIGHT),
+        (2 * settings::DISP_WIDTH, settings::DISP_HEIGHT),
+        (640, 216),
+    ];
+    for (width, height) in sizes {
+        let mut png = Vec::new();
+        let frame = test_frame(width, height);
+        headless::write_png(video_frame(&frame, width, height), &mut png).unwrap();
+        assert_eq!(png[..8], *b"\x89PNG\r\n\x1a\n");
+
+        let mut chunks = Vec::new();
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let (kind, data) = (&rest[4..8], &rest[8..8 + len]);
+            let crc = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc, crc32(kind.iter().chain(data)), "{width}x{height}");
+            chunks.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        let kinds: Vec<_> = chunks.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, [b"IHDR", b"IDAT", b"IEND"]);
+        assert!(png.ends_with(&[0xAE, 0x42, 0x60, 0x82]));
+
+        let ihdr = chunks[0].1;
+        assert_eq!(ihdr[..4], width.to_be_bytes());
+        assert_eq!(ihdr[4..8], height.to_be_bytes());
+        assert_eq!(ihdr[8..], [8, 2, 0, 0, 0]);
+
+        // Unwrap the stored deflate blocks
+        let idat = chunks[1].1;
+        assert_eq!(idat[..2], [0x78, 0x01]);
+        let mut raw = Vec::new();
+        let mut blocks = &idat[2..idat.len() - 4];
+        loop {
+            let len = u16::from_le_bytes([blocks[1], blocks[2]]);
+            assert_eq!(u16::from_le_bytes([blocks[3], blocks[4]]), !len);
+            raw.extend_from_slice(&blocks[5..5 + len as usize]);
+            let last = blocks[0] == 1;
+            blocks = &blocks[5 + len as usize..];
+            if last {
+                break;
+            }
+        }
+        assert!(blocks.is_empty());
+        assert_eq!(idat[idat.len() - 4..], adler32(&raw).to_be_bytes());
+
+        let stride = 1 + 3 * width as usize;
+        assert_eq!(raw.len(), stride * height as usize);
+        assert!(raw.chunks(stride).all(|line| line[0] == 0));
+        assert_eq!(raw[..4], [0, 0xFF, 0, 0]);
+        assert_eq!(raw[raw.len() - 3..], [0, 0, 0xFF]);
+    }
+}
+
+#[test]
+fn image_size_mismatch() {
+    let frame = test_frame(2, 3);
+    for (pixels, width, height) in [(&frame[..], 3, 3), (&frame[..], 0, 3), (&[][..], 0, 0)] {
+        let frame = video_frame(pixels, width, height);
+        let err = headless::write_png(frame, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        let err = headless::write_ppm(frame, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    // The machine's own frames are as wide as the renderer makes them
+    let mut apple2 = boot(&[JMP_ABS, ORG as u8, (ORG >> 8) as u8]);
+    apple2.set_monitor(Monitor::Composite);
+    apple2.run_frames(1);
+    let frame = apple2.video_frame();
+    assert_eq!((frame.width, frame.height), (560, settings::DISP_HEIGHT));
+    headless::write_png(frame, &mut Vec::new()).unwrap();
+}
+
+// Fills the text screen while clicking the speaker, with a language card in slot 0
+fn busy_machine() -> Apple2<'static, NullAudio> {
+    let mut program = vec![];
+    let lp = here(&program);
+    program.extend([0xFE, 0x00, 0x04]); // INC $0400,X
+    program.push(0xE8); // INX
+    program.extend([LDA_ABS, 0x30, 0xC0]); // SPKR
+    // Write enable language card RAM (reading ROM, which the program runs from)
+    program.extend([LDA_ABS, 0x81, 0xC0]);
+    program.extend([LDA_ABS, 0x81, 0xC0]);
+    program.extend([0xEE, 0x00, 0xE0]); // INC $E000
+    program.extend([JMP_ABS, lp as u8, (lp >> 8) as u8]);
+
+    let mut apple2 = boot(&program);
+    let card = Box::leak(Box::new(peripheral::language::LanguageCard::new()));
+    apple2.insert_peripheral(card, 0);
+    apple2
+}
+
+// Where the machine got to after running on from a saved state
+fn outcome(apple2: &Apple2<NullAudio>) -> (Vec<u8>, Vec<String>, Vec<u32>, Vec<u8>) {
+    (
+        apple2.ram[0..0xC000].to_vec(),
+        apple2.screen_text(),
+        apple2.frame().to_vec(),
+        apple2.save_state(),
+    )
+}
+
+#[test]
+fn save_state_round_trip() {
+    let mut apple2 = busy_machine();
+    apple2.run_frames(3);
+    let state = apple2.save_state();
+    apple2.run_frames(2);
+    let expected = outcome(&apple2);
+
+    // Having gone off somewhere else, the machine picks up exactly where the state left off
+    apple2.run_frames(1);
+    apple2.load_state(&state).unwrap();
+    apple2.run_frames(2);
+    assert!(outcome(&apple2) == expected);
+}
+
+#[test]
+fn save_state_rejects_mismatches() {
+    let mut apple2 = busy_machine();
+    apple2.run_frames(1);
+    let state = apple2.save_state();
+
+    let mut bad_magic = state.clone();
+    bad_magic[0] ^= 0xFF;
+    assert_eq!(apple2.load_state(&bad_magic), Err("Not a save state"));
+
+    let mut bad_version = state.clone();
+    bad_version[4] = bad_version[4].wrapping_add(1);
+    assert_eq!(
+        apple2.load_state(&bad_version),
+        Err("Save state version is not supported")
+    );
+
+    assert!(apple2.load_state(&state[..state.len() - 1]).is_err());
+    assert_eq!(apple2.load_state(&state), Ok(()));
+}
+
+#[test]
+fn failed_load_state_changes_nothing() {
+    let mut apple2 = busy_machine();
+    apple2.run_frames(1);
+    let state = apple2.save_state();
+    apple2.run_frames(2);
+    let before = apple2.save_state();
+
+    // Cut off part way through, after the CPU and memory have been read
+    assert!(apple2.load_state(&state[..state.len() - 1]).is_err());
+    assert!(apple2.save_state() == before);
+
+    // And the machine runs on from where it was, not from the state it failed to load
+    apple2.run_frames(2);
+    let expected = outcome(&apple2);
+    apple2.load_state(&before).unwrap();
+    apple2.run_frames(2);
+    assert!(outcome(&apple2) == expected);
+}
+
+#[test]
+fn floating_bus() {
+    let mut program = vec![0xA0, 0x00]; // LDY #$00
+
+    // Reads of a soft switch, an empty slot's I/O and an empty slot's ROM, stored at $1000-$13FF
+    let sample = |program: &mut Vec<u8>, reads: &[(u16, u16)]| {
+        let lp = here(program);
+        for &(addr, dest) in reads {
+            program.extend([LDA_ABS, addr as u8, (addr >> 8) as u8]);
+            program.extend([0x99, dest as u8, (dest >> 8) as u8]); // STA dest,Y
+        }
+        program.push(0xC8); // INY
+        let offset = lp.wrapping_sub(here(program) + 2) as u8;
+        program.extend([0xD0, offset]); // BNE lp
+    };
+
+    // Lores still fetches from the text page
+    sample(&mut program, &[(0xC050, 0x1000)]);
+    program.extend([LDA_ABS, 0x57, 0xC0]); // HIRES
+    program.extend([LDA_ABS, 0x52, 0xC0]); // MIXED off
+    sample(
+        &mut program,
+        &[(0xC050, 0x1100), (0xC0E0, 0x1200), (0xC600, 0x1300)],
+    );
+    end_program(&mut program);
+
+    // Make it possible to tell where the video scanner fetched from.
+    // During horizontal blanking, lores and text are fetched from $1400 up instead (Sather 5-7).
+    let mut apple2 = boot(&program);
+    apple2.ram[0x0400..0x0800].fill(0xA5);
+    apple2.ram[0x1400..0x1800].fill(0xC3);
+    apple2.ram[0x2000..0x4000].fill(0x5A);
+    apple2.run_frames(2);
+
+    let lores = &apple2.ram[0x1000..0x1100];
+    assert!(
+        lores.iter().all(|&byte| byte == 0xA5 || byte == 0xC3),
+        "{lores:02X?}"
+    );
+    assert!(
+        lores.contains(&0xA5) && lores.contains(&0xC3),
+        "{lores:02X?}"
+    );
+    assert!(apple2.ram[0x1100..0x1400].iter().all(|&byte| byte == 0x5A));
+}
+
+#[test]
+fn annunciator_events() {
+    // Each access is on the last cycle of a 4 cycle instruction, after the 7 cycle reset
+    let mut program = vec![];
+    for addr in [0xC059, 0xC059, 0xC05B, 0xC058] {
+        program.extend([LDA_ABS, addr as u8, (addr >> 8) as u8]);
+    }
+    switch(&mut program, 0xC05F);
+    program.extend([LDA_ABS, 0x5E, 0xC0]);
+    end_program(&mut program);
+
+    let mut apple2 = boot(&program);
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let log = events.clone();
+    apple2.set_annunciator_handler(move |event| log.borrow_mut().push(event));
+
+    // Turning on AN0 again doesn't count as a change
+    let event = |annunciator, on, cycle| AnnunciatorEvent {
+        annunciator,
+        on,
+        cycle,
+    };
+    let expected = [
+        event(0, true, 10),
+        event(1, true, 18),
+        event(0, false, 22),
+        event(3, true, 26),
+        event(3, false, 30),
+    ];
+
+    apple2.run_frames(1);
+    assert_eq!(apple2.cycles(), 17030);
+    assert_eq!(apple2.annunciators(), [false, true, false, false]);
+    assert_eq!(events.take(), expected);
+
+    // Power on turns AN1 off, and counts cycles from the start again
+    apple2.power_cycle();
+    apple2.run_frames(1);
+    let events = events.take();
+    assert_eq!(events[0], event(1, false, 0));
+    assert_eq!(events[1..], expected);
+}
+
+#[test]
+fn game_inputs() {
+    // Times paddle 0 like the Monitor's PREAD (11 cycles a step), then reads the buttons, forever
+    let mut program = vec![];
+    let lp = here(&program);
+    program.extend([LDA_ABS, 0x70, 0xC0]); // PTRIG
+    program.extend([0xA0, 0x00]); // LDY #$00
+    program.extend([LDA_ABS, 0x64, 0xC0]); // PADDL0
+    program.extend([0x10, 0x03]); // BPL done
+    program.push(0xC8); // INY
+    program.extend([0xD0, 0xF8]); // BNE PADDL0
+    program.extend([0x8C, 0x00, 0x03]); // done: STY $0300
+    for button in 0..3 {
+        read_to(&mut program, 0xC061 + button, 0x0301 + button);
+    }
+    program.extend([JMP_ABS, lp as u8, (lp >> 8) as u8]);
+
+    let mut apple2 = boot(&program);
+    for value in [0, 10, 128, 255] {
+        apple2.set_paddle(0, value);
+        apple2.run_frames(2);
+        assert!(apple2.ram[0x300].abs_diff(value) <= 1, "{value}");
+    }
+
+    // Buttons show up in bit 7
+    let buttons = |apple2: &Apple2<NullAudio>| {
+        let buttons: [u8; 3] = apple2.ram[0x301..0x304].try_into().unwrap();
+        buttons.map(|byte| byte >> 7)
+    };
+    assert_eq!(buttons(&apple2), [0, 0, 0]);
+    apple2.set_button(0, true);
+    apple2.set_button(2, true);
+    apple2.run_frames(1);
+    assert_eq!(buttons(&apple2), [1, 0, 1]);
+
+    // With the shift key mod, PB2 is pulled up unless shift is held
+    apple2.set_button(2, false);
+    apple2.set_shift_key_mod(true);
+    apple2.run_frames(1);
+    assert_eq!(buttons(&apple2), [1, 0, 1]);
+    apple2.set_shift_key(true);
+    apple2.run_frames(1);
+    assert_eq!(buttons(&apple2), [1, 0, 0]);
+}
+
+#[test]
+fn typed_keys_wait_for_strobe() {
+    // Keeps reading the keyboard without clearing the strobe until $0380 is set,
+    // then stores each key with how many times it polled before the key came in
+    let mut program = vec![];
+    read_to(&mut program, 0xC000, 0x0300); // wait:
+    program.extend([LDA_ABS, 0x80, 0x03]);
+    program.extend([0xF0, 0xF5]); // BEQ wait
+    program.extend([0xA2, 0x00]); // LDX #$00
+    let lp = here(&program);
+    program.extend([0xA0, 0x00]); // LDY #$00
+    program.push(0xC8); // key: INY
+    program.extend([LDA_ABS, 0x00, 0xC0]); // KBD
+    program.extend([0x10, 0xFA]); // BPL key
+    switch(&mut program, 0xC010); // KBDSTRB
+    program.extend([0x9D, 0x10, 0x03]); // STA $0310,X
+    program.push(0x98); // TYA
+    program.extend([0x9D, 0x20, 0x03]); // STA $0320,X
+    program.push(0xE8); // INX
+    program.extend([JMP_ABS, lp as u8, (lp >> 8) as u8]);
+
+    // Only the first key gets latched while the strobe is left set
+    let mut apple2 = boot(&program);
+    apple2.type_text("ab\n1");
+    apple2.run_frames(2);
+    assert_eq!(apple2.ram[0x300], b'A' | 0x80);
+    assert!(apple2.is_typing());
+
+    // Then each one as soon as the last is read
+    apple2.ram[0x380] = 1;
+    apple2.run_frames(1);
+    assert!(!apple2.is_typing());
+    assert_eq!(apple2.ram[0x310..0x314], [0xC1, 0xC2, 0x8D, 0xB1]);
+    assert!(apple2.ram[0x321..0x324].iter().all(|&polls| polls <= 2));
+
+    // A type delay holds each key back that many cycles after the strobe is cleared,
+    // at 9 cycles a poll
+    apple2.set_type_delay(900);
+    apple2.type_text("xyz");
+    apple2.run_frames(1);
+    assert_eq!(apple2.ram[0x314..0x317], [0xD8, 0xD9, 0xDA]);
+    assert!(
+        apple2.ram[0x325..0x327]
+            .iter()
+            .all(|polls| (99..=101).contains(polls)),
+        "{:?}",
+        &apple2.ram[0x325..0x327]
+    );
+}
+
+#[test]
+fn arrow_keys() {
+    let mut apple2 = boot(&[JMP_ABS, ORG as u8, (ORG >> 8) as u8]);
+    apple2.input_arrow(true);
+    assert_eq!(apple2.io.keyboard.latch(), 0x95);
+    apple2.input_arrow(false);
+    assert_eq!(apple2.io.keyboard.latch(), 0x88);
+
+    // The II+ has no up or down arrows
+    apple2.input_arrow_key(Arrow::Up);
+    assert_eq!(apple2.io.keyboard.latch(), 0x88);
+    apple2.input_arrow_key(Arrow::Right);
+    assert_eq!(apple2.io.keyboard.latch(), 0x95);
+}
+
+// Counts how many times the machine was reset in $0300
+fn count_resets(model: Model, needs_ctrl: bool, presses: &[bool]) -> u8 {
+    let mut program = vec![0xEE, 0x00, 0x03]; // INC $0300
+    end_program(&mut program);
+
+    let mut apple2 = boot_rom(model, &test_rom(model, &program));
+    apple2.set_reset_needs_ctrl(needs_ctrl);
+    apple2.run_frames(1);
+    for &ctrl in presses {
+        apple2.reset_key(ctrl);
+        apple2.run_frames(1);
+    }
+    apple2.ram[0x300]
+}
+
+#[test]
+fn reset_key() {
+    assert_eq!(count_resets(Model::Apple2Plus, false, &[false, true]), 3);
+    assert_eq!(count_resets(Model::Apple2Plus, true, &[false, true]), 2);
+
+    // The IIe always needs CTRL, whatever the switch says
+    assert_eq!(count_resets(Model::Apple2e, false, &[false, true]), 2);
+}
+
+#[test]
+fn cold_boot_defaults() {
+    // The display and language card statuses, as read back on the IIe
+    const STATUSES: [u16; 6] = [0xC01A, 0xC01B, 0xC01C, 0xC01D, 0xC011, 0xC012];
+
+    // Records the statuses at $0300 as the program starts, changes them all
+    // (and turns on AN0), then records them again at $0310
+    let mut program = vec![];
+    for (status, dest) in STATUSES.into_iter().zip(0x0300..) {
+        read_to(&mut program, status, dest);
+    }
+    for addr in [0xC050, 0xC053, 0xC055, 0xC057, 0xC089, 0xC089, 0xC059] {
+        program.extend([LDA_ABS, addr as u8, (addr >> 8) as u8]);
+    }
+    for (status, dest) in STATUSES.into_iter().zip(0x0310..) {
+        read_to(&mut program, status, dest);
+    }
+    end_program(&mut program);
+
+    let defaults = [0x80, 0x00, 0x00, 0x00, 0x80, 0x00];
+    let changed = [0x00, 0x80, 0x80, 0x80, 0x00, 0x00];
+    let mut apple2 = run_iie(&program);
+    assert_eq!(apple2.ram[0x300..0x306], defaults);
+    assert_eq!(apple2.ram[0x310..0x316], changed);
+    assert_eq!(apple2.annunciators(), [true, false, false, false]);
+
+    // RESET only puts the language card back
+    apple2.reset();
+    apple2.run_frames(1);
+    assert_eq!(apple2.ram[0x300..0x304], changed[..4]);
+    assert_eq!(apple2.ram[0x304..0x306], defaults[4..]);
+
+    // But a cold boot puts everything back
+    apple2.power_cycle();
+    assert_eq!(apple2.annunciators(), [false; 4]);
+    apple2.run_frames(1);
+    assert_eq!(apple2.ram[0x300..0x306], defaults);
+}
+
+// Hangs on to every sample played
+struct CaptureAudio(Vec<f32>);
+
+impl Audio for CaptureAudio {
+    fn feed_samples(&mut self, samples: &[f32]) {
+        self.0.extend_from_slice(samples);
+    }
+}
+
+#[test]
+fn power_cycle_sound() {
+    // Leaves the speaker pulled to the other side, so it drifts back to the middle from there
+    let mut program = vec![];
+    for _ in 0..3 {
+        program.extend([LDA_ABS, 0x30, 0xC0]);
+    }
+    end_program(&mut program);
+
+    let fw_rom = test_rom(Model::Apple2Plus, &program);
+    let mut apple2 = Apple2Builder::new(Model::Apple2Plus)
+        .fw_rom(&fw_rom)
+        .char_rom(&[0; 2048])
+        .build(CaptureAudio(Vec::new()))
+        .unwrap();
+    apple2.init();
+    apple2.run_frames(1);
+    let first = std::mem::take(&mut apple2.audio_mut().0);
+    assert!(first.iter().any(|&sample| sample != 0.0));
+
+    // A cold boot sounds just the same as the first time
+    apple2.run_frames(3);
+    apple2.power_cycle();
+    apple2.audio_mut().0.clear();
+    apple2.run_frames(1);
+    assert!(apple2.audio().0 == first);
+}
+
+#[test]
+fn random_ram_init() {
+    let mut apple2 = boot(&[JMP_ABS, ORG as u8, (ORG >> 8) as u8]);
+    assert!(apple2.ram[0..0xC000].iter().all(|&byte| byte == 0));
+
+    // The same seed always gives the same RAM, and a different one doesn't
+    apple2.set_ram_init(RamInit::Random(1));
+    apple2.power_cycle();
+    let ram = apple2.ram[0..0xC000].to_vec();
+    let mut expected = vec![0; 0xC000];
+    RamInit::Random(1).fill(&mut expected);
+    assert!(ram == expected);
+    assert!(ram.iter().any(|&byte| byte != 0));
+
+    apple2.run_frames(1);
+    apple2.power_cycle();
+    assert!(apple2.ram[0..0xC000] == ram);
+
+    apple2.set_ram_init(RamInit::Random(2));
+    apple2.power_cycle();
+    assert!(apple2.ram[0..0xC000] != ram);
+}
+
+// Each access made by a DMA card, as (slot, address, data)
+type DmaLog = Rc<RefCell<Vec<(usize, u16, u8)>>>;
+
+// Once `delay` cycles have passed, takes over the bus to read `addr`, write `bytes` after it,
+// then read `addr` again, logging each access
+struct DmaCard {
+    slot: usize,
+    delay: usize,
+    ops: std::collections::VecDeque<(u16, Option<u8>)>,
+    current: Option<(u16, Option<u8>)>,
+    log: DmaLog,
+}
+
+impl DmaCard {
+    fn new(slot: usize, delay: usize, addr: u16, bytes: &[u8], log: &DmaLog) -> Self {
+        let mut ops = std::collections::VecDeque::from([(addr, None)]);
+        ops.extend(
+            (addr + 1..)
+                .zip(bytes)
+                .map(|(addr, &byte)| (addr, Some(byte))),
+        );
+        ops.push_back((addr, None));
+        DmaCard {
+            slot,
+            delay,
+            ops,
+            current: None,
+            log: log.clone(),
+        }
+    }
+}
+
+impl Peripheral for DmaCard {
+    fn tick(&mut self, _bus: &mut dyn Bus, pins: &mut peripheral::Pins) {
+        self.delay = self.delay.saturating_sub(1);
+        pins.set_dma(self.delay == 0 && !self.ops.is_empty());
+    }
+
+    fn io_select(&mut self, _bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {}
+
+    fn device_select(&mut self, _bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {}
+
+    fn io_strobe(&mut self, _bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {}
+
+    fn dma_start(&mut self, bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {
+        self.current = self.ops.pop_front();
+        match self.current {
+            Some((addr, Some(byte))) => bus.start_write(addr, byte),
+            Some((addr, None)) => bus.start_read(addr),
+            None => (),
+        }
+    }
+
+    fn dma_end(&mut self, bus: &mut dyn Bus, _pins: &mut peripheral::Pins) {
+        if let Some((addr, _)) = self.current.take() {
+            self.log.borrow_mut().push((self.slot, addr, bus.data()));
+        }
+    }
+}
+
+#[test]
+fn dma() {
+    // Counts up at $0300 while filling $0400-$04FF with 0-255
+    let mut program = vec![0xA2, 0x00]; // LDX #$00
+    let lp = here(&program);
+    program.extend([0xEE, 0x00, 0x03]); // INC $0300
+    program.push(0x8A); // TXA
+    program.extend([0x9D, 0x00, 0x04]); // STA $0400,X
+    program.push(0xE8); // INX
+    program.extend([0xD0, (lp.wrapping_sub(here(&program) + 2)) as u8]); // BNE lp
+    end_program(&mut program);
+
+    // Both cards ask for the bus at once, part way through the loop
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut apple2 = boot(&program);
+    for (slot, byte) in [(2, 0x22), (5, 0x55)] {
+        let card = DmaCard::new(slot, 1000, 0x0300, &[byte; 3], &log);
+        apple2.insert_peripheral(Box::leak(Box::new(card)), slot);
+    }
+    apple2.run_frames(1);
+
+    // The higher slot goes first, then the other takes over straight away,
+    // with the CPU stopped the whole time
+    let log = log.take();
+    let count = log[0].2;
+    assert!(count > 0);
+    let slot_ops = |slot, byte| {
+        [
+            (slot, 0x0300, count),
+            (slot, 0x0301, byte),
+            (slot, 0x0302, byte),
+            (slot, 0x0303, byte),
+            (slot, 0x0300, count),
+        ]
+    };
+    assert_eq!(log[..5], slot_ops(5, 0x55));
+    assert_eq!(log[5..], slot_ops(2, 0x22));
+
+    // The CPU carried on where it left off
+    assert_eq!(apple2.ram[0x0300], 0);
+    assert!((0..=255).all(|i| apple2.ram[0x0400 + i] == i as u8));
+    assert_eq!(apple2.ram[0x0301..0x0304], [0x22; 3]);
+}
+
+#[test]
+fn bus_recording_error() {
+    // Only just enough room for the header, so writing fails part way through the first frame
+    let buf = Box::leak(Box::new([0; 1024]));
+    let mut apple2 = boot(&[JMP_ABS, ORG as u8, (ORG >> 8) as u8]);
+    apple2
+        .start_bus_recording(Box::new(std::io::Cursor::new(&mut buf[..])))
+        .unwrap();
+    apple2.run_frames(1);
+
+    let e = apple2.stop_bus_recording().unwrap_err();
+    assert_eq!(e.kind(), std::io::ErrorKind::WriteZero);
+    assert!(apple2.stop_bus_recording().is_ok());
+}
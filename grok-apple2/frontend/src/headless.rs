@@ -0,0 +1,88 @@
+use grok_apple2_core::Apple2;
+use grok_apple2_core::headless::{self, WavAudio};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+// Sound is only written out if --wav was given
+pub type WavRecorder = Option<WavAudio<BufWriter<File>>>;
+
+pub fn wav_recorder(path: Option<&Path>, sample_rate: u32) -> std::io::Result<WavRecorder> {
+    path.map(|path| WavAudio::new(BufWriter::new(File::create(path)?), sample_rate))
+        .transpose()
+}
+
+pub fn finish_wav(recorder: &mut WavRecorder) {
+    if let Some(wav) = recorder
+        && let Err(e) = wav.flush()
+    {
+        eprintln!("Failed to write WAV: {e}");
+    }
+}
+
+pub struct Options {
+    pub frames: usize,
+    pub dump_dir: Option<PathBuf>,
+    pub dump_every: usize,
+    pub ppm: bool,
+    pub until_text: Option<String>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            // About 5 seconds, long enough to boot most disks
+            frames: 300,
+            dump_dir: None,
+            dump_every: 0,
+            ppm: false,
+            until_text: None,
+        }
+    }
+}
+
+// Runs as fast as possible without a window or audio device, stopping early once `until_text`
+// is on screen (if given). Dumps every `dump_every` frames (and always the last one) if a
+// directory was given, and returns whether `until_text` showed up.
+pub fn run(apple2: &mut Apple2<WavRecorder>, options: &Options) -> std::io::Result<bool> {
+    let mut result = Ok(());
+    let mut frame = 0;
+    let found = apple2.run_until(options.frames, |apple2| {
+        frame += 1;
+        let found = options
+            .until_text
+            .as_ref()
+            .is_some_and(|text| apple2.screen_contains(text));
+
+        let dump = found
+            || frame == options.frames
+            || (options.dump_every > 0 && frame.is_multiple_of(options.dump_every));
+        if let Some(dir) = &options.dump_dir
+            && dump
+            && result.is_ok()
+        {
+            result = dump_frame(apple2, dir, frame, options.ppm);
+        }
+        found
+    });
+
+    result?;
+    Ok(found.is_some())
+}
+
+fn dump_frame(
+    apple2: &Apple2<WavRecorder>,
+    dir: &Path,
+    frame: usize,
+    ppm: bool,
+) -> std::io::Result<()> {
+    let ext = if ppm { "ppm" } else { "png" };
+    let path = dir.join(format!("frame_{frame:05}.{ext}"));
+    let mut out = BufWriter::new(File::create(&path)?);
+    if ppm {
+        headless::write_ppm(apple2.video_frame(), &mut out)?;
+    } else {
+        headless::write_png(apple2.video_frame(), &mut out)?;
+    }
+    out.flush()
+}
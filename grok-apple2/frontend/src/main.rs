@@ -1,16 +1,25 @@
+use grok_apple2_core::headless::TeeAudio;
 use grok_apple2_core::peripheral::serial::SuperSerial;
+use grok_apple2_core::peripheral::videx::{self, Videoterm};
 use grok_apple2_core::peripheral::{disk, language};
-use grok_apple2_core::{Apple2, settings};
+use grok_apple2_core::tape::Tape;
+use grok_apple2_core::{Apple2, Apple2Builder, Arrow, Audio, Model, Monitor, Phosphor, settings};
 use sdl2::EventPump;
 use sdl2::audio::{AudioCallback, AudioDevice, AudioSpecDesired};
+use sdl2::clipboard::ClipboardUtil;
 use sdl2::event::Event;
 use sdl2::keyboard::{Keycode, Mod};
 use sdl2::pixels::PixelFormatEnum;
-use sdl2::render::{Canvas, Texture};
-use sdl2::video::Window;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{Window, WindowContext};
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+mod headless;
 mod serial;
+use headless::WavRecorder;
 use serial::StdSerialPort;
 
 // The correct ROM files must be placed at the paths below
@@ -21,22 +30,59 @@ const CHAR_ROM: [u8; 0x800] = *include_bytes!("../roms/char_set.rom");
 const DISK2_ROM: [u8; 0x100] = *include_bytes!("../roms/disk2.rom");
 const SSC_ROM: [u8; 0x800] = *include_bytes!("../roms/ssc.rom");
 
+// The Videoterm is optional, so its ROMs are loaded at runtime instead,
+// as are the ROMs for any model other than the II+
+const VIDEX_SLOT: usize = 3;
+
 const FRAME_RATE: u32 = 60;
 const US_PER_FRAME: u64 = 1000000 / FRAME_RATE as u64;
-const SAMPLE_BUF_SZ: usize = 1024;
 const SAMPLE_VOLUME: f32 = 0.5;
 
+// Emulation is paced to keep about this much audio queued up
+const AUDIO_LATENCY_MS: usize = 50;
+const MAX_AUDIO_LATENCY_MS: usize = 500;
+
 struct SdlDisplay {
     canvas: Canvas<Window>,
+    texture_creator: TextureCreator<WindowContext>,
     texture: Texture,
+    size: (u32, u32),
 }
 
 impl SdlDisplay {
-    fn draw(&mut self, frame: &[u32]) {
+    fn new(canvas: Canvas<Window>) -> Self {
+        let texture_creator = canvas.texture_creator();
+        let size = (settings::DISP_WIDTH, settings::DISP_HEIGHT);
+        let texture = Self::create_texture(&texture_creator, size);
+        SdlDisplay {
+            canvas,
+            texture_creator,
+            texture,
+            size,
+        }
+    }
+
+    fn create_texture(
+        texture_creator: &TextureCreator<WindowContext>,
+        (width, height): (u32, u32),
+    ) -> Texture {
+        texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGB888, width, height)
+            .unwrap()
+    }
+
+    fn draw(&mut self, frame: &[u32], width: u32, height: u32) {
+        // Frames come in different sizes depending on the renderer (and the Videoterm's are
+        // different again), but they're all stretched to fill the window
+        if (width, height) != self.size {
+            self.size = (width, height);
+            self.texture = Self::create_texture(&self.texture_creator, self.size);
+        }
+
         self.texture
             .with_lock(None, |buf, pitch| {
                 for (src, dst) in frame
-                    .chunks_exact(settings::DISP_WIDTH as usize)
+                    .chunks_exact(width as usize)
                     .zip(buf.chunks_exact_mut(pitch))
                 {
                     for (px, slot) in src.iter().zip(dst.chunks_exact_mut(4)) {
@@ -50,22 +96,69 @@ impl SdlDisplay {
     }
 }
 
-pub struct SquareWave {
-    buffer: [f32; SAMPLE_BUF_SZ],
-    sample_idx: usize,
-    buf_idx: usize,
+// The monitor F9 switches to after this one
+fn next_monitor(monitor: Monitor) -> Monitor {
+    match monitor {
+        Monitor::Color => Monitor::Composite,
+        Monitor::Composite => Monitor::Rgb,
+        Monitor::Rgb => Monitor::Monochrome(Phosphor::Green),
+        Monitor::Monochrome(Phosphor::Green) => Monitor::Monochrome(Phosphor::Amber),
+        Monitor::Monochrome(Phosphor::Amber) => Monitor::Monochrome(Phosphor::White),
+        Monitor::Monochrome(Phosphor::White) => Monitor::Color,
+    }
+}
+
+// Which video output F10 shows when a Videoterm is installed,
+// where the soft video switch follows annunciator 0 like the real one
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VideoSwitch {
+    Annunciator,
+    Apple,
+    Videx,
+}
+
+impl VideoSwitch {
+    fn next(self) -> Self {
+        match self {
+            VideoSwitch::Annunciator => VideoSwitch::Apple,
+            VideoSwitch::Apple => VideoSwitch::Videx,
+            VideoSwitch::Videx => VideoSwitch::Annunciator,
+        }
+    }
+
+    fn shows_videx(self, an0: bool) -> bool {
+        match self {
+            VideoSwitch::Annunciator => an0,
+            VideoSwitch::Apple => false,
+            VideoSwitch::Videx => true,
+        }
+    }
+}
+
+// Samples waiting to be played, shared between the emulator and the audio callback
+type SampleQueue = Arc<Mutex<VecDeque<f32>>>;
+
+struct SdlPlayback {
+    queue: SampleQueue,
 }
 
-impl SquareWave {
-    pub fn insert_sample(&mut self, sample: f32) {
-        self.buffer[self.buf_idx] = sample;
-        self.buf_idx += 1;
-        self.buf_idx %= SAMPLE_BUF_SZ;
+impl AudioCallback for SdlPlayback {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        let mut queue = self.queue.lock().unwrap();
+        for x in out.iter_mut() {
+            // Play silence if the emulator falls behind
+            *x = queue.pop_front().unwrap_or(0.0);
+        }
     }
 }
 
 struct SdlAudio {
-    device: AudioDevice<SquareWave>,
+    // Never used directly, but playback stops once dropped
+    _device: AudioDevice<SdlPlayback>,
+    queue: SampleQueue,
+    sample_rate: u32,
 }
 
 impl SdlAudio {
@@ -78,54 +171,104 @@ impl SdlAudio {
             samples: Some(512),
         };
 
-        let wave = SquareWave {
-            buffer: [0.0; SAMPLE_BUF_SZ],
-            sample_idx: 0,
-            buf_idx: 0,
+        let queue = SampleQueue::default();
+        let playback = SdlPlayback {
+            queue: queue.clone(),
         };
 
         let device = audio_subsystem
-            .open_playback(None, &audio_spec, |_| wave)
+            .open_playback(None, &audio_spec, |_| playback)
             .unwrap();
         device.resume();
 
-        SdlAudio { device }
+        // The device may not support the rate asked for
+        let sample_rate = device.spec().freq as u32;
+        SdlAudio {
+            _device: device,
+            queue,
+            sample_rate,
+        }
+    }
+}
+
+impl grok_apple2_core::Audio for SdlAudio {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
     }
 
-    fn insert_samples(&mut self, samples: &[bool]) {
-        let mut lock = self.device.lock();
-        for s in samples {
-            lock.insert_sample(match s {
-                true => SAMPLE_VOLUME,
-                false => -SAMPLE_VOLUME,
-            });
+    fn feed_samples(&mut self, samples: &[f32]) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.extend(samples.iter().map(|s| s * SAMPLE_VOLUME));
+
+        // Drop the oldest samples rather than let latency build up forever
+        let max = self.sample_rate as usize * MAX_AUDIO_LATENCY_MS / 1000;
+        if queue.len() > max {
+            let excess = queue.len() - max;
+            queue.drain(..excess);
         }
     }
+
+    fn buffered(&self) -> Option<usize> {
+        Some(self.queue.lock().unwrap().len())
+    }
 }
 
-impl grok_apple2_core::Audio for SdlAudio {
-    fn feed_samples(&mut self, samples: &[bool]) {
-        self.insert_samples(samples);
+// The model to emulate, along with any ROMs given to replace the built-in II+ ones
+struct Roms {
+    model: Model,
+    fw_rom: Option<Vec<u8>>,
+    char_rom: Option<Vec<u8>>,
+}
+
+impl Roms {
+    fn build<'a, A: Audio>(&self, audio: A) -> Apple2<'a, A> {
+        let fw_rom = self.fw_rom.as_deref().unwrap_or(&FW_ROM);
+        let char_rom = self.char_rom.as_deref().unwrap_or(&CHAR_ROM);
+        Apple2Builder::new(self.model)
+            .fw_rom(fw_rom)
+            .char_rom(char_rom)
+            .build(audio)
+            .unwrap_or_else(|e| {
+                eprintln!("{e}");
+                std::process::exit(1);
+            })
     }
 }
 
-impl AudioCallback for SquareWave {
-    type Channel = f32;
+fn parse_model(name: &str) -> Model {
+    match name {
+        "ii" => Model::Apple2,
+        "ii+" => Model::Apple2Plus,
+        "iie" => Model::Apple2e,
+        "iie-enhanced" => Model::Apple2eEnhanced,
+        _ => usage(),
+    }
+}
 
-    fn callback(&mut self, out: &mut [f32]) {
-        for x in out.iter_mut() {
-            if self.sample_idx == self.buf_idx {
-                *x = 0.0;
-            } else {
-                *x = self.buffer[self.sample_idx];
-                self.sample_idx += 1;
-                self.sample_idx %= SAMPLE_BUF_SZ;
-            }
-        }
+fn window_title(model: Model) -> &'static str {
+    match model {
+        Model::Apple2 => "Apple ][",
+        Model::Apple2Plus => "Apple ][+",
+        Model::Apple2e => "Apple //e",
+        Model::Apple2eEnhanced => "Enhanced Apple //e",
     }
 }
 
-fn handle_input(apple2: &mut Apple2<SdlAudio>, event_pump: &mut EventPump) -> bool {
+// The tape to play with F5, and where F6 saves recordings to
+struct Tapes {
+    input: Option<Tape>,
+    output: Option<PathBuf>,
+}
+
+fn handle_input(
+    apple2: &mut Apple2<TeeAudio<SdlAudio, WavRecorder>>,
+    event_pump: &mut EventPump,
+    clipboard: &ClipboardUtil,
+    tapes: &Tapes,
+    monitor: &mut Monitor,
+    video_switch: &mut VideoSwitch,
+    held_keys: &mut HashSet<Keycode>,
+) -> bool {
     // TODO: Escape keys, and will need to change key for reset()
 
     for event in event_pump.poll_iter() {
@@ -135,26 +278,117 @@ fn handle_input(apple2: &mut Apple2<SdlAudio>, event_pump: &mut EventPump) -> bo
             }
             Event::KeyDown {
                 keycode: Some(Keycode::Escape),
+                keymod,
+                ..
+            } => {
+                // Escape stands in for the RESET key
+                apple2.reset_key(keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD));
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F5),
+                ..
+            } => {
+                if let Some(tape) = &tapes.input {
+                    // Tapes made from binary files know where they go, so type the Monitor command to read them
+                    if let Some(command) = tape.read_command() {
+                        apple2.type_text(&command);
+                    }
+                    apple2.play_tape(tape.clone());
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F6),
+                ..
+            } => {
+                if let Some(path) = &tapes.output {
+                    match apple2.stop_tape_recording() {
+                        Some(tape) => save_tape(&tape, path),
+                        None => apple2.start_tape_recording(),
+                    }
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F9),
                 ..
             } => {
-                apple2.reset();
+                *monitor = next_monitor(*monitor);
+                apple2.set_monitor(*monitor);
             }
+            Event::KeyDown {
+                keycode: Some(Keycode::F10),
+                ..
+            } => {
+                *video_switch = video_switch.next();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::F12),
+                ..
+            } => {
+                apple2.power_cycle();
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::Insert),
+                keymod,
+                ..
+            } if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => {
+                // Shift+Insert pastes from the host clipboard
+                if let Ok(text) = clipboard.clipboard_text() {
+                    apple2.type_text(&text);
+                }
+            }
+            Event::KeyDown {
+                keycode: Some(Keycode::LShift | Keycode::RShift),
+                ..
+            } => apple2.set_shift_key(true),
+            Event::KeyUp {
+                keycode: Some(Keycode::LShift | Keycode::RShift),
+                ..
+            } => apple2.set_shift_key(false),
             Event::KeyDown {
                 keycode: Some(keycode),
                 keymod,
                 ..
             } => {
+                held_keys.insert(keycode);
+
                 // Special case for arrow keys because they don't have an ASCII code
-                if keycode == Keycode::Right {
-                    apple2.input_arrow(true);
-                } else if keycode == Keycode::Left {
-                    apple2.input_arrow(false);
-                } else {
-                    let shift = keymod.contains(Mod::LSHIFTMOD) || keymod.contains(Mod::RSHIFTMOD);
-                    let ctrl = keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD);
-                    apple2.input(keycode as u8, shift, ctrl);
+                match keycode {
+                    Keycode::Right => apple2.input_arrow_key(Arrow::Right),
+                    Keycode::Left => apple2.input_arrow_key(Arrow::Left),
+                    Keycode::Up => apple2.input_arrow_key(Arrow::Up),
+                    Keycode::Down => apple2.input_arrow_key(Arrow::Down),
+                    _ => {
+                        let shift =
+                            keymod.contains(Mod::LSHIFTMOD) || keymod.contains(Mod::RSHIFTMOD);
+                        let ctrl = keymod.contains(Mod::LCTRLMOD) || keymod.contains(Mod::RCTRLMOD);
+                        apple2.input(keycode as u8, shift, ctrl);
+                    }
                 }
             }
+            Event::KeyUp {
+                keycode: Some(keycode),
+                ..
+            } => {
+                // The IIe can only tell once every key has been let go
+                held_keys.remove(&keycode);
+                if held_keys.is_empty() {
+                    apple2.key_released();
+                }
+            }
+            // The first two axes of a joystick are paddles 0 and 1
+            Event::JoyAxisMotion {
+                axis_idx: axis @ 0..=1,
+                value,
+                ..
+            } => apple2.set_paddle(axis as usize, ((value as i32 + 0x8000) >> 8) as u8),
+            Event::JoyButtonDown {
+                button_idx: button @ 0..=2,
+                ..
+            } => apple2.set_button(button as usize, true),
+            Event::JoyButtonUp {
+                button_idx: button @ 0..=2,
+                ..
+            } => apple2.set_button(button as usize, false),
             _ => {}
         }
     }
@@ -162,18 +396,187 @@ fn handle_input(apple2: &mut Apple2<SdlAudio>, event_pump: &mut EventPump) -> bo
     true
 }
 
+fn usage() -> ! {
+    eprintln!(
+        "Usage: grok-apple2 [--model ii|ii+|iie|iie-enhanced [--rom FIRMWARE CHARSET]] [--type TEXT] [--tape FILE] [--tape-addr HEX] [--tape-fast] [--tape-out FILE] [--wav FILE] [--videx FIRMWARE CHARSET] [--headless [--frames N] [--until-text TEXT] [--dump-dir DIR] [--dump-every N] [--ppm]] [DISK]"
+    );
+    std::process::exit(1);
+}
+
+// Binary files are loaded at `load_addr`
+fn load_tape(tape_file: &str, load_addr: u16) -> Tape {
+    let buffer = std::fs::read(tape_file).unwrap();
+    let ext = Path::new(tape_file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    match ext.as_str() {
+        "wav" => Tape::from_wav(&buffer).unwrap(),
+        "ct2" => Tape::from_ct2(&buffer).unwrap(),
+        // Anything else is treated as raw binary to be read back with the Monitor's R command
+        _ => Tape::from_binary(&buffer, load_addr),
+    }
+}
+
+fn load_rom<const N: usize>(path: &str) -> [u8; N] {
+    let buffer = std::fs::read(path).unwrap();
+    buffer
+        .try_into()
+        .unwrap_or_else(|_| panic!("{path} should be {N} bytes"))
+}
+
+fn save_tape(tape: &Tape, path: &Path) {
+    if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("ct2"))
+    {
+        std::fs::write(path, tape.to_ct2()).unwrap();
+    } else {
+        let mut out = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        tape.write_wav(&mut out).unwrap();
+    }
+}
+
+fn insert_disk(disk_card: &mut disk::ControllerCard, disk_file: &str) {
+    let buffer = std::fs::read(disk_file).unwrap();
+    let ext = std::path::Path::new(disk_file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("");
+    match ext {
+        "woz" => disk_card.insert_woz(&buffer),
+        "dsk" => disk_card.insert_dsk(&buffer),
+        "po" => disk_card.insert_po(&buffer),
+        _ => panic!("Unsupported disk format: .{}", ext),
+    }
+}
+
+// No serial card here, since it needs to set up a port on the host,
+// and no Videoterm since only the Apple's video is dumped
+fn run_headless(
+    roms: &Roms,
+    disk_file: Option<&str>,
+    type_text: Option<&str>,
+    wav_file: Option<&Path>,
+    options: &headless::Options,
+) {
+    let mut language_card = language::LanguageCard::new();
+    let mut disk_card = disk::ControllerCard::new(DISK2_ROM, settings::CPU_CLK_SPEED as usize);
+    if let Some(disk_file) = disk_file {
+        insert_disk(&mut disk_card, disk_file);
+    }
+
+    let recorder = headless::wav_recorder(wav_file, settings::SAMPLE_RATE).unwrap();
+    let mut apple2 = roms.build(recorder);
+    if !roms.model.is_iie() {
+        apple2.insert_peripheral(&mut language_card, 0);
+    }
+    apple2.insert_peripheral(&mut disk_card, 6);
+    apple2.init();
+    if let Some(text) = type_text {
+        apple2.type_text(text);
+    }
+
+    let result = headless::run(&mut apple2, options);
+    headless::finish_wav(apple2.audio_mut());
+    let found = result.unwrap_or_else(|e| {
+        eprintln!("Failed to dump frame: {e}");
+        std::process::exit(1);
+    });
+    if let Some(text) = &options.until_text
+        && !found
+    {
+        eprintln!("Timed out waiting for {text:?}");
+        std::process::exit(1);
+    }
+}
+
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let mut headless = false;
+    let mut headless_options = headless::Options::default();
+    let mut disk_file = None;
+    let mut type_text = None;
+    let mut tape_file = None;
+    let mut tape_addr = 0x800;
+    let mut tape_fast = false;
+    let mut wav_file: Option<PathBuf> = None;
+    let mut videx_roms = None;
+    let mut roms = Roms {
+        model: Model::Apple2Plus,
+        fw_rom: None,
+        char_rom: None,
+    };
+    let mut tapes = Tapes {
+        input: None,
+        output: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().unwrap_or_else(|| usage());
+        match arg.as_str() {
+            "--model" => roms.model = parse_model(&value()),
+            "--rom" => {
+                roms.fw_rom = Some(std::fs::read(value()).unwrap());
+                roms.char_rom = Some(std::fs::read(value()).unwrap());
+            }
+            "--headless" => headless = true,
+            "--frames" => headless_options.frames = value().parse().unwrap_or_else(|_| usage()),
+            "--until-text" => headless_options.until_text = Some(value()),
+            "--dump-dir" => headless_options.dump_dir = Some(value().into()),
+            "--dump-every" => {
+                headless_options.dump_every = value().parse().unwrap_or_else(|_| usage())
+            }
+            "--ppm" => headless_options.ppm = true,
+            "--type" => type_text = Some(value()),
+            "--tape" => tape_file = Some(value()),
+            "--tape-addr" => {
+                tape_addr = u16::from_str_radix(&value(), 16).unwrap_or_else(|_| usage())
+            }
+            "--tape-fast" => tape_fast = true,
+            "--tape-out" => tapes.output = Some(value().into()),
+            "--wav" => wav_file = Some(value().into()),
+            "--videx" => {
+                let rom = load_rom::<{ videx::ROM_SIZE }>(&value());
+                let char_rom = load_rom::<{ videx::CHAR_ROM_SIZE }>(&value());
+                videx_roms = Some((rom, char_rom));
+            }
+            _ if arg.starts_with("--") || disk_file.is_some() => usage(),
+            _ => disk_file = Some(arg),
+        }
+    }
+    // The built-in ROMs only suit the II+
+    if roms.model != Model::Apple2Plus && roms.fw_rom.is_none() {
+        usage();
+    }
+    tapes.input = tape_file.map(|tape_file| load_tape(&tape_file, tape_addr));
+
+    if headless {
+        run_headless(
+            &roms,
+            disk_file.as_deref(),
+            type_text.as_deref(),
+            wav_file.as_deref(),
+            &headless_options,
+        );
+        return;
+    }
 
     // Initialize SDL
     let sdl_context = sdl2::init().unwrap();
     let mut event_pump = sdl_context.event_pump().unwrap();
 
+    // Use the first joystick (if any) as the Apple's joystick
+    let joystick_subsystem = sdl_context.joystick().unwrap();
+    let _joystick = joystick_subsystem.open(0).ok();
+
     // Initialize video
     let video_subsystem = sdl_context.video().unwrap();
+    let clipboard = video_subsystem.clipboard();
     let window = video_subsystem
         .window(
-            "Apple ][+",
+            window_title(roms.model),
             settings::DISP_WIDTH * settings::DISP_SCALE,
             settings::DISP_HEIGHT * settings::DISP_SCALE,
         )
@@ -183,18 +586,15 @@ fn main() {
         .unwrap();
 
     let canvas = window.into_canvas().build().unwrap();
-    let texture_creator = canvas.texture_creator();
-    let texture = texture_creator
-        .create_texture_streaming(
-            PixelFormatEnum::RGB888,
-            settings::DISP_WIDTH,
-            settings::DISP_HEIGHT,
-        )
-        .unwrap();
-    let mut display = SdlDisplay { canvas, texture };
+    let mut display = SdlDisplay::new(canvas);
+    let mut monitor = Monitor::default();
+    let mut video_switch = VideoSwitch::Annunciator;
+    let mut held_keys = HashSet::new();
 
     // Initialize audio
     let audio = SdlAudio::new(&sdl_context);
+    let audio_latency = audio.sample_rate as usize * AUDIO_LATENCY_MS / 1000;
+    let recorder = headless::wav_recorder(wav_file.as_deref(), audio.sample_rate).unwrap();
 
     // Initialize peripherals
     let mut language_card = language::LanguageCard::new();
@@ -206,37 +606,70 @@ fn main() {
 
     let mut disk_card = disk::ControllerCard::new(DISK2_ROM, settings::CPU_CLK_SPEED as usize);
 
+    let mut videx_card = videx_roms.map(|(rom, char_rom)| Videoterm::new(rom, char_rom));
+    let has_videx = videx_card.is_some();
+
     // Insert disk
-    if args.len() > 1 {
-        let disk_file = &args[1];
-        let buffer = std::fs::read(disk_file).unwrap();
-        let ext = std::path::Path::new(disk_file)
-            .extension()
-            .and_then(|e| e.to_str())
-            .unwrap_or("");
-        match ext {
-            "woz" => disk_card.insert_woz(&buffer),
-            "dsk" => disk_card.insert_dsk(&buffer),
-            "po" => disk_card.insert_po(&buffer),
-            _ => panic!("Unsupported disk format: .{}", ext),
-        }
+    if let Some(disk_file) = &disk_file {
+        insert_disk(&mut disk_card, disk_file);
     }
 
     // Initialize Apple 2
-    let mut apple2 = Apple2::new(FW_ROM, CHAR_ROM, audio);
-    apple2.insert_peripheral(&mut language_card, 0);
+    let mut apple2 = roms.build(TeeAudio(audio, recorder));
+    if !roms.model.is_iie() {
+        apple2.insert_peripheral(&mut language_card, 0);
+    }
     apple2.insert_peripheral(&mut serial_card, 2);
+    if let Some(videx_card) = &mut videx_card {
+        apple2.insert_peripheral(videx_card, VIDEX_SLOT);
+    }
     apple2.insert_peripheral(&mut disk_card, 6);
     apple2.init();
+    apple2.set_tape_fast_load(tape_fast);
+    if let Some(text) = &type_text {
+        apple2.type_text(text);
+    }
 
     // Main loop
-    while handle_input(&mut apple2, &mut event_pump) {
+    while handle_input(
+        &mut apple2,
+        &mut event_pump,
+        &clipboard,
+        &tapes,
+        &mut monitor,
+        &mut video_switch,
+        &mut held_keys,
+    ) {
         let start_time = Instant::now();
-        let frame = apple2.run_frame();
-        display.draw(frame);
-        let elapsed = Duration::from_micros(start_time.elapsed().as_micros() as u64);
+        apple2.run_frame();
 
-        // Sleep for rest of frame period
+        let show_videx = has_videx && video_switch.shows_videx(apple2.annunciators()[0]);
+        let videx_frame = if show_videx {
+            apple2.peripheral_video(VIDEX_SLOT)
+        } else {
+            None
+        };
+        match videx_frame {
+            Some(frame) => display.draw(frame.pixels, frame.width, frame.height),
+            None => {
+                let width = apple2.frame_width();
+                display.draw(apple2.frame(), width, settings::DISP_HEIGHT);
+            }
+        }
+
+        // Pace to the audio device if possible, so it never runs dry
+        if apple2.audio_buffered().is_some() {
+            while apple2
+                .audio_buffered()
+                .is_some_and(|buffered| buffered > audio_latency)
+            {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            continue;
+        }
+
+        // Otherwise sleep for rest of frame period
+        let elapsed = Duration::from_micros(start_time.elapsed().as_micros() as u64);
         let frame = Duration::from_micros(US_PER_FRAME);
         if frame > elapsed {
             std::thread::sleep(frame - elapsed);
@@ -244,4 +677,6 @@ fn main() {
             eprintln!("Missed frame!");
         }
     }
+
+    headless::finish_wav(&mut apple2.audio_mut().1);
 }